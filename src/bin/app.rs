@@ -1,9 +1,69 @@
 use std::error::Error;
+use std::path::PathBuf;
+
+use clap::Parser;
+
+use rust_gpt::config::{Config, PROFILE_ENV};
+use rust_gpt::encryption::Secret;
 use rust_gpt::tui::Application;
+use rust_gpt::tui::keymap::Keymap;
+use rust_gpt::workspace::Workspace;
+
+#[derive(Parser, Debug)]
+#[command(author, version, about = "Chat over the conversations of a workspace in the terminal", long_about = None)]
+struct Cli {
+    /// Conversation to open, or directory of the workspace. The workspace of the profile, and
+    /// the conversation opened last, if not given
+    path: Option<PathBuf>,
+
+    /// Profile of the configuration file to use, overrides RGPT_PROFILE
+    #[arg(long)]
+    profile: Option<String>,
+}
 
 #[tokio::main]
 pub async fn main() -> Result<(), Box<dyn Error>>{
+    let args = Cli::parse();
+
+    // Load the profile
+    let profile_name = args.profile.or_else(|| std::env::var(PROFILE_ENV).ok());
+    let config = Config::load().await?;
+    let profile = config.profile(profile_name.as_deref())?
+        .with_env()?;
+    let client = profile.create_client().await?;
+
+    // Find the workspace, and the conversation to open
+    let (workspace, conversation) = match args.path {
+        Some(path) if path.is_dir() => (Workspace::open(path).await?, None),
+        Some(path) => (Workspace::containing(&path).await?, Some(path)),
+        None => (Workspace::open(profile.workspace_dir()).await?, None),
+    };
+
+    // The passphrase of encrypted workspaces is asked before the terminal is taken over
+    let workspace = if workspace.is_encrypted().await? {
+        let secret = match Secret::from_env().await? {
+            Some(secret) => secret,
+            None => Secret::from_passphrase(&rpassword::prompt_password("Passphrase: ")?)?,
+        };
+        workspace.verify_secret(&secret).await?;
+        workspace.with_secret(Some(secret))
+    } else {
+        workspace
+    };
+
     // Create application and run
-    let mut app = Application::default();
+    let mut app = Application::build(workspace, client).await?
+        .with_parameters(profile.parameters_builder()?.build()?)
+        .with_keymap(Keymap::build(config.tui().keymap())?)
+        .with_theme(config.tui().theme().clone());
+    if let Some(system_prompt) = profile.system_prompt() {
+        app = app.with_system_prompt(system_prompt);
+    }
+
+    match conversation {
+        Some(path) => app.open(&path).await?,
+        None => app.open_last().await?,
+    }
+
     Ok(app.run().await?)
-}
\ No newline at end of file
+}
@@ -0,0 +1,264 @@
+use std::io::Write;
+
+use async_openai::types::Role;
+use rustyline::DefaultEditor;
+use rustyline::error::ReadlineError;
+use uuid::Uuid;
+
+use rust_gpt::RustGPTError;
+use rust_gpt::conversations::{ClientRef, CompletionModel, CompletionParametersBuilder, Conversation};
+
+use crate::{output, print_message_list, save_conversation};
+use crate::error::{CliError, CliResult, Context};
+use crate::output::OutputFormat;
+
+/// Module with tests related to the interactive chat
+#[cfg(test)]
+mod tests;
+
+const HELP: &str = "\
+Commands:
+  /regen            Creates a new response for the last query
+  /branch           Lists the branches of the conversation
+  /branch N         Continues the branch with the given index
+  /params           Shows the parameters used for completions
+  /params key=value Changes parameters (temperature, max_tokens, n, model)
+  /save             Saves the conversation
+  /help             Shows this help
+  /quit             Exits the chat, like Ctrl-D";
+
+/// Interactive session over a conversation, continuing from the message of the active branch
+struct ChatSession {
+    conversation: Conversation,
+    client: ClientRef,
+
+    /// Last message of the active branch
+    anchor: Uuid,
+
+    /// Format of the messages, responses are streamed as text if None
+    format: Option<OutputFormat>,
+}
+
+impl ChatSession {
+    /// Sends the query as a child of the anchor and streams the response. An unanswered query
+    /// in the anchor is kept as another branch.
+    async fn send(&mut self, query: String) -> CliResult {
+        let parent = self.conversation.query_parent(self.anchor)
+            .context("find the message to continue")?;
+        let &message = self.conversation.add_queries(parent, vec![query])
+            .context("create message")?
+            .first()
+            .expect("first message created");
+
+        self.anchor = message.id();
+        self.complete().await
+    }
+
+    /// Creates a new response for the last query of the active branch
+    async fn regenerate(&mut self) -> CliResult {
+        let last_query = self.conversation.get_message_list(Some(self.anchor))
+            .context("get message list")?
+            .into_iter()
+            .rev()
+            .find(|msg| *msg.role() == Role::User)
+            .map(|msg| msg.id());
+
+        let Some(last_query) = last_query else {
+            return Err(CliError::NotFound(String::from("query to answer")));
+        };
+
+        self.anchor = last_query;
+        self.complete().await
+    }
+
+    /// Completes the anchor, which must be a query, moving the anchor to the first response.
+    /// Responses are only printed once finished when an output format is given.
+    async fn complete(&mut self) -> CliResult {
+        let streamed = self.format.is_none();
+        let mut stdout = std::io::stdout();
+        let result = self.conversation.do_completion_streamed(self.anchor, self.client.clone(), None, |index, delta| {
+            // Only the first response is shown, the rest are stored as alternative branches
+            if streamed && index == 0 {
+                print!("{}", delta);
+                let _ = stdout.flush();
+            }
+        }).await;
+        if streamed {
+            println!();
+        }
+
+        let response_ids: Vec<Uuid> = match result.context("complete conversation") {
+            Ok(responses) => responses.into_iter().map(|msg| msg.id()).collect(),
+            Err(e) => {
+                // The query is kept, so it can be answered later
+                save_conversation(&self.conversation).await?;
+                return Err(e);
+            }
+        };
+
+        if streamed && response_ids.len() > 1 {
+            println!("({} more responses stored as alternative branches)", response_ids.len() - 1);
+        } else if !streamed {
+            let query = self.conversation.iter()
+                .find(|msg| msg.id() == self.anchor)
+                .expect("query message");
+            let responses: Vec<_> = self.conversation.iter()
+                .filter(|msg| response_ids.contains(&msg.id()))
+                .collect();
+            output::print(&output::Completion::build(&self.conversation, query, &responses), self.format);
+        }
+        if let Some(&response) = response_ids.first() {
+            self.anchor = response;
+        }
+
+        // Keep the conversation stored after every turn
+        save_conversation(&self.conversation).await
+    }
+
+    /// Lists the branches, or switches to the branch with the given index
+    fn branch(&mut self, argument: Option<&str>) -> CliResult {
+        let latest = self.conversation.get_latest_messages();
+
+        let Some(argument) = argument else {
+            if self.format.is_some() {
+                output::print(&output::BranchList::build(&self.conversation), self.format);
+                return Ok(());
+            }
+
+            for (i, msg) in latest.iter().enumerate() {
+                let marker = if msg.id() == self.anchor { "*" } else { " " };
+                let preview: String = msg.content().chars().take(60).collect();
+                println!("{} {}: {}", marker, i, preview.split_whitespace().collect::<Vec<_>>().join(" "));
+            }
+            return Ok(());
+        };
+
+        let Some(message) = argument.parse::<usize>().ok().and_then(|i| latest.get(i)) else {
+            return Err(CliError::NotFound(format!("branch with index {}", argument)));
+        };
+
+        self.anchor = message.id();
+        print_message_list(&self.conversation, &self.conversation.get_message_list(Some(self.anchor))
+            .context("get message list")?, self.format);
+        Ok(())
+    }
+
+    /// Shows the parameters, or changes the given ones
+    fn params(&mut self, assignments: &[&str]) -> CliResult {
+        let current = self.conversation.default_parameters().clone();
+        if assignments.is_empty() {
+            println!("temperature={} max_tokens={} n={} model={}",
+                     current.temperature(), current.max_tokens(), current.n(), current.model());
+            return Ok(());
+        }
+
+        let mut builder = CompletionParametersBuilder::from(&current);
+        for assignment in assignments {
+            let Some((key, value)) = assignment.split_once('=') else {
+                return Err(CliError::InvalidInput(format!("expected key=value, got {}", assignment)));
+            };
+
+            let applied = match key {
+                "temperature" => value.parse().map(|v| { builder.temperature(v); }).is_ok(),
+                "max_tokens" => value.parse().map(|v| { builder.max_tokens(v); }).is_ok(),
+                "n" => value.parse().map(|v| { builder.n(v); }).is_ok(),
+                "model" => value.parse::<CompletionModel>().map(|v| { builder.model(v); }).is_ok(),
+                _ => return Err(CliError::InvalidInput(format!("unknown parameter {}", key))),
+            };
+
+            if !applied {
+                return Err(CliError::InvalidInput(format!("invalid value for {}: {}", key, value)));
+            }
+        }
+
+        let parameters = builder.build()
+            .map_err(|e| CliError::InvalidInput(e.to_string()))?;
+        self.conversation.set_default_parameters(parameters);
+        Ok(())
+    }
+}
+
+/// Runs an interactive chat over the conversation, until the user quits
+///
+/// # Arguments
+///
+/// * `conversation`: Conversation to continue
+/// * `client`: Client for the completions
+/// * `branch`: Index of the branch to continue, the first one if None
+/// * `format`: Format of the messages and responses, the default one of each if None
+///
+/// returns: Result<(), CliError>
+pub async fn run(conversation: Conversation, client: ClientRef, branch: Option<usize>, format: Option<OutputFormat>)
+                 -> CliResult {
+    let anchor = {
+        let latest = conversation.get_latest_messages();
+        let Some(&message) = latest.get(branch.unwrap_or(0)) else {
+            return Err(CliError::NotFound(format!("conversation with index {}", branch.unwrap_or(0))));
+        };
+        message.id()
+    };
+
+    let mut session = ChatSession {
+        conversation,
+        client,
+        anchor,
+        format,
+    };
+
+    print_message_list(&session.conversation, &session.conversation.get_message_list(Some(session.anchor))
+        .context("get message list")?, format);
+    eprintln!("Type /help for the list of commands");
+
+    let mut editor = DefaultEditor::new()
+        .map_err(|e| CliError::Failed {
+            action: String::from("create line editor"),
+            source: Box::new(RustGPTError::Initialize(e.to_string())),
+        })?;
+    loop {
+        let line = match editor.readline("> ") {
+            Ok(line) => line,
+            // Ctrl-C only discards the line being written, Ctrl-D quits
+            Err(ReadlineError::Interrupted) => continue,
+            Err(ReadlineError::Eof) => break,
+            Err(e) => {
+                eprintln!("Couldn't read line: {}", e);
+                break;
+            }
+        };
+
+        let line = line.trim();
+        if line.is_empty() {
+            continue;
+        }
+        let _ = editor.add_history_entry(line);
+
+        let Some(command) = line.strip_prefix('/') else {
+            if let Err(e) = session.send(line.to_string()).await {
+                eprintln!("{}. Use /regen to try again", e);
+            }
+            continue;
+        };
+
+        let mut parts = command.split_whitespace();
+        let result = match parts.next().unwrap_or_default() {
+            "regen" => session.regenerate().await,
+            "branch" => session.branch(parts.next()),
+            "params" => session.params(&parts.collect::<Vec<_>>()),
+            "save" => save_conversation(&session.conversation).await
+                .map(|_| println!("Conversation saved at: {}", session.conversation.path().display())),
+            "help" => {
+                println!("{}", HELP);
+                Ok(())
+            }
+            "quit" | "exit" => break,
+            other => Err(CliError::InvalidInput(format!("unknown command /{}, type /help for the list of commands", other))),
+        };
+
+        // Errors don't end the session
+        if let Err(e) = result {
+            eprintln!("{}", e);
+        }
+    }
+
+    save_conversation(&session.conversation).await
+}
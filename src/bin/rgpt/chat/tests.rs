@@ -0,0 +1,44 @@
+use std::path::PathBuf;
+
+use rust_gpt::client::ClientOptions;
+use rust_gpt::client::ollama::OllamaOptions;
+
+use super::*;
+
+/// Creates a session whose completions fail, as nothing listens on the address of its client
+async fn failing_session(directory: &std::path::Path) -> ChatSession {
+    let parameters = CompletionParametersBuilder::default().build().expect("parameters");
+    let conversation = Conversation::build(parameters, directory.join("chat.yaml"), "System")
+        .expect("conversation");
+    let anchor = conversation.get_root_message().id();
+
+    let client = ClientOptions::default()
+        .with_api_base("http://127.0.0.1:9")
+        .with_ollama(OllamaOptions::default().with_default_model("llama2"))
+        .create_client().await
+        .expect("client");
+
+    ChatSession { conversation, client, anchor, format: None }
+}
+
+#[tokio::test]
+async fn send_after_failed_completion() {
+    let directory: PathBuf = std::env::temp_dir().join(format!("rgpt-chat-{}", Uuid::new_v4()));
+    std::fs::create_dir(&directory).expect("temp dir");
+    let mut session = failing_session(&directory).await;
+
+    // The failed query stays as the anchor
+    assert!(session.send(String::from("First")).await.is_err());
+    let first = session.anchor;
+    assert_eq!(session.conversation.iter().find(|msg| msg.id() == first).map(|msg| msg.content().as_str()),
+               Some("First"));
+
+    // .. and a new query becomes a sibling of it, instead of failing
+    assert!(matches!(session.send(String::from("Second")).await, Err(CliError::Failed { .. })));
+    let second = session.anchor;
+    assert_ne!(first, second);
+    let root = session.conversation.get_root_message().id();
+    assert_eq!(session.conversation.get_children(root).len(), 2);
+
+    let _ = std::fs::remove_dir_all(&directory);
+}
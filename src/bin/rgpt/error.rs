@@ -0,0 +1,134 @@
+use std::error::Error;
+use std::fmt::{Display, Formatter};
+use std::io;
+use std::process::ExitCode;
+
+use async_openai::error::OpenAIError;
+
+use rust_gpt::RustGPTError;
+
+/// Module with tests related to the errors of the commands
+#[cfg(test)]
+mod tests;
+
+/// Exit codes of rgpt, 1 is used for any other failure
+const EXIT_INVALID_INPUT: u8 = 2;
+const EXIT_NOT_FOUND: u8 = 3;
+const EXIT_API_ERROR: u8 = 4;
+const EXIT_RATE_LIMITED: u8 = 5;
+const EXIT_IO: u8 = 6;
+
+/// Failure of a command
+#[derive(Debug)]
+pub enum CliError {
+    /// An operation failed, the action describes what was being done
+    Failed { action: String, source: Box<RustGPTError> },
+
+    /// Something requested by the user doesn't exist
+    NotFound(String),
+
+    /// The arguments given by the user can't be used
+    InvalidInput(String),
+}
+
+pub type CliResult<T = ()> = Result<T, CliError>;
+
+impl Display for CliError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            CliError::Failed { action, source } => write!(f, "Couldn't {}: {}", action, describe(source)),
+            CliError::NotFound(what) => write!(f, "Not found: {}", what),
+            CliError::InvalidInput(reason) => write!(f, "Invalid input: {}", reason),
+        }
+    }
+}
+
+impl CliError {
+    /// Returns the exit code of the process for this error
+    pub fn exit_code(&self) -> ExitCode {
+        let code = match self {
+            CliError::NotFound(_) => EXIT_NOT_FOUND,
+            CliError::InvalidInput(_) => EXIT_INVALID_INPUT,
+            CliError::Failed { source, .. } => match source.as_ref() {
+                RustGPTError::DirectoryIO(e) if e.kind() == io::ErrorKind::NotFound => EXIT_NOT_FOUND,
+                RustGPTError::ConversationNotFound(_) | RustGPTError::MessageNotPartOfConversation => EXIT_NOT_FOUND,
+                RustGPTError::DirectoryIO(_) | RustGPTError::Initialize(_) | RustGPTError::WriteConversation(_) => EXIT_IO,
+                RustGPTError::ClientError(e) if is_rate_limit(e) => EXIT_RATE_LIMITED,
+                RustGPTError::ClientError(_) | RustGPTError::ResponseError(_) => EXIT_API_ERROR,
+                RustGPTError::Serialization(_) | RustGPTError::JsonSerialization(_) | RustGPTError::BadMessage(_)
+                | RustGPTError::NoQueryGiven | RustGPTError::InvalidMessageRole | RustGPTError::UnknownModel(_)
+                | RustGPTError::Import(_) | RustGPTError::Encryption(_) | RustGPTError::MissingSecret(_)
+                | RustGPTError::InvalidPattern(_) | RustGPTError::OutdatedIndex | RustGPTError::UnknownProfile(_)
+                | RustGPTError::InvalidSetting(_) => EXIT_INVALID_INPUT,
+                RustGPTError::NoClientSpecified => 1,
+            },
+        };
+
+        ExitCode::from(code)
+    }
+
+    /// Returns the message for the user, along with the chain of causes when verbose
+    pub fn report(&self, verbose: bool) -> String {
+        let mut report = self.to_string();
+        if !verbose {
+            return report;
+        }
+
+        if let CliError::Failed { source, .. } = self {
+            let mut cause: Option<&dyn Error> = Some(source.as_ref());
+            while let Some(error) = cause {
+                report.push_str(&format!("\n  caused by: {}", error));
+                cause = error.source();
+            }
+            report.push_str(&format!("\n  details: {:?}", source));
+        }
+
+        report
+    }
+}
+
+/// Returns a short explanation of the error, without the names of the internal types
+fn describe(error: &RustGPTError) -> String {
+    match error {
+        RustGPTError::DirectoryIO(e) => e.to_string(),
+        RustGPTError::Serialization(e) => format!("invalid conversation file ({})", e),
+        RustGPTError::JsonSerialization(e) => format!("invalid JSON data ({})", e),
+        RustGPTError::ClientError(OpenAIError::ApiError(e)) if is_rate_limit_type(e.r#type.as_deref(), &e.code) =>
+            format!("rate limit reached, try again later ({})", e.message),
+        RustGPTError::ClientError(OpenAIError::ApiError(e)) => format!("the API returned an error: {}", e.message),
+        RustGPTError::ClientError(OpenAIError::Reqwest(e)) => format!("couldn't reach the API ({})", e),
+        RustGPTError::ClientError(e) => e.to_string(),
+        RustGPTError::InvalidPattern(e) => format!("invalid search pattern ({})", e),
+        e => e.to_string(),
+    }
+}
+
+fn is_rate_limit(error: &OpenAIError) -> bool {
+    match error {
+        OpenAIError::ApiError(e) => is_rate_limit_type(e.r#type.as_deref(), &e.code),
+        _ => false,
+    }
+}
+
+/// Checks the type and code of an API error for the ones used when rate limited
+fn is_rate_limit_type(error_type: Option<&str>, code: &Option<serde_json::Value>) -> bool {
+    matches!(error_type, Some("requests") | Some("tokens"))
+        || code.as_ref().and_then(|code| code.as_str()) == Some("rate_limit_exceeded")
+}
+
+/// Adds a description of the action to the errors of a result
+pub trait Context<T> {
+    fn context(self, action: &str) -> CliResult<T>;
+}
+
+impl<T, E> Context<T> for Result<T, E>
+where
+    E: Into<RustGPTError>
+{
+    fn context(self, action: &str) -> CliResult<T> {
+        self.map_err(|e| CliError::Failed {
+            action: action.to_string(),
+            source: Box::new(e.into()),
+        })
+    }
+}
@@ -0,0 +1,50 @@
+use async_openai::error::ApiError;
+
+use super::*;
+
+fn failed(source: RustGPTError) -> CliError {
+    Err::<(), _>(source).context("test").expect_err("error")
+}
+
+fn api_error(r#type: Option<&str>, code: Option<&str>) -> RustGPTError {
+    RustGPTError::ClientError(OpenAIError::ApiError(ApiError {
+        message: String::from("API error"),
+        r#type: r#type.map(str::to_string),
+        param: None,
+        code: code.map(serde_json::Value::from),
+    }))
+}
+
+#[test]
+fn exit_codes() {
+    assert_eq!(CliError::InvalidInput(String::from("input")).exit_code(), ExitCode::from(EXIT_INVALID_INPUT));
+    assert_eq!(CliError::NotFound(String::from("conversation")).exit_code(), ExitCode::from(EXIT_NOT_FOUND));
+
+    // Failures get the code of their source
+    let missing = io::Error::new(io::ErrorKind::NotFound, "missing");
+    assert_eq!(failed(missing.into()).exit_code(), ExitCode::from(EXIT_NOT_FOUND));
+    assert_eq!(failed(RustGPTError::MessageNotPartOfConversation).exit_code(), ExitCode::from(EXIT_NOT_FOUND));
+    let denied = io::Error::new(io::ErrorKind::PermissionDenied, "denied");
+    assert_eq!(failed(denied.into()).exit_code(), ExitCode::from(EXIT_IO));
+    assert_eq!(failed(RustGPTError::WriteConversation(String::from("full"))).exit_code(), ExitCode::from(EXIT_IO));
+    assert_eq!(failed(api_error(Some("server_error"), None)).exit_code(), ExitCode::from(EXIT_API_ERROR));
+    assert_eq!(failed(RustGPTError::ResponseError(String::from("empty"))).exit_code(),
+               ExitCode::from(EXIT_API_ERROR));
+    assert_eq!(failed(RustGPTError::InvalidMessageRole).exit_code(), ExitCode::from(EXIT_INVALID_INPUT));
+    assert_eq!(failed(RustGPTError::NoClientSpecified).exit_code(), ExitCode::from(1));
+}
+
+#[test]
+fn rate_limits() {
+    // Detected by the type of the error or by its code
+    for error in [api_error(Some("requests"), None), api_error(Some("tokens"), None),
+                  api_error(None, Some("rate_limit_exceeded"))] {
+        let error = failed(error);
+        assert_eq!(error.exit_code(), ExitCode::from(EXIT_RATE_LIMITED));
+        assert!(error.to_string().contains("rate limit reached"));
+    }
+
+    let error = failed(api_error(Some("invalid_request_error"), Some("context_length_exceeded")));
+    assert_eq!(error.exit_code(), ExitCode::from(EXIT_API_ERROR));
+    assert_eq!(error.to_string(), "Couldn't test: the API returned an error: API error");
+}
@@ -0,0 +1,912 @@
+use std::path::{Path, PathBuf};
+use std::process::ExitCode;
+use std::time::Duration;
+
+use async_openai::types::Role;
+use chrono::{NaiveDate, TimeZone, Utc};
+use clap::{Args, Parser, Subcommand, ValueEnum};
+use uuid::Uuid;
+
+use rust_gpt::client::{ApiKeySource, ClientOptions};
+use rust_gpt::config::{Config, PROFILE_ENV, Profile};
+use rust_gpt::conversations::{CompletionModel, Conversation, Message};
+use rust_gpt::import::DEFAULT_SYSTEM_MESSAGE;
+use rust_gpt::encryption::Secret;
+use rust_gpt::export::{self, ExportScope};
+use rust_gpt::import::{chatgpt, finetune};
+use rust_gpt::search::{self, SearchQuery, SearchReport};
+use rust_gpt::search::semantic::{self, DEFAULT_EMBEDDING_MODEL, HashingEmbeddings, OpenAIEmbeddings};
+use rust_gpt::workspace::Workspace;
+use rust_gpt::RustGPTError;
+
+use crate::error::{CliError, CliResult, Context};
+use crate::output::OutputFormat;
+
+/// Interactive chat over a conversation
+mod chat;
+
+/// Composition of queries from stdin and files
+mod query;
+
+/// Output of the commands in several formats
+mod output;
+
+/// Errors of the commands and their exit codes
+mod error;
+
+#[derive(Parser, Debug)]
+#[command(author, version, about, long_about = None)]
+#[command(propagate_version = true)]
+struct Cli {
+    #[command(subcommand)]
+    command: Commands,
+
+    /// Format of the output, each command has its own default
+    #[arg(long, global = true, value_enum)]
+    output: Option<OutputFormat>,
+
+    /// Shows the full chain of causes of errors
+    #[arg(short, long, global = true)]
+    verbose: bool,
+
+    /// Profile of the configuration file to use, overrides RGPT_PROFILE
+    #[arg(long, global = true)]
+    profile: Option<String>,
+
+    #[command(flatten)]
+    client: ClientArgs,
+}
+
+/// Settings of the connection to the API, overriding the ones of the profile
+#[derive(Args, Debug)]
+#[command(next_help_heading = "Connection")]
+struct ClientArgs {
+    /// Base URL of an OpenAI compatible API, e.g. http://localhost:8080/v1
+    #[arg(long, global = true)]
+    api_base: Option<String>,
+
+    /// Environment variable with the API key
+    #[arg(long, global = true, conflicts_with_all = ["api_key_file", "no_api_key"])]
+    api_key_env: Option<String>,
+
+    /// File with the API key
+    #[arg(long, global = true, conflicts_with = "no_api_key")]
+    api_key_file: Option<PathBuf>,
+
+    /// Doesn't send an API key, e.g. for local servers
+    #[arg(long, global = true)]
+    no_api_key: bool,
+
+    #[arg(long, global = true)]
+    org_id: Option<String>,
+
+    /// Header sent with every request, as NAME=VALUE
+    #[arg(long = "header", global = true, value_parser = parse_header)]
+    headers: Vec<(String, String)>,
+
+    /// Maximum duration of a request, in seconds
+    #[arg(long, global = true)]
+    timeout: Option<u64>,
+
+    /// Maximum duration for connecting to the API, in seconds
+    #[arg(long, global = true)]
+    connect_timeout: Option<u64>,
+
+    /// Proxy for every request, e.g. http://proxy:3128
+    #[arg(long, global = true)]
+    proxy: Option<String>,
+
+    /// Uses Azure OpenAI with the given api-version
+    #[arg(long, global = true)]
+    azure_api_version: Option<String>,
+
+    /// Azure OpenAI deployment used for a model, as MODEL=DEPLOYMENT
+    #[arg(long = "azure-deployment", global = true, value_parser = parse_deployment)]
+    azure_deployments: Vec<(String, String)>,
+
+    /// Uses the native API of a local Ollama server, at http://localhost:11434 unless --api-base is given.
+    /// Only Ollama is supported natively: for llama.cpp, use its OpenAI compatible server with --api-base
+    #[arg(long, global = true)]
+    ollama: bool,
+
+    /// Local model used by Ollama for a model, as MODEL=LOCAL_MODEL
+    #[arg(long = "ollama-model", global = true, value_parser = parse_local_model)]
+    ollama_models: Vec<(String, String)>,
+
+    /// Local model used by Ollama for every model without its own one
+    #[arg(long, global = true)]
+    ollama_default_model: Option<String>,
+}
+
+impl ClientArgs {
+    /// Replaces the given options with the ones set in the command line
+    fn apply(self, mut options: ClientOptions) -> ClientOptions {
+        if let Some(api_base) = self.api_base {
+            options = options.with_api_base(&api_base);
+        }
+        if let Some(name) = self.api_key_env {
+            options = options.with_api_key(ApiKeySource::Env(name));
+        }
+        if let Some(path) = self.api_key_file {
+            options = options.with_api_key(ApiKeySource::File(path));
+        }
+        if self.no_api_key {
+            options = options.with_api_key(ApiKeySource::None);
+        }
+        if let Some(org_id) = self.org_id {
+            options = options.with_org_id(&org_id);
+        }
+        for (name, value) in self.headers {
+            options = options.with_header(&name, &value);
+        }
+        if let Some(timeout) = self.timeout {
+            options = options.with_timeout(Duration::from_secs(timeout));
+        }
+        if let Some(timeout) = self.connect_timeout {
+            options = options.with_connect_timeout(Duration::from_secs(timeout));
+        }
+        if let Some(proxy) = self.proxy {
+            options = options.with_proxy(&proxy);
+        }
+        if self.azure_api_version.is_some() || !self.azure_deployments.is_empty() {
+            let mut azure = options.azure().cloned().unwrap_or_default();
+            if let Some(api_version) = self.azure_api_version {
+                azure = azure.with_api_version(&api_version);
+            }
+            for (model, deployment) in self.azure_deployments {
+                azure = azure.with_deployment(&model, &deployment);
+            }
+            options = options.with_azure(azure);
+        }
+        if self.ollama || !self.ollama_models.is_empty() || self.ollama_default_model.is_some() {
+            let mut ollama = options.ollama().cloned().unwrap_or_default();
+            for (model, local_model) in self.ollama_models {
+                ollama = ollama.with_model(&model, &local_model);
+            }
+            if let Some(local_model) = self.ollama_default_model {
+                ollama = ollama.with_default_model(&local_model);
+            }
+            options = options.with_ollama(ollama);
+        }
+
+        options
+    }
+}
+
+/// Parses a header given in the command line as NAME=VALUE
+fn parse_header(value: &str) -> Result<(String, String), String> {
+    value.split_once('=')
+        .map(|(name, value)| (name.trim().to_string(), value.trim().to_string()))
+        .ok_or_else(|| format!("expected NAME=VALUE, got {}", value))
+}
+
+/// Parses an Azure deployment given in the command line as MODEL=DEPLOYMENT
+fn parse_deployment(value: &str) -> Result<(String, String), String> {
+    value.split_once('=')
+        .map(|(model, deployment)| (model.trim().to_string(), deployment.trim().to_string()))
+        .ok_or_else(|| format!("expected MODEL=DEPLOYMENT, got {}", value))
+}
+
+/// Parses the local model of Ollama given in the command line as MODEL=LOCAL_MODEL
+fn parse_local_model(value: &str) -> Result<(String, String), String> {
+    value.split_once('=')
+        .map(|(model, local_model)| (model.trim().to_string(), local_model.trim().to_string()))
+        .ok_or_else(|| format!("expected MODEL=LOCAL_MODEL, got {}", value))
+}
+
+#[derive(Subcommand, Debug)]
+enum Commands {
+    // Creates a new conversation
+    New(NewConversation),
+    Complete(CompleteConversation),
+    Chat(ChatConversation),
+    Show(ShowConversation),
+    Export(ExportConversation),
+    #[command(subcommand)]
+    Import(ImportConversations),
+    Prefer(PreferConversation),
+    #[command(subcommand)]
+    Crypt(CryptWorkspace),
+    Search(SearchConversations),
+    Reindex(ReindexWorkspace),
+    Delete(DeleteConversation),
+
+    /// Lists the models available in the API, or in the local server
+    Models(ListModels),
+}
+
+#[derive(Args, Debug)]
+struct NewConversation {
+    path: PathBuf,
+    name: String,
+
+    /// System message of the conversation, the one of the profile if not given
+    system_query: Option<String>,
+
+    #[arg(short = 'n', long)]
+    max_tokens: Option<u16>,
+
+    #[arg(short, long)]
+    temperature: Option<f32>,
+
+    #[arg(short, long)]
+    model: Option<CompletionModel>,
+}
+
+#[derive(Args, Debug)]
+struct CompleteConversation {
+    path: PathBuf,
+
+    /// Text of the query, use - for reading it from stdin
+    #[arg(required_unless_present_any = ["prefix", "file"])]
+    query: Option<String>,
+
+    /// Files whose content is attached to the query
+    #[arg(short, long)]
+    file: Vec<PathBuf>,
+
+    /// Text placed before the query, which is read from stdin if it isn't given
+    #[arg(short, long)]
+    prefix: Option<String>,
+}
+
+#[derive(Args, Debug)]
+struct ChatConversation {
+    path: PathBuf,
+
+    /// Index of the branch to continue
+    #[arg(short = 'n', long)]
+    conversation_index: Option<usize>,
+}
+
+#[derive(Args, Debug)]
+struct ShowConversation {
+    path: PathBuf,
+
+    #[arg(short = 'n', long, conflicts_with = "message")]
+    conversation_index: Option<u16>,
+
+    /// Shows the branch that goes through the message with the given id
+    #[arg(short, long)]
+    message: Option<Uuid>,
+}
+
+#[derive(Args, Debug)]
+struct SearchConversations {
+    /// Text to find within the messages
+    pattern: String,
+
+    /// Directory with the conversations, the one of the profile if not given
+    #[arg(short, long)]
+    workspace: Option<PathBuf>,
+
+    /// Uses the pattern as a regular expression
+    #[arg(short, long)]
+    regex: bool,
+
+    /// Makes the text search case sensitive
+    #[arg(short, long, conflicts_with = "regex")]
+    case_sensitive: bool,
+
+    /// Only finds messages with the given role
+    #[arg(long, value_enum)]
+    role: Option<RoleFilter>,
+
+    /// Only finds messages created on or after the given date (YYYY-MM-DD)
+    #[arg(long, value_parser = parse_date)]
+    since: Option<NaiveDate>,
+
+    /// Only finds messages created on or before the given date (YYYY-MM-DD)
+    #[arg(long, value_parser = parse_date)]
+    until: Option<NaiveDate>,
+
+    /// Shows the branch of the result with the given index instead of the list of results
+    #[arg(short, long)]
+    open: Option<usize>,
+
+    /// Ranks the messages by the given keywords using the search index of the workspace
+    #[arg(long, conflicts_with_all = ["regex", "case_sensitive", "role", "since", "until"])]
+    ranked: bool,
+
+    /// Finds the messages with the most similar meaning, using embeddings
+    #[arg(long, conflicts_with_all = ["ranked", "regex", "case_sensitive", "role", "since", "until"])]
+    semantic: bool,
+
+    /// Backend used for the embeddings of a semantic search
+    #[arg(long, value_enum, default_value_t = EmbeddingsBackend::Openai)]
+    embeddings: EmbeddingsBackend,
+
+    /// Maximum number of results of a ranked or semantic search
+    #[arg(short, long, default_value_t = 20)]
+    limit: usize,
+}
+
+#[derive(ValueEnum, Copy, Clone, Debug)]
+enum EmbeddingsBackend {
+    /// OpenAI embeddings API
+    Openai,
+
+    /// Offline stand-in that only matches shared words
+    Local,
+}
+
+#[derive(Args, Debug)]
+struct ReindexWorkspace {
+    /// Directory with the conversations, the one of the profile if not given
+    #[arg(short, long)]
+    workspace: Option<PathBuf>,
+}
+
+#[derive(Args, Debug)]
+struct DeleteConversation {
+    path: PathBuf,
+}
+
+#[derive(Args, Debug)]
+struct ListModels {}
+
+#[derive(ValueEnum, Copy, Clone, Debug)]
+enum RoleFilter {
+    System,
+    User,
+    Assistant,
+}
+
+impl From<RoleFilter> for Role {
+    fn from(value: RoleFilter) -> Self {
+        match value {
+            RoleFilter::System => Role::System,
+            RoleFilter::User => Role::User,
+            RoleFilter::Assistant => Role::Assistant,
+        }
+    }
+}
+
+/// Parses a date given in the command line
+fn parse_date(value: &str) -> Result<NaiveDate, String> {
+    NaiveDate::parse_from_str(value, "%Y-%m-%d")
+        .map_err(|e| e.to_string())
+}
+
+#[derive(Args, Debug)]
+struct ExportConversation {
+    path: PathBuf,
+
+    #[arg(short, long, value_enum, default_value_t = ExportFormat::Md)]
+    format: ExportFormat,
+
+    /// Index of the conversation to export (as listed by `show`)
+    #[arg(short = 'n', long, conflicts_with = "all")]
+    conversation_index: Option<u16>,
+
+    /// Exports the full tree, including every alternate branch
+    #[arg(short, long)]
+    all: bool,
+
+    /// Only exports the branches marked as preferred (fine-tuning format only)
+    #[arg(short, long)]
+    preferred: bool,
+
+    /// File to write the export to, instead of stdout
+    #[arg(short = 'o', long)]
+    output_file: Option<PathBuf>,
+}
+
+#[derive(Subcommand, Debug)]
+enum ImportConversations {
+    /// Imports the `conversations.json` file of a ChatGPT data export
+    #[command(name = "chatgpt")]
+    ChatGPT(ImportFile),
+
+    /// Imports a JSONL file in the OpenAI fine-tuning format
+    Finetune(ImportFile),
+}
+
+#[derive(Args, Debug)]
+struct ImportFile {
+    file: PathBuf,
+
+    /// Directory where the imported conversations are stored, the one of the profile if not given
+    #[arg(short, long)]
+    workspace: Option<PathBuf>,
+}
+
+#[derive(ValueEnum, Copy, Clone, Debug)]
+enum ExportFormat {
+    Md,
+    Html,
+    Finetune,
+}
+
+#[derive(Args, Debug)]
+struct PreferConversation {
+    path: PathBuf,
+
+    /// Index of the conversation whose last message is marked (as listed by `show`)
+    conversation_index: u16,
+
+    /// Removes the mark instead of adding it
+    #[arg(short, long)]
+    unset: bool,
+}
+
+#[derive(Subcommand, Debug)]
+enum CryptWorkspace {
+    /// Encrypts every conversation stored as plain text
+    Encrypt(CryptParams),
+
+    /// Decrypts every conversation, storing them as plain text
+    Decrypt(CryptParams),
+
+    /// Encrypts every conversation with a new secret
+    Rekey(CryptParams),
+}
+
+#[derive(Args, Debug)]
+struct CryptParams {
+    /// Directory with the conversations, the one of the profile if not given
+    #[arg(short, long)]
+    workspace: Option<PathBuf>,
+
+    /// Keyfile with the current secret, instead of a passphrase
+    #[arg(short, long)]
+    keyfile: Option<PathBuf>,
+
+    /// Keyfile with the new secret when rekeying, instead of a passphrase
+    #[arg(long)]
+    new_keyfile: Option<PathBuf>,
+}
+
+/// Reads a secret from the keyfile if given, otherwise from the environment or asking for a
+/// passphrase. New passphrases are asked twice, as a typo would make the conversations
+/// unrecoverable.
+async fn read_secret(keyfile: Option<PathBuf>, prompt: &str, use_env: bool, confirm: bool) -> CliResult<Secret> {
+    if let Some(keyfile) = keyfile {
+        return Secret::from_keyfile(keyfile).await
+            .context("read keyfile");
+    }
+
+    if use_env {
+        if let Some(secret) = Secret::from_env().await.context("read secret from environment")? {
+            return Ok(secret);
+        }
+    }
+
+    let passphrase = rpassword::prompt_password(prompt)
+        .context("read passphrase")?;
+    if confirm {
+        let repeated = rpassword::prompt_password("Repeat the passphrase: ")
+            .context("read passphrase")?;
+        if repeated != passphrase {
+            return Err(CliError::InvalidInput(String::from("the passphrases don't match")));
+        }
+    }
+
+    Secret::from_passphrase(&passphrase)
+        .context("create secret")
+}
+
+/// Loads a conversation, asking for the passphrase if it's encrypted and the environment doesn't
+/// have its secret
+async fn load_conversation(path: &Path) -> CliResult<Conversation> {
+    match Conversation::load(path).await {
+        Err(RustGPTError::MissingSecret(_)) => {
+            let secret = read_secret(None, "Passphrase: ", false, false).await?;
+            Conversation::load_with_secret(path, Some(&secret)).await
+                .context("load conversation")
+        }
+        result => result.context("load conversation"),
+    }
+}
+
+/// Returns the secret of the workspace if it has encrypted conversations, reading it from the
+/// environment or asking for the passphrase
+async fn workspace_secret(workspace: &Workspace) -> CliResult<Option<Secret>> {
+    if !workspace.is_encrypted().await.context("read workspace")? {
+        return Ok(None);
+    }
+
+    let secret = read_secret(None, "Passphrase: ", true, false).await?;
+    workspace.verify_secret(&secret).await
+        .context("check passphrase")?;
+
+    Ok(Some(secret))
+}
+
+/// Loads the selected profile of the configuration file, with the environment variables applied
+async fn load_profile(name: Option<&str>) -> CliResult<Profile> {
+    let config = Config::load().await
+        .context("load configuration")?;
+
+    let name = name.map(str::to_string)
+        .or_else(|| std::env::var(PROFILE_ENV).ok());
+    config.profile(name.as_deref())
+        .and_then(|profile| profile.with_env())
+        .context("load profile")
+}
+
+/// Saves the conversation, keeping the search index of its workspace up to date
+async fn save_conversation(conversation: &Conversation) -> CliResult {
+    let workspace = Workspace::containing(conversation.path()).await
+        .context("open workspace")?;
+    workspace.save_conversation(conversation).await
+        .context("save conversation")
+}
+
+/// Creates a new conversation with the given parameters
+///
+/// # Arguments
+///
+/// * `conversation_params`:
+///
+/// returns: ()
+async fn new_conversation(conversation_params: NewConversation, profile: &Profile, format: Option<OutputFormat>)
+                          -> CliResult {
+    // Create parameters, the given ones take precedence over the ones of the profile
+    let mut builder = profile.parameters_builder()
+        .context("read parameters of the profile")?;
+    if let Some(temperature) = conversation_params.temperature {
+        builder.temperature(temperature);
+    }
+    if let Some(max_tokens) = conversation_params.max_tokens {
+        builder.max_tokens(max_tokens);
+    }
+    if let Some(model) = conversation_params.model {
+        builder.model(model);
+    }
+    let parameters = builder.build()
+        .map_err(|e| CliError::InvalidInput(e.to_string()))?;
+
+    // Create conversation
+    let system_query = conversation_params.system_query
+        .or_else(|| profile.system_prompt().map(str::to_string))
+        .unwrap_or_else(|| DEFAULT_SYSTEM_MESSAGE.to_string());
+    let mut conversation = Conversation::build(
+        parameters,
+        conversation_params.path,
+        system_query.as_str(),
+    ).context("build conversation")?;
+    conversation.set_name(conversation_params.name);
+
+    // Conversations of encrypted workspaces are encrypted too
+    let workspace = Workspace::containing(conversation.path()).await
+        .context("open workspace")?;
+    conversation.set_secret(workspace_secret(&workspace).await?);
+
+    // Save the conversation
+    save_conversation(&conversation).await?;
+
+    output::print(&output::CreatedConversation::build(&conversation), format);
+    Ok(())
+}
+
+/// Tries to complete a conversation from the disk
+///
+/// # Arguments
+///
+/// * `params`:
+///
+/// returns: ()
+async fn complete_conversation(params: CompleteConversation, profile: &Profile, format: Option<OutputFormat>) -> CliResult {
+    // Load the conversation
+    let mut conversation = load_conversation(&params.path).await?;
+
+    // Get main conversation
+    let &latest = conversation.get_latest_messages().first()
+        .expect("conversations always have a root message");
+
+    // Add the query
+    let query = query::compose(params.query, params.prefix, &params.file).await
+        .context("read query")?;
+    if query.is_empty() {
+        return Err(CliError::InvalidInput(String::from("the query is empty")));
+    }
+
+    let &messages = conversation.add_queries(latest.id(), vec![query])
+        .context("create message")?
+        .first()
+        .expect("first message created");
+
+    // Create client
+    let client = profile.create_client().await
+        .context("create client")?;
+
+    // Complete the conversation
+    let message_id = messages.id();
+    let response_ids: Vec<_> = conversation.do_completion(message_id, client, None)
+        .await
+        .context("complete conversation")?
+        .into_iter()
+        .map(|msg| msg.id())
+        .collect();
+
+    // Save the conversation
+    save_conversation(&conversation).await?;
+
+    // Show to the user
+    let query = conversation.iter()
+        .find(|msg| msg.id() == message_id)
+        .expect("query message");
+    let responses: Vec<_> = conversation.iter()
+        .filter(|msg| response_ids.contains(&msg.id()))
+        .collect();
+    output::print(&output::Completion::build(&conversation, query, &responses), format);
+    Ok(())
+}
+
+/// Starts an interactive chat over a conversation from the disk
+async fn chat_conversation(params: ChatConversation, profile: &Profile, format: Option<OutputFormat>) -> CliResult {
+    let conversation = load_conversation(&params.path).await?;
+
+    let client = profile.create_client().await
+        .context("create client")?;
+
+    chat::run(conversation, client, params.conversation_index, format).await
+}
+
+/// Prints a chain of messages of the conversation
+fn print_message_list(conversation: &Conversation, message_list: &[&Message], format: Option<OutputFormat>) {
+    output::print(&output::MessageList::build(conversation, message_list), format);
+}
+
+/// Shows a conversation with the given index
+async fn show_conversation(params: ShowConversation, format: Option<OutputFormat>) -> CliResult {
+    // Load the conversation
+    let conversation = load_conversation(&params.path).await?;
+
+    // Get all the latest messages
+    let latest = conversation.get_latest_messages();
+
+    if let Some(message_id) = params.message {
+        // Get conversation anchored by the given message
+        let message_list = conversation.get_message_list(Some(message_id))
+            .context("get message list")?;
+        print_message_list(&conversation, &message_list, format);
+    } else if let Some(index) = params.conversation_index {
+        let Some(message) = latest.get(index as usize) else {
+            return Err(CliError::NotFound(format!("conversation with index {}", index)));
+        };
+
+        // Get conversation anchored by the given message
+        let message_list = conversation.get_message_list(Some(message.id()))
+            .context("get message list")?;
+        print_message_list(&conversation, &message_list, format);
+    } else {
+        // Show all of the latest messages
+        output::print(&output::BranchList::build(&conversation), format);
+    }
+    Ok(())
+}
+
+/// Exports a conversation to another format
+async fn export_conversation(params: ExportConversation, format: Option<OutputFormat>) -> CliResult {
+    // Load the conversation
+    let conversation = load_conversation(&params.path).await?;
+
+    // Select what to export
+    let scope = if params.all {
+        ExportScope::Tree
+    } else if let Some(index) = params.conversation_index {
+        let latest = conversation.get_latest_messages();
+        let Some(message) = latest.get(index as usize) else {
+            return Err(CliError::NotFound(format!("conversation with index {}", index)));
+        };
+
+        ExportScope::Branch(Some(message.id()))
+    } else {
+        ExportScope::Branch(None)
+    };
+
+    let data = match params.format {
+        ExportFormat::Md => export::to_markdown(&conversation, scope),
+        ExportFormat::Html => export::to_html(&conversation, scope),
+        ExportFormat::Finetune => export::to_finetune_jsonl(&conversation, scope, params.preferred),
+    }.context("export conversation")?;
+
+    match params.output_file {
+        Some(path) => {
+            tokio::fs::write(&path, data).await
+                .context("write export")?;
+            output::print(&output::Exported::build(&path), format);
+        }
+        None => output::print(&output::ExportedContent::build(&conversation, data), format),
+    }
+    Ok(())
+}
+
+/// Imports conversations from other applications into a workspace
+async fn import_conversations(params: ImportConversations, profile: &Profile, format: Option<OutputFormat>) -> CliResult {
+    let (ImportConversations::ChatGPT(file) | ImportConversations::Finetune(file)) = &params;
+    let workspace = Workspace::open(file.workspace.clone().unwrap_or_else(|| profile.workspace_dir())).await
+        .context("open workspace")?;
+    let secret = workspace_secret(&workspace).await?;
+    let workspace = workspace.with_secret(secret);
+
+    let report = match params {
+        ImportConversations::ChatGPT(params) => chatgpt::import_file(&workspace, params.file).await,
+        ImportConversations::Finetune(params) => finetune::import_file(&workspace, params.file).await,
+    }.context("import conversations")?;
+
+    output::print(&output::Imported::from(&report), format);
+    Ok(())
+}
+
+/// Marks the last message of a conversation as preferred
+async fn prefer_conversation(params: PreferConversation, format: Option<OutputFormat>) -> CliResult {
+    let mut conversation = load_conversation(&params.path).await?;
+
+    let latest = conversation.get_latest_messages();
+    let Some(message) = latest.get(params.conversation_index as usize) else {
+        return Err(CliError::NotFound(format!("conversation with index {}", params.conversation_index)));
+    };
+
+    let message_id = message.id();
+    conversation.set_preferred(message_id, !params.unset)
+        .context("mark message")?;
+
+    save_conversation(&conversation).await?;
+
+    let message = conversation.iter()
+        .find(|msg| msg.id() == message_id)
+        .expect("marked message");
+    output::print(&output::PreferredMessage::build(&conversation, message), format);
+    Ok(())
+}
+
+/// Encrypts, decrypts or rekeys all the conversations of a workspace
+async fn crypt_workspace(params: CryptWorkspace, profile: &Profile, format: Option<OutputFormat>) -> CliResult {
+    let (current, new, params) = match params {
+        CryptWorkspace::Encrypt(params) => {
+            let new = read_secret(params.keyfile.clone(), "Passphrase: ", true, true).await?;
+            (None, Some(new), params)
+        }
+        CryptWorkspace::Decrypt(params) => {
+            let current = read_secret(params.keyfile.clone(), "Passphrase: ", true, false).await?;
+            (Some(current), None, params)
+        }
+        CryptWorkspace::Rekey(params) => {
+            let current = read_secret(params.keyfile.clone(), "Current passphrase: ", true, false).await?;
+            let new = read_secret(params.new_keyfile.clone(), "New passphrase: ", false, true).await?;
+            (Some(current), Some(new), params)
+        }
+    };
+
+    let workspace = Workspace::open(params.workspace.unwrap_or_else(|| profile.workspace_dir())).await
+        .context("open workspace")?;
+    let updated = workspace.reencrypt(current.as_ref(), new.as_ref()).await
+        .context("update workspace")?;
+
+    output::print(&output::Reencrypted::build(updated), format);
+    Ok(())
+}
+
+/// Searches the messages of all the conversations in a workspace
+async fn search_conversations(params: SearchConversations, profile: &Profile, format: Option<OutputFormat>) -> CliResult {
+    let mut query = if params.regex {
+        SearchQuery::regex(&params.pattern).context("parse regular expression")?
+    } else {
+        SearchQuery::substring(&params.pattern, params.case_sensitive)
+    };
+
+    if let Some(role) = params.role {
+        query = query.with_role(role.into());
+    }
+    if let Some(since) = params.since {
+        query = query.with_since(Utc.from_utc_datetime(&since.and_hms_opt(0, 0, 0).unwrap()));
+    }
+    if let Some(until) = params.until.and_then(|until| until.succ_opt()) {
+        query = query.with_until(Utc.from_utc_datetime(&until.and_hms_opt(0, 0, 0).unwrap()));
+    }
+
+    let workspace = Workspace::open(params.workspace.unwrap_or_else(|| profile.workspace_dir())).await
+        .context("open workspace")?;
+    let report = if params.ranked {
+        let Some(index) = workspace.index().await.context("load search index")? else {
+            return Err(CliError::NotFound(String::from("search index of the workspace, create it with `rgpt reindex`")));
+        };
+
+        SearchReport::from(index.query(&params.pattern, params.limit))
+    } else if params.semantic {
+        match params.embeddings {
+            EmbeddingsBackend::Openai => {
+                let client = profile.create_client().await
+                    .context("create client")?;
+                let backend = OpenAIEmbeddings::build(client, DEFAULT_EMBEDDING_MODEL);
+                semantic::search_workspace(&workspace, &backend, &params.pattern, params.limit).await
+            }
+            EmbeddingsBackend::Local => {
+                let backend = HashingEmbeddings::default();
+                semantic::search_workspace(&workspace, &backend, &params.pattern, params.limit).await
+            }
+        }.context("search conversations")?
+    } else {
+        search::search_workspace(&workspace, &query).await
+            .context("search conversations")?
+    };
+
+    if let Some(index) = params.open {
+        let Some(hit) = report.hits().get(index) else {
+            return Err(CliError::NotFound(format!("result with index {}", index)));
+        };
+
+        let conversation = load_conversation(hit.path()).await?;
+        let message_list = conversation.get_message_list(Some(hit.message_id()))
+            .context("get message list")?;
+        print_message_list(&conversation, &message_list, format);
+        return Ok(());
+    }
+
+    output::print(&output::SearchResults::build(&report), format);
+    Ok(())
+}
+
+/// Creates the search index of a workspace from scratch
+async fn reindex_workspace(params: ReindexWorkspace, profile: &Profile, format: Option<OutputFormat>) -> CliResult {
+    let workspace = Workspace::open(params.workspace.unwrap_or_else(|| profile.workspace_dir())).await
+        .context("open workspace")?;
+    let index = workspace.rebuild_index().await
+        .context("rebuild search index")?;
+
+    output::print(&output::Reindexed::build(index.len()), format);
+    Ok(())
+}
+
+/// Deletes a conversation
+async fn delete_conversation(params: DeleteConversation, format: Option<OutputFormat>) -> CliResult {
+    let workspace = Workspace::containing(&params.path).await
+        .context("open workspace")?;
+    workspace.delete_conversation(&params.path).await
+        .context("delete conversation")?;
+
+    output::print(&output::Deleted::build(&params.path), format);
+    Ok(())
+}
+
+async fn list_models(profile: &Profile, format: Option<OutputFormat>) -> CliResult {
+    let client = profile.create_client().await
+        .context("create client")?;
+    let models = client.list_models().await
+        .context("list models")?;
+
+    output::print(&output::ModelList::build(models), format);
+    Ok(())
+}
+
+#[tokio::main]
+async fn main() -> ExitCode {
+    let args = Cli::parse();
+
+    let profile = match load_profile(args.profile.as_deref()).await {
+        Ok(profile) => {
+            let options = args.client.apply(profile.client_options().clone());
+            profile.with_client_options(options)
+        }
+        Err(e) => {
+            eprintln!("{}", e.report(args.verbose));
+            return e.exit_code();
+        }
+    };
+
+    let result = match args.command {
+        Commands::New(params) => new_conversation(params, &profile, args.output).await,
+        Commands::Complete(params) => complete_conversation(params, &profile, args.output).await,
+        Commands::Chat(params) => chat_conversation(params, &profile, args.output).await,
+        Commands::Show(params) => show_conversation(params, args.output).await,
+        Commands::Export(params) => export_conversation(params, args.output).await,
+        Commands::Import(params) => import_conversations(params, &profile, args.output).await,
+        Commands::Prefer(params) => prefer_conversation(params, args.output).await,
+        Commands::Crypt(params) => crypt_workspace(params, &profile, args.output).await,
+        Commands::Search(params) => search_conversations(params, &profile, args.output).await,
+        Commands::Reindex(params) => reindex_workspace(params, &profile, args.output).await,
+        Commands::Delete(params) => delete_conversation(params, args.output).await,
+        Commands::Models(_) => list_models(&profile, args.output).await,
+    };
+
+    match result {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(e) => {
+            eprintln!("{}", e.report(args.verbose));
+            e.exit_code()
+        }
+    }
+}
@@ -0,0 +1,556 @@
+use std::path::{Path, PathBuf};
+
+use async_openai::types::Role;
+use chrono::{DateTime, Utc};
+use clap::ValueEnum;
+use serde::Serialize;
+use tabled::builder;
+use tabled::settings::Modify;
+use tabled::settings::object::Columns;
+use tabled::settings::width::Wrap;
+use uuid::Uuid;
+
+use rust_gpt::conversations::{Conversation, Message};
+use rust_gpt::import::ImportReport;
+use rust_gpt::search::SearchReport;
+
+/// Module with tests related to the output of the commands
+#[cfg(test)]
+mod tests;
+
+/// Width used for tables when the size of the terminal is unknown
+const DEFAULT_WIDTH: usize = 100;
+
+/// Format of the output of a command. The JSON and YAML structures only get new fields, existing
+/// fields are never renamed or removed.
+#[derive(ValueEnum, Copy, Clone, Debug, PartialEq)]
+pub enum OutputFormat {
+    Json,
+    Yaml,
+    Text,
+    Table,
+}
+
+/// Result of a command that can be shown in every output format
+pub trait Render: Serialize {
+    /// Format used when none is given
+    const DEFAULT_FORMAT: OutputFormat = OutputFormat::Text;
+
+    /// Human readable output, with one element per line
+    fn text(&self) -> String;
+
+    /// Output as tables, wrapping contents to the width of the terminal
+    fn table(&self) -> String {
+        self.text()
+    }
+}
+
+/// Prints the result of a command in the given format, or its default one if None
+pub fn print<T>(value: &T, format: Option<OutputFormat>)
+where
+    T: Render
+{
+    let output = match format.unwrap_or(T::DEFAULT_FORMAT) {
+        OutputFormat::Json => serde_json::to_string_pretty(value)
+            .expect("serialize output"),
+        OutputFormat::Yaml => serde_yaml::to_string(value)
+            .expect("serialize output"),
+        OutputFormat::Text => value.text(),
+        OutputFormat::Table => value.table(),
+    };
+
+    println!("{}", output.trim_end());
+}
+
+/// Returns the width for the last column of a table, given the width of the other columns
+fn wrap_width(other_columns: usize) -> usize {
+    let terminal = crossterm::terminal::size()
+        .map(|(columns, _)| columns as usize)
+        .unwrap_or(DEFAULT_WIDTH);
+
+    terminal.saturating_sub(other_columns).max(20)
+}
+
+/// Returns the text in a single line, with its whitespace collapsed
+fn single_line(text: &str) -> String {
+    text.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+#[derive(Serialize, Debug)]
+pub struct MessageOutput {
+    id: Uuid,
+    parent_id: Option<Uuid>,
+
+    /// Position of the message among its siblings
+    index: u8,
+    role: Role,
+    content: String,
+    created: Option<DateTime<Utc>>,
+    model: Option<String>,
+    preferred: bool,
+}
+
+impl From<&Message> for MessageOutput {
+    fn from(message: &Message) -> Self {
+        MessageOutput {
+            id: message.id(),
+            parent_id: message.parent_id(),
+            index: message.index(),
+            role: message.role().clone(),
+            content: message.content().clone(),
+            created: message.created(),
+            model: message.model().map(str::to_string),
+            preferred: message.is_preferred(),
+        }
+    }
+}
+
+/// A chain of messages of a conversation, from the root
+#[derive(Serialize, Debug)]
+pub struct MessageList {
+    path: PathBuf,
+    name: String,
+    messages: Vec<MessageOutput>,
+}
+
+impl MessageList {
+    pub fn build(conversation: &Conversation, messages: &[&Message]) -> Self {
+        MessageList {
+            path: conversation.path().to_path_buf(),
+            name: conversation.name().to_string(),
+            messages: messages.iter().map(|&msg| msg.into()).collect(),
+        }
+    }
+}
+
+impl Render for MessageList {
+    const DEFAULT_FORMAT: OutputFormat = OutputFormat::Table;
+
+    fn text(&self) -> String {
+        self.messages.iter()
+            .map(|msg| format!("[{}] {}\n", msg.role, msg.content))
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+
+    fn table(&self) -> String {
+        let mut table_builder = builder::Builder::default();
+        table_builder.set_header(["#", "ROLE", "CONTENT"]);
+        for (i, msg) in self.messages.iter().enumerate() {
+            table_builder.push_record([i.to_string(), msg.role.to_string(), msg.content.clone()]);
+        }
+        let mut table = table_builder.build();
+
+        // The longest role is "assistant"
+        table.with(Modify::list(Columns::last(), Wrap::new(wrap_width(22))));
+        table.to_string()
+    }
+}
+
+#[derive(Serialize, Debug)]
+pub struct BranchOutput {
+    /// Index used for selecting the branch in other commands
+    index: usize,
+    last_message: MessageOutput,
+}
+
+/// The branches of a conversation, identified by their last message
+#[derive(Serialize, Debug)]
+pub struct BranchList {
+    path: PathBuf,
+    name: String,
+    branches: Vec<BranchOutput>,
+}
+
+impl BranchList {
+    pub fn build(conversation: &Conversation) -> Self {
+        BranchList {
+            path: conversation.path().to_path_buf(),
+            name: conversation.name().to_string(),
+            branches: conversation.get_latest_messages().into_iter()
+                .enumerate()
+                .map(|(index, msg)| BranchOutput { index, last_message: msg.into() })
+                .collect(),
+        }
+    }
+}
+
+impl Render for BranchList {
+    const DEFAULT_FORMAT: OutputFormat = OutputFormat::Table;
+
+    fn text(&self) -> String {
+        self.branches.iter()
+            .map(|branch| format!("{}: {}\n", branch.index, single_line(&branch.last_message.content)))
+            .collect()
+    }
+
+    fn table(&self) -> String {
+        let mut table_builder = builder::Builder::default();
+        table_builder.set_header(["INDEX", "LAST RESPONSE"]);
+        for branch in self.branches.iter() {
+            table_builder.push_record([branch.index.to_string(), branch.last_message.content.clone()]);
+        }
+        let mut table = table_builder.build();
+
+        table.with(Modify::list(Columns::last(), Wrap::new(wrap_width(14))));
+        table.to_string()
+    }
+}
+
+/// A newly created conversation
+#[derive(Serialize, Debug)]
+pub struct CreatedConversation {
+    path: PathBuf,
+    name: String,
+    root: MessageOutput,
+}
+
+impl CreatedConversation {
+    pub fn build(conversation: &Conversation) -> Self {
+        CreatedConversation {
+            path: conversation.path().to_path_buf(),
+            name: conversation.name().to_string(),
+            root: conversation.get_root_message().into(),
+        }
+    }
+}
+
+impl Render for CreatedConversation {
+    fn text(&self) -> String {
+        format!("Conversation saved at: {}", self.path.display())
+    }
+}
+
+/// A query along with the responses that were generated for it
+#[derive(Serialize, Debug)]
+pub struct Completion {
+    path: PathBuf,
+    query: MessageOutput,
+    responses: Vec<MessageOutput>,
+}
+
+impl Completion {
+    pub fn build(conversation: &Conversation, query: &Message, responses: &[&Message]) -> Self {
+        Completion {
+            path: conversation.path().to_path_buf(),
+            query: query.into(),
+            responses: responses.iter().map(|&msg| msg.into()).collect(),
+        }
+    }
+}
+
+impl Render for Completion {
+    fn text(&self) -> String {
+        self.responses.iter()
+            .map(|msg| format!("Response: {}\n", msg.content))
+            .collect()
+    }
+
+    fn table(&self) -> String {
+        let mut table_builder = builder::Builder::default();
+        table_builder.set_header(["INDEX", "RESPONSE"]);
+        for msg in self.responses.iter() {
+            table_builder.push_record([msg.index.to_string(), msg.content.clone()]);
+        }
+        let mut table = table_builder.build();
+
+        table.with(Modify::list(Columns::last(), Wrap::new(wrap_width(14))));
+        table.to_string()
+    }
+}
+
+/// A message that was marked or unmarked as preferred
+#[derive(Serialize, Debug)]
+pub struct PreferredMessage {
+    path: PathBuf,
+    message: MessageOutput,
+}
+
+impl PreferredMessage {
+    pub fn build(conversation: &Conversation, message: &Message) -> Self {
+        PreferredMessage {
+            path: conversation.path().to_path_buf(),
+            message: message.into(),
+        }
+    }
+}
+
+impl Render for PreferredMessage {
+    fn text(&self) -> String {
+        let state = if self.message.preferred { "Marked" } else { "Unmarked" };
+        format!("{} as preferred: {}", state, self.message.id)
+    }
+}
+
+#[derive(Serialize, Debug)]
+pub struct HitOutput {
+    /// Index used for opening the result
+    index: usize,
+    path: PathBuf,
+    conversation_name: String,
+    message_id: Uuid,
+    role: Role,
+    created: Option<DateTime<Utc>>,
+    snippet: String,
+    score: Option<f32>,
+}
+
+#[derive(Serialize, Debug)]
+pub struct SkippedConversationOutput {
+    path: PathBuf,
+    reason: String,
+}
+
+/// Results of a search
+#[derive(Serialize, Debug)]
+pub struct SearchResults {
+    hits: Vec<HitOutput>,
+
+    /// Conversations that couldn't be searched
+    skipped: Vec<SkippedConversationOutput>,
+}
+
+impl SearchResults {
+    pub fn build(report: &SearchReport) -> Self {
+        SearchResults {
+            hits: report.hits().iter().enumerate()
+                .map(|(index, hit)| HitOutput {
+                    index,
+                    path: hit.path().clone(),
+                    conversation_name: hit.conversation_name().to_string(),
+                    message_id: hit.message_id(),
+                    role: hit.role().clone(),
+                    created: hit.created(),
+                    snippet: hit.snippet().to_string(),
+                    score: hit.score(),
+                })
+                .collect(),
+            skipped: report.skipped().iter()
+                .map(|skipped| SkippedConversationOutput {
+                    path: skipped.path().to_path_buf(),
+                    reason: skipped.reason().to_string(),
+                })
+                .collect(),
+        }
+    }
+
+    /// Returns a line for each conversation that couldn't be searched
+    fn skipped_text(&self) -> String {
+        self.skipped.iter()
+            .map(|skipped| format!("Skipped: {} ({})\n", skipped.path.display(), skipped.reason))
+            .collect()
+    }
+}
+
+impl Render for SearchResults {
+    const DEFAULT_FORMAT: OutputFormat = OutputFormat::Table;
+
+    fn text(&self) -> String {
+        self.hits.iter()
+            .map(|hit| format!("{}: {} {} [{}] {}\n", hit.index, hit.path.display(), hit.message_id, hit.role,
+                               hit.snippet))
+            .chain([self.skipped_text()])
+            .collect()
+    }
+
+    fn table(&self) -> String {
+        let mut table_builder = builder::Builder::default();
+        table_builder.set_header(["INDEX", "CONVERSATION", "MESSAGE", "ROLE", "MATCH"]);
+        for hit in self.hits.iter() {
+            table_builder.push_record([
+                hit.index.to_string(),
+                hit.path.display().to_string(),
+                hit.message_id.to_string(),
+                hit.role.to_string(),
+                hit.snippet.clone(),
+            ]);
+        }
+        let mut table = table_builder.build();
+
+        // Widest value of the other columns, or their header, along with the borders and padding
+        let width = |header: &str, value: fn(&HitOutput) -> usize| {
+            self.hits.iter().map(value).max().unwrap_or(0).max(header.len())
+        };
+        let other_columns = width("INDEX", |hit| hit.index.to_string().len())
+            + width("CONVERSATION", |hit| hit.path.display().to_string().chars().count())
+            + width("MESSAGE", |hit| hit.message_id.to_string().len())
+            + width("ROLE", |hit| hit.role.to_string().len())
+            + 16;
+        table.with(Modify::list(Columns::last(), Wrap::new(wrap_width(other_columns))));
+        format!("{}\n{}", table, self.skipped_text())
+    }
+}
+
+#[derive(Serialize, Debug)]
+pub struct SkippedOutput {
+    source: String,
+    id: String,
+    reason: String,
+}
+
+/// Conversations imported from other applications
+#[derive(Serialize, Debug)]
+pub struct Imported {
+    imported: Vec<PathBuf>,
+    skipped: Vec<SkippedOutput>,
+}
+
+impl From<&ImportReport> for Imported {
+    fn from(report: &ImportReport) -> Self {
+        Imported {
+            imported: report.imported().to_vec(),
+            skipped: report.skipped().iter()
+                .map(|skipped| SkippedOutput {
+                    source: skipped.source().to_string(),
+                    id: skipped.id().to_string(),
+                    reason: skipped.reason().to_string(),
+                })
+                .collect(),
+        }
+    }
+}
+
+impl Render for Imported {
+    const DEFAULT_FORMAT: OutputFormat = OutputFormat::Table;
+
+    fn text(&self) -> String {
+        let imported = self.imported.iter()
+            .map(|path| format!("Imported: {}\n", path.display()));
+        let skipped = self.skipped.iter()
+            .map(|skipped| format!("Skipped: {} {} ({})\n", skipped.source, skipped.id, skipped.reason));
+
+        imported.chain(skipped).collect()
+    }
+
+    fn table(&self) -> String {
+        let mut output: String = self.imported.iter()
+            .map(|path| format!("Imported: {}\n", path.display()))
+            .collect();
+
+        if !self.skipped.is_empty() {
+            let mut table_builder = builder::Builder::default();
+            table_builder.set_header(["CONVERSATION", "ID", "REASON"]);
+            for skipped in self.skipped.iter() {
+                table_builder.push_record([skipped.source.as_str(), &skipped.id, &skipped.reason]);
+            }
+            output.push_str(&format!("Skipped {} elements:\n{}", self.skipped.len(), table_builder.build()));
+        }
+
+        output
+    }
+}
+
+/// A conversation written to a file in another format
+#[derive(Serialize, Debug)]
+pub struct Exported {
+    path: PathBuf,
+}
+
+impl Exported {
+    pub fn build(path: &Path) -> Self {
+        Exported { path: path.to_path_buf() }
+    }
+}
+
+impl Render for Exported {
+    fn text(&self) -> String {
+        format!("Conversation exported to: {}", self.path.display())
+    }
+}
+
+/// A conversation exported to the standard output
+#[derive(Serialize, Debug)]
+pub struct ExportedContent {
+    /// Path to the exported conversation
+    path: PathBuf,
+    content: String,
+}
+
+impl ExportedContent {
+    pub fn build(conversation: &Conversation, content: String) -> Self {
+        ExportedContent {
+            path: conversation.path().to_path_buf(),
+            content,
+        }
+    }
+}
+
+impl Render for ExportedContent {
+    fn text(&self) -> String {
+        self.content.clone()
+    }
+}
+
+/// Conversations whose encryption changed
+#[derive(Serialize, Debug)]
+pub struct Reencrypted {
+    updated: Vec<PathBuf>,
+}
+
+impl Reencrypted {
+    pub fn build(updated: Vec<PathBuf>) -> Self {
+        Reencrypted { updated }
+    }
+}
+
+impl Render for Reencrypted {
+    fn text(&self) -> String {
+        format!("Updated {} conversations", self.updated.len())
+    }
+}
+
+/// A rebuilt search index
+#[derive(Serialize, Debug)]
+pub struct Reindexed {
+    indexed_messages: usize,
+}
+
+impl Reindexed {
+    pub fn build(indexed_messages: usize) -> Self {
+        Reindexed { indexed_messages }
+    }
+}
+
+impl Render for Reindexed {
+    fn text(&self) -> String {
+        format!("Indexed {} messages", self.indexed_messages)
+    }
+}
+
+/// A deleted conversation
+#[derive(Serialize, Debug)]
+pub struct Deleted {
+    deleted: PathBuf,
+}
+
+impl Deleted {
+    pub fn build(path: &Path) -> Self {
+        Deleted { deleted: path.to_path_buf() }
+    }
+}
+
+impl Render for Deleted {
+    fn text(&self) -> String {
+        format!("Conversation deleted: {}", self.deleted.display())
+    }
+}
+
+/// Models available in the API
+#[derive(Serialize, Debug)]
+pub struct ModelList {
+    models: Vec<String>,
+}
+
+impl ModelList {
+    pub fn build(models: Vec<String>) -> Self {
+        ModelList { models }
+    }
+}
+
+impl Render for ModelList {
+    fn text(&self) -> String {
+        self.models.iter()
+            .map(|model| format!("{}\n", model))
+            .collect()
+    }
+}
@@ -0,0 +1,94 @@
+use std::path::PathBuf;
+
+use serde_json::Value;
+
+use rust_gpt::conversations::CompletionParametersBuilder;
+use rust_gpt::search::{search_conversation, SearchQuery};
+
+use super::*;
+
+/// Returns the sorted fields of the JSON object
+fn fields(value: &Value) -> Vec<&str> {
+    let mut fields: Vec<&str> = value.as_object().expect("object").keys().map(String::as_str).collect();
+    fields.sort();
+    fields
+}
+
+fn json<T: Render>(value: &T) -> Value {
+    serde_json::to_value(value).expect("serialize output")
+}
+
+/// Builds a conversation with a query and its responses
+fn build_conversation() -> Conversation {
+    let parameters = CompletionParametersBuilder::default().build().expect("parameters");
+    let mut conversation = Conversation::build(parameters, PathBuf::from("output.yaml"), "System")
+        .expect("conversation");
+    let root = conversation.get_root_message().id();
+    let query = conversation.add_queries(root, vec![String::from("Query")]).expect("query")[0].id();
+    let completion = conversation.prepare_completion(query, None).expect("completion");
+    conversation.finish_completion(&completion, vec![String::from("Response")]).expect("responses");
+    conversation
+}
+
+const MESSAGE_FIELDS: [&str; 8] = ["content", "created", "id", "index", "model", "parent_id", "preferred", "role"];
+
+#[test]
+fn json_schema() {
+    let conversation = build_conversation();
+    let messages = conversation.get_latest_messages();
+    let response = messages[0];
+    let query = conversation.iter().find(|msg| msg.role() == &Role::User).expect("query");
+
+    let list = json(&MessageList::build(&conversation, &[query, response]));
+    assert_eq!(fields(&list), vec!["messages", "name", "path"]);
+    assert_eq!(fields(&list["messages"][0]), MESSAGE_FIELDS);
+
+    let branches = json(&BranchList::build(&conversation));
+    assert_eq!(fields(&branches), vec!["branches", "name", "path"]);
+    assert_eq!(fields(&branches["branches"][0]), vec!["index", "last_message"]);
+    assert_eq!(fields(&branches["branches"][0]["last_message"]), MESSAGE_FIELDS);
+
+    let created = json(&CreatedConversation::build(&conversation));
+    assert_eq!(fields(&created), vec!["name", "path", "root"]);
+    assert_eq!(fields(&created["root"]), MESSAGE_FIELDS);
+
+    let completion = json(&Completion::build(&conversation, query, &[response]));
+    assert_eq!(fields(&completion), vec!["path", "query", "responses"]);
+    assert_eq!(fields(&completion["responses"][0]), MESSAGE_FIELDS);
+
+    let preferred = json(&PreferredMessage::build(&conversation, response));
+    assert_eq!(fields(&preferred), vec!["message", "path"]);
+
+    let hits = search_conversation(&conversation, &SearchQuery::substring("response", false));
+    let results = json(&SearchResults::build(&SearchReport::from(hits)));
+    assert_eq!(fields(&results), vec!["hits", "skipped"]);
+    assert_eq!(fields(&results["hits"][0]),
+               vec!["conversation_name", "created", "index", "message_id", "path", "role", "score", "snippet"]);
+
+    assert_eq!(fields(&json(&Imported::from(&ImportReport::default()))), vec!["imported", "skipped"]);
+    assert_eq!(fields(&json(&Exported::build(Path::new("output.md")))), vec!["path"]);
+    assert_eq!(fields(&json(&ExportedContent::build(&conversation, String::from("# Chat")))), vec!["content", "path"]);
+    assert_eq!(fields(&json(&Reencrypted::build(vec![PathBuf::from("output.yaml")]))), vec!["updated"]);
+    assert_eq!(fields(&json(&Reindexed::build(2))), vec!["indexed_messages"]);
+    assert_eq!(fields(&json(&Deleted::build(Path::new("output.yaml")))), vec!["deleted"]);
+    assert_eq!(fields(&json(&ModelList::build(vec![String::from("gpt-4")]))), vec!["models"]);
+
+    // Roles and identifiers keep the format of the conversations
+    assert_eq!(list["messages"][0]["role"], "user");
+    assert_eq!(list["messages"][1]["parent_id"], query.id().to_string());
+}
+
+#[test]
+fn message_table() {
+    let conversation = build_conversation();
+    let messages = conversation.get_message_list(None).expect("message list");
+    let table = MessageList::build(&conversation, &messages).table();
+
+    assert!(table.lines().any(|line| line.contains("ROLE") && line.contains("CONTENT")));
+    assert!(table.lines().any(|line| line.contains("assistant") && line.contains("Response")));
+}
+
+#[test]
+fn single_line_text() {
+    assert_eq!(single_line("First line\n\n  second\tline "), "First line second line");
+}
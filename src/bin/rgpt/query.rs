@@ -0,0 +1,78 @@
+use std::path::{Path, PathBuf};
+
+use tokio::fs;
+use tokio::io::{self, AsyncRead, AsyncReadExt};
+
+/// Module with tests related to the composition of queries
+#[cfg(test)]
+mod tests;
+
+/// Argument used for reading the query from the standard input
+pub const STDIN_ARGUMENT: &str = "-";
+
+/// Creates the text of a query from its sources, in this order: the prefix, the query (read from
+/// stdin if it is `-`) and the attached files. If there is a prefix but no query, the query is read
+/// from stdin.
+///
+/// # Arguments
+///
+/// * `query`: Text of the query, or `-` for reading from stdin
+/// * `prefix`: Text before the query, e.g. an instruction about what to do with stdin
+/// * `files`: Files attached after the query, within fenced code blocks
+///
+/// returns: Result<String, Error>
+pub async fn compose(query: Option<String>, prefix: Option<String>, files: &[PathBuf]) -> io::Result<String> {
+    compose_from(query, prefix, files, io::stdin()).await
+}
+
+/// Creates the text of a query as [compose] does, reading what would come from stdin from the
+/// given input instead
+async fn compose_from<R>(query: Option<String>, prefix: Option<String>, files: &[PathBuf], input: R)
+                         -> io::Result<String>
+where
+    R: AsyncRead + Unpin
+{
+    let query = match query {
+        Some(query) if query == STDIN_ARGUMENT => Some(read_all(input).await?),
+        None if prefix.is_some() => Some(read_all(input).await?),
+        query => query,
+    };
+
+    let mut parts: Vec<String> = prefix.into_iter()
+        .chain(query)
+        .map(|part| part.trim_end().to_string())
+        .filter(|part| !part.is_empty())
+        .collect();
+
+    for path in files {
+        let content = fs::read_to_string(path).await?;
+        parts.push(attachment(path, &content));
+    }
+
+    Ok(parts.join("\n\n"))
+}
+
+async fn read_all<R>(mut input: R) -> io::Result<String>
+where
+    R: AsyncRead + Unpin
+{
+    let mut text = String::new();
+    input.read_to_string(&mut text).await?;
+    Ok(text)
+}
+
+/// Formats the content of a file as a fenced code block with a header naming the file
+fn attachment(path: &Path, content: &str) -> String {
+    // The fence must be longer than any run of backticks within the content
+    let longest_run = content.split(|c| c != '`')
+        .map(str::len)
+        .max()
+        .unwrap_or(0);
+    let fence = "`".repeat(longest_run.max(2) + 1);
+
+    let language = path.extension()
+        .and_then(|ext| ext.to_str())
+        .unwrap_or_default();
+
+    format!("File: {}\n{}{}\n{}\n{}", path.display(), fence, language, content.trim_end(), fence)
+}
@@ -0,0 +1,55 @@
+use uuid::Uuid;
+
+use super::*;
+
+#[tokio::test]
+async fn compose_sources() {
+    let compose = |query: Option<&str>, prefix: Option<&str>, stdin: &'static str| {
+        compose_from(query.map(String::from), prefix.map(String::from), &[], stdin.as_bytes())
+    };
+
+    // The query is used as is, stdin is only read when asked for
+    assert_eq!(compose(Some("Hello"), None, "ignored").await.expect("query"), "Hello");
+    assert_eq!(compose(Some("-"), None, "From stdin\n").await.expect("stdin"), "From stdin");
+
+    // A prefix goes before the query, which is read from stdin if not given
+    assert_eq!(compose(Some("Query"), Some("Prefix"), "ignored").await.expect("prefix"), "Prefix\n\nQuery");
+    assert_eq!(compose(None, Some("Explain:"), "fn main() {}\n").await.expect("prefix and stdin"),
+               "Explain:\n\nfn main() {}");
+
+    // Empty parts are left out
+    assert_eq!(compose(Some("-"), Some("Prefix"), "  \n").await.expect("empty stdin"), "Prefix");
+    assert_eq!(compose(None, None, "ignored").await.expect("nothing"), "");
+}
+
+#[tokio::test]
+async fn compose_attachments() {
+    let directory = std::env::temp_dir().join(format!("rgpt-query-{}", Uuid::new_v4()));
+    fs::create_dir(&directory).await.expect("temp dir");
+    let path = directory.join("main.rs");
+    fs::write(&path, "fn main() {}\n").await.expect("write file");
+
+    let text = compose_from(Some(String::from("Review")), None, std::slice::from_ref(&path), &b""[..]).await
+        .expect("compose");
+    assert_eq!(text, format!("Review\n\nFile: {}\n```rs\nfn main() {{}}\n```", path.display()));
+
+    let missing = compose_from(None, None, &[directory.join("missing.rs")], &b""[..]).await;
+    assert!(missing.is_err());
+
+    let _ = fs::remove_dir_all(&directory).await;
+}
+
+#[test]
+fn attachment_fences() {
+    let path = Path::new("notes.md");
+
+    // The fence is longer than any fence within the content
+    let content = "Example:\n```rust\nlet x = 1;\n```\n";
+    assert_eq!(attachment(path, content), format!("File: notes.md\n````md\n{}\n````", content.trim_end()));
+
+    let content = "Five ````` backticks";
+    assert!(attachment(path, content).ends_with("\n``````"));
+
+    // Files without an extension get no language
+    assert_eq!(attachment(Path::new("Makefile"), "all:"), "File: Makefile\n```\nall:\n```");
+}
@@ -0,0 +1,400 @@
+use std::collections::{BTreeMap, HashMap};
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::time::Duration;
+
+use async_openai::config::{AzureConfig, Config, OpenAIConfig};
+use async_openai::types::{ChatCompletionResponseStream, CreateChatCompletionRequest, CreateChatCompletionResponse,
+                          CreateEmbeddingRequest, CreateEmbeddingResponse};
+use reqwest::header::{HeaderMap, HeaderName, HeaderValue, AUTHORIZATION};
+use serde::{Deserialize, Serialize};
+use tokio::fs;
+
+use crate::client::ollama::{OllamaClient, OllamaOptions, DEFAULT_OLLAMA_BASE};
+use crate::conversations::ClientRef;
+use crate::{Result, RustGPTError};
+
+/// Contains the backend for the native API of a local Ollama server. Other local servers, like
+/// llama.cpp, are used through their OpenAI compatible API instead.
+pub mod ollama;
+
+/// Module with tests related to creating clients
+#[cfg(test)]
+mod tests;
+
+/// Environment variable with the API key when no other source is given
+pub const DEFAULT_API_KEY_ENV: &str = "OPENAI_API_KEY";
+
+/// Where the API key is read from when creating a client
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum ApiKeySource {
+    /// Environment variable with the key, no key is sent if it isn't set
+    Env(String),
+
+    /// File whose content (without surrounding whitespace) is the key
+    File(PathBuf),
+
+    /// The key itself
+    Value(String),
+
+    /// No key is sent, e.g. for local servers
+    None,
+}
+
+impl Default for ApiKeySource {
+    fn default() -> Self {
+        ApiKeySource::Env(DEFAULT_API_KEY_ENV.to_string())
+    }
+}
+
+impl ApiKeySource {
+    /// Returns the key, if the source has one
+    pub async fn read(&self) -> Result<Option<String>> {
+        Ok(match self {
+            ApiKeySource::Env(name) => std::env::var(name).ok(),
+            ApiKeySource::File(path) => Some(fs::read_to_string(path).await?.trim().to_string()),
+            ApiKeySource::Value(key) => Some(key.clone()),
+            ApiKeySource::None => None,
+        })
+    }
+}
+
+/// Version of the Azure OpenAI API used when none is given
+pub const DEFAULT_AZURE_API_VERSION: &str = "2023-05-15";
+
+/// Settings of Azure OpenAI, where each model is used through a deployment
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(default)]
+pub struct AzureOptions {
+    /// Value of the `api-version` query parameter, e.g. `2023-05-15`
+    api_version: String,
+
+    /// Name of the deployment of each model, by model name (e.g. `gpt-4: my-gpt4-deployment`)
+    deployments: BTreeMap<String, String>,
+}
+
+impl Default for AzureOptions {
+    fn default() -> Self {
+        Self::build(DEFAULT_AZURE_API_VERSION)
+    }
+}
+
+impl AzureOptions {
+    pub fn build(api_version: &str) -> Self {
+        AzureOptions {
+            api_version: api_version.to_string(),
+            deployments: BTreeMap::new(),
+        }
+    }
+
+    pub fn api_version(&self) -> &str { &self.api_version }
+    pub fn deployments(&self) -> &BTreeMap<String, String> { &self.deployments }
+
+    /// Uses the given deployment for the model (e.g. `gpt-4` or `text-embedding-ada-002`)
+    pub fn with_deployment(mut self, model: &str, deployment: &str) -> Self {
+        self.deployments.insert(model.to_string(), deployment.to_string());
+        self
+    }
+
+    pub fn with_api_version(mut self, api_version: &str) -> Self {
+        self.api_version = api_version.to_string();
+        self
+    }
+}
+
+/// Header with the key of Azure OpenAI
+const AZURE_API_KEY_HEADER: &str = "api-key";
+
+/// Configuration of async-openai that leaves out the header with the API key when it's empty, as
+/// the configurations always send it otherwise
+#[derive(Debug, Clone)]
+pub struct OptionalKeyConfig<C>(C);
+
+impl<C: Config> OptionalKeyConfig<C> {
+    pub fn new(config: C) -> Self {
+        OptionalKeyConfig(config)
+    }
+}
+
+impl<C: Config> Config for OptionalKeyConfig<C> {
+    fn headers(&self) -> HeaderMap {
+        let mut headers = self.0.headers();
+        if self.0.api_key().is_empty() {
+            headers.remove(AUTHORIZATION);
+            headers.remove(AZURE_API_KEY_HEADER);
+        }
+
+        headers
+    }
+
+    fn url(&self, path: &str) -> String { self.0.url(path) }
+    fn query(&self) -> Vec<(&str, &str)> { self.0.query() }
+    fn api_base(&self) -> &str { self.0.api_base() }
+    fn api_key(&self) -> &str { self.0.api_key() }
+}
+
+/// Client for the completions and embeddings of one of the supported backends
+pub enum ApiClient {
+    /// OpenAI or any API compatible with it
+    OpenAI(async_openai::Client<OptionalKeyConfig<OpenAIConfig>>),
+
+    /// Azure OpenAI, with a client for each deployment by model name
+    Azure(HashMap<String, async_openai::Client<OptionalKeyConfig<AzureConfig>>>),
+
+    /// Native API of a local Ollama server
+    Ollama(OllamaClient),
+}
+
+impl ApiClient {
+    /// Creates a chat completion
+    pub async fn chat(&self, request: CreateChatCompletionRequest) -> Result<CreateChatCompletionResponse> {
+        Ok(match self {
+            ApiClient::OpenAI(client) => client.chat().create(request).await?,
+            ApiClient::Azure(clients) => Self::deployment(clients, &request.model)?.chat().create(request).await?,
+            ApiClient::Ollama(client) => client.chat(request).await?,
+        })
+    }
+
+    /// Creates a chat completion, receiving its content as it is generated
+    pub async fn chat_stream(&self, request: CreateChatCompletionRequest) -> Result<ChatCompletionResponseStream> {
+        Ok(match self {
+            ApiClient::OpenAI(client) => client.chat().create_stream(request).await?,
+            ApiClient::Azure(clients) => Self::deployment(clients, &request.model)?.chat().create_stream(request).await?,
+            ApiClient::Ollama(client) => client.chat_stream(request).await?,
+        })
+    }
+
+    /// Creates the embeddings of the input of the request
+    pub async fn embeddings(&self, request: CreateEmbeddingRequest) -> Result<CreateEmbeddingResponse> {
+        Ok(match self {
+            ApiClient::OpenAI(client) => client.embeddings().create(request).await?,
+            ApiClient::Azure(clients) => Self::deployment(clients, &request.model)?.embeddings().create(request).await?,
+            ApiClient::Ollama(client) => client.embeddings(request).await?,
+        })
+    }
+
+    /// Returns the name of the model that answers the requests for the given one, e.g. the local
+    /// model that Ollama runs for it
+    pub fn answering_model(&self, model: &str) -> String {
+        match self {
+            ApiClient::Ollama(client) => client.local_model(model).to_string(),
+            ApiClient::OpenAI(_) | ApiClient::Azure(_) => model.to_string(),
+        }
+    }
+
+    /// Returns the names of the models that can be used, sorted. For Azure OpenAI these are the
+    /// models with a deployment.
+    pub async fn list_models(&self) -> Result<Vec<String>> {
+        let mut models: Vec<String> = match self {
+            ApiClient::OpenAI(client) => client.models().list().await?
+                .data
+                .into_iter()
+                .map(|model| model.id)
+                .collect(),
+            ApiClient::Azure(clients) => clients.keys().cloned().collect(),
+            ApiClient::Ollama(client) => client.list_models().await?
+                .into_iter()
+                .map(|model| model.name().to_string())
+                .collect(),
+        };
+
+        models.sort();
+        Ok(models)
+    }
+
+    fn deployment<'a>(clients: &'a HashMap<String, async_openai::Client<OptionalKeyConfig<AzureConfig>>>,
+                      model: &str) -> Result<&'a async_openai::Client<OptionalKeyConfig<AzureConfig>>> {
+        clients.get(model)
+            .ok_or_else(|| RustGPTError::InvalidSetting(format!("no Azure deployment for model {}", model)))
+    }
+}
+
+/// Settings of the connection to an OpenAI compatible API, like a local server (llama.cpp, vLLM,
+/// Ollama) or one behind a corporate proxy.
+///
+/// # Examples
+///
+/// ```
+/// use std::time::Duration;
+/// use rust_gpt::client::{ApiKeySource, ClientOptions};
+///
+/// let options = ClientOptions::default()
+///     .with_api_base("http://localhost:8080/v1")
+///     .with_api_key(ApiKeySource::None)
+///     .with_header("X-Team", "research")
+///     .with_timeout(Duration::from_secs(120));
+/// assert_eq!(options.api_base(), Some("http://localhost:8080/v1"));
+/// ```
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+#[serde(default)]
+pub struct ClientOptions {
+    api_base: Option<String>,
+
+    /// Written as a map with a single key, e.g. `api_key: {env: MY_KEY}` or `api_key: none`
+    #[serde(with = "serde_yaml::with::singleton_map")]
+    api_key: ApiKeySource,
+    org_id: Option<String>,
+
+    /// Headers sent with every request
+    headers: BTreeMap<String, String>,
+
+    /// Maximum duration of a request, in whole seconds
+    timeout_secs: Option<u64>,
+
+    /// Maximum duration for connecting to the server, in whole seconds
+    connect_timeout_secs: Option<u64>,
+
+    /// URL of the proxy for every request, e.g. `http://proxy:3128`
+    proxy: Option<String>,
+
+    /// Uses Azure OpenAI instead, with `api_base` being the endpoint of the resource
+    azure: Option<AzureOptions>,
+
+    /// Uses the native API of Ollama instead, with `api_base` being the address of the server
+    /// ([DEFAULT_OLLAMA_BASE] if not given)
+    ollama: Option<OllamaOptions>,
+}
+
+impl ClientOptions {
+    pub fn api_base(&self) -> Option<&str> { self.api_base.as_deref() }
+    pub fn api_key(&self) -> &ApiKeySource { &self.api_key }
+    pub fn org_id(&self) -> Option<&str> { self.org_id.as_deref() }
+    pub fn headers(&self) -> &BTreeMap<String, String> { &self.headers }
+    pub fn timeout(&self) -> Option<Duration> { self.timeout_secs.map(Duration::from_secs) }
+    pub fn connect_timeout(&self) -> Option<Duration> { self.connect_timeout_secs.map(Duration::from_secs) }
+    pub fn proxy(&self) -> Option<&str> { self.proxy.as_deref() }
+    pub fn azure(&self) -> Option<&AzureOptions> { self.azure.as_ref() }
+    pub fn ollama(&self) -> Option<&OllamaOptions> { self.ollama.as_ref() }
+
+    /// Base URL of the API, e.g. `https://api.openai.com/v1`
+    pub fn with_api_base(mut self, api_base: &str) -> Self {
+        self.api_base = Some(api_base.to_string());
+        self
+    }
+
+    pub fn with_api_key(mut self, api_key: ApiKeySource) -> Self {
+        self.api_key = api_key;
+        self
+    }
+
+    pub fn with_org_id(mut self, org_id: &str) -> Self {
+        self.org_id = Some(org_id.to_string());
+        self
+    }
+
+    /// Adds a header sent with every request, replacing any previous value
+    pub fn with_header(mut self, name: &str, value: &str) -> Self {
+        self.headers.insert(name.to_string(), value.to_string());
+        self
+    }
+
+    /// Maximum duration of a request, rounded up to whole seconds
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout_secs = Some(whole_secs(timeout));
+        self
+    }
+
+    /// Maximum duration for connecting to the server, rounded up to whole seconds
+    pub fn with_connect_timeout(mut self, timeout: Duration) -> Self {
+        self.connect_timeout_secs = Some(whole_secs(timeout));
+        self
+    }
+
+    pub fn with_proxy(mut self, proxy: &str) -> Self {
+        self.proxy = Some(proxy.to_string());
+        self
+    }
+
+    pub fn with_azure(mut self, azure: AzureOptions) -> Self {
+        self.azure = Some(azure);
+        self
+    }
+
+    pub fn with_ollama(mut self, ollama: OllamaOptions) -> Self {
+        self.ollama = Some(ollama);
+        self
+    }
+
+    /// Creates the HTTP client with the headers, timeouts and proxy of the options
+    fn http_client(&self) -> Result<reqwest::Client> {
+        let invalid = |e: &dyn std::fmt::Display| RustGPTError::InvalidSetting(e.to_string());
+
+        let mut headers = HeaderMap::new();
+        for (name, value) in self.headers.iter() {
+            let name = HeaderName::from_bytes(name.as_bytes()).map_err(|e| invalid(&e))?;
+            let value = HeaderValue::from_str(value).map_err(|e| invalid(&e))?;
+            headers.insert(name, value);
+        }
+
+        let mut builder = reqwest::Client::builder().default_headers(headers);
+        if let Some(timeout) = self.timeout() {
+            builder = builder.timeout(timeout);
+        }
+        if let Some(timeout) = self.connect_timeout() {
+            builder = builder.connect_timeout(timeout);
+        }
+        if let Some(proxy) = &self.proxy {
+            builder = builder.proxy(reqwest::Proxy::all(proxy).map_err(|e| invalid(&e))?);
+        }
+
+        builder.build().map_err(|e| invalid(&e))
+    }
+
+    /// Creates a client with the given options, reading the API key from its source.
+    pub async fn create_client(&self) -> Result<ClientRef> {
+        // The configurations read OPENAI_API_KEY by default, so it is always replaced, and an
+        // empty key is left out of the requests
+        let api_key = self.api_key.read().await?.unwrap_or_default();
+        let http_client = self.http_client()?;
+
+        if let Some(ollama) = &self.ollama {
+            let api_base = self.api_base.as_deref().unwrap_or(DEFAULT_OLLAMA_BASE);
+            let client = OllamaClient::build(http_client, api_base, ollama.clone());
+
+            return Ok(Arc::new(ApiClient::Ollama(client)));
+        }
+
+        if let Some(azure) = &self.azure {
+            let Some(api_base) = &self.api_base else {
+                return Err(RustGPTError::InvalidSetting(String::from("Azure OpenAI requires the API base URL")));
+            };
+
+            let clients = azure.deployments.iter()
+                .map(|(model, deployment)| {
+                    let config = AzureConfig::new()
+                        .with_api_base(api_base.trim_end_matches('/'))
+                        .with_api_version(&azure.api_version)
+                        .with_deployment_id(deployment)
+                        .with_api_key(&api_key);
+                    let client = async_openai::Client::with_config(OptionalKeyConfig::new(config))
+                        .with_http_client(http_client.clone());
+
+                    (model.clone(), client)
+                })
+                .collect();
+
+            return Ok(Arc::new(ApiClient::Azure(clients)));
+        }
+
+        let mut config = OpenAIConfig::new();
+        if let Some(api_base) = &self.api_base {
+            config = config.with_api_base(api_base.trim_end_matches('/'));
+        }
+        if let Some(org_id) = &self.org_id {
+            config = config.with_org_id(org_id);
+        }
+
+        config = config.with_api_key(api_key);
+
+        let client = async_openai::Client::with_config(OptionalKeyConfig::new(config))
+            .with_http_client(http_client);
+
+        Ok(Arc::new(ApiClient::OpenAI(client)))
+    }
+}
+
+/// Returns the duration in seconds, rounding up any fraction so short timeouts aren't zero
+fn whole_secs(duration: Duration) -> u64 {
+    duration.as_secs() + u64::from(duration.subsec_nanos() > 0)
+}
@@ -0,0 +1,392 @@
+use std::collections::BTreeMap;
+
+use async_openai::error::OpenAIError;
+use async_openai::types::{ChatChoice, ChatCompletionRequestMessage, ChatCompletionResponseMessage,
+                          ChatCompletionResponseStream, ChatCompletionResponseStreamMessage,
+                          ChatCompletionStreamResponseDelta, CreateChatCompletionRequest,
+                          CreateChatCompletionResponse, CreateChatCompletionStreamResponse, CreateEmbeddingRequest,
+                          CreateEmbeddingResponse, Embedding, EmbeddingInput, EmbeddingUsage, Role, Usage};
+use chrono::Utc;
+use futures::{future, stream, StreamExt};
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+
+use crate::{Result, RustGPTError};
+
+/// Module with tests related to the Ollama backend
+#[cfg(test)]
+mod tests;
+
+/// Address of a local Ollama server with the default settings
+pub const DEFAULT_OLLAMA_BASE: &str = "http://localhost:11434";
+
+/// Settings of a local Ollama server
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+#[serde(default)]
+pub struct OllamaOptions {
+    /// Local model used for each completion model, by model name (e.g. `gpt-4: llama2:13b`)
+    models: BTreeMap<String, String>,
+
+    /// Local model used for completion models without an entry in `models`
+    default_model: Option<String>,
+}
+
+impl OllamaOptions {
+    pub fn models(&self) -> &BTreeMap<String, String> { &self.models }
+    pub fn default_model(&self) -> Option<&str> { self.default_model.as_deref() }
+
+    /// Uses the local model for the given completion model (e.g. `gpt-3.5-turbo`)
+    pub fn with_model(mut self, model: &str, local_model: &str) -> Self {
+        self.models.insert(model.to_string(), local_model.to_string());
+        self
+    }
+
+    /// Uses the local model for every completion model that isn't mapped
+    pub fn with_default_model(mut self, local_model: &str) -> Self {
+        self.default_model = Some(local_model.to_string());
+        self
+    }
+
+    /// Returns the local model for the requested one. Models that aren't mapped, when there is
+    /// no default model, are requested with the same name.
+    pub fn local_model<'a>(&'a self, model: &'a str) -> &'a str {
+        self.models.get(model)
+            .map(String::as_str)
+            .or(self.default_model.as_deref())
+            .unwrap_or(model)
+    }
+}
+
+/// Model available in the local server
+#[derive(Debug, Clone, Deserialize, PartialEq)]
+pub struct LocalModel {
+    name: String,
+    #[serde(default)]
+    size: u64,
+    #[serde(default)]
+    modified_at: Option<String>,
+}
+
+impl LocalModel {
+    pub fn name(&self) -> &str { &self.name }
+
+    /// Size of the model in bytes
+    pub fn size(&self) -> u64 { self.size }
+    pub fn modified_at(&self) -> Option<&str> { self.modified_at.as_deref() }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+struct OllamaMessage {
+    role: String,
+    content: String,
+}
+
+#[derive(Debug, Default, Serialize, PartialEq)]
+struct OllamaParameters {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    temperature: Option<f32>,
+
+    /// Maximum number of generated tokens
+    #[serde(skip_serializing_if = "Option::is_none")]
+    num_predict: Option<u16>,
+}
+
+#[derive(Debug, Serialize, PartialEq)]
+struct OllamaChatRequest {
+    model: String,
+    messages: Vec<OllamaMessage>,
+    stream: bool,
+    options: OllamaParameters,
+}
+
+#[derive(Debug, Deserialize)]
+struct OllamaChatResponse {
+    message: Option<OllamaMessage>,
+    #[serde(default)]
+    done: bool,
+    error: Option<String>,
+    prompt_eval_count: Option<u32>,
+    eval_count: Option<u32>,
+}
+
+#[derive(Debug, Serialize)]
+struct OllamaEmbeddingRequest<'a> {
+    model: &'a str,
+    prompt: &'a str,
+}
+
+#[derive(Debug, Deserialize)]
+struct OllamaEmbeddingResponse {
+    embedding: Vec<f32>,
+}
+
+#[derive(Debug, Deserialize)]
+struct OllamaTagsResponse {
+    models: Vec<LocalModel>,
+}
+
+#[derive(Debug, Deserialize)]
+struct OllamaError {
+    error: String,
+}
+
+/// Client for the native API of an Ollama server. Requests and responses use the same types as
+/// the OpenAI API, several completions are created with repeated calls.
+pub struct OllamaClient {
+    http: reqwest::Client,
+    api_base: String,
+    options: OllamaOptions,
+}
+
+impl OllamaClient {
+    pub fn build(http: reqwest::Client, api_base: &str, options: OllamaOptions) -> Self {
+        OllamaClient {
+            http,
+            api_base: api_base.trim_end_matches('/').to_string(),
+            options,
+        }
+    }
+
+    /// Returns the local model that answers the requests for the given one
+    pub fn local_model<'a>(&'a self, model: &'a str) -> &'a str {
+        self.options.local_model(model)
+    }
+
+    /// Creates the completions of the request, one call for each
+    pub async fn chat(&self, request: CreateChatCompletionRequest) -> Result<CreateChatCompletionResponse> {
+        let ollama_request = self.chat_request(&request, false);
+
+        let mut choices = Vec::new();
+        let mut usage = Usage { prompt_tokens: 0, completion_tokens: 0, total_tokens: 0 };
+        for index in 0..request.n.unwrap_or(1).max(1) {
+            let response: OllamaChatResponse = self.post("/api/chat", &ollama_request).await?;
+            if let Some(error) = response.error {
+                return Err(RustGPTError::ResponseError(error));
+            }
+
+            usage.prompt_tokens += response.prompt_eval_count.unwrap_or(0);
+            usage.completion_tokens += response.eval_count.unwrap_or(0);
+            choices.push(ChatChoice {
+                index: index as u32,
+                message: ChatCompletionResponseMessage {
+                    role: Role::Assistant,
+                    content: response.message.map(|m| m.content),
+                    function_call: None,
+                },
+                finish_reason: Some(String::from("stop")),
+            });
+        }
+        usage.total_tokens = usage.prompt_tokens + usage.completion_tokens;
+
+        Ok(CreateChatCompletionResponse {
+            id: String::new(),
+            object: String::from("chat.completion"),
+            created: Utc::now().timestamp() as u32,
+            model: ollama_request.model,
+            usage: Some(usage),
+            choices,
+        })
+    }
+
+    /// Creates the completions of the request, streaming them one after the other
+    pub async fn chat_stream(&self, request: CreateChatCompletionRequest) -> Result<ChatCompletionResponseStream> {
+        let ollama_request = self.chat_request(&request, true);
+        let http = self.http.clone();
+        let url = format!("{}/api/chat", self.api_base);
+
+        let completions = stream::iter(0..request.n.unwrap_or(1).max(1) as u32)
+            .then(move |index| {
+                let response = http.post(&url).json(&ollama_request).send();
+                let model = ollama_request.model.clone();
+
+                async move {
+                    match response.await.and_then(|r| r.error_for_status()) {
+                        Ok(response) => lines(response.bytes_stream())
+                            .map(move |line| match line {
+                                Ok(line) => parse_stream_line(&line, index, &model),
+                                Err(e) => Some(Err(e)),
+                            })
+                            .filter_map(future::ready)
+                            .left_stream(),
+                        Err(e) => stream::once(future::ready(Err(OpenAIError::Reqwest(e))))
+                            .right_stream(),
+                    }
+                }
+            })
+            .flatten();
+
+        Ok(Box::pin(completions))
+    }
+
+    /// Creates the embeddings of each input, one call for each
+    pub async fn embeddings(&self, request: CreateEmbeddingRequest) -> Result<CreateEmbeddingResponse> {
+        let inputs = match request.input {
+            EmbeddingInput::String(text) => vec![text],
+            EmbeddingInput::StringArray(texts) => texts,
+            _ => return Err(RustGPTError::InvalidSetting(String::from("Ollama only creates embeddings of texts"))),
+        };
+
+        let model = self.options.local_model(&request.model);
+        let mut data = Vec::with_capacity(inputs.len());
+        for (index, prompt) in inputs.iter().enumerate() {
+            let response: OllamaEmbeddingResponse = self.post("/api/embeddings", &OllamaEmbeddingRequest {
+                model,
+                prompt,
+            }).await?;
+
+            data.push(Embedding {
+                index: index as u32,
+                object: String::from("embedding"),
+                embedding: response.embedding,
+            });
+        }
+
+        Ok(CreateEmbeddingResponse {
+            object: String::from("list"),
+            model: model.to_string(),
+            data,
+            usage: EmbeddingUsage { prompt_tokens: 0, total_tokens: 0 },
+        })
+    }
+
+    /// Returns the models available in the server
+    pub async fn list_models(&self) -> Result<Vec<LocalModel>> {
+        let response = self.http.get(format!("{}/api/tags", self.api_base))
+            .send().await
+            .map_err(http_error)?;
+
+        Ok(Self::read_response::<OllamaTagsResponse>(response).await?.models)
+    }
+
+    /// Translates a request of the OpenAI API into one of the native API
+    fn chat_request(&self, request: &CreateChatCompletionRequest, stream: bool) -> OllamaChatRequest {
+        OllamaChatRequest {
+            model: self.options.local_model(&request.model).to_string(),
+            messages: request.messages.iter().map(translate_message).collect(),
+            stream,
+            options: OllamaParameters {
+                temperature: request.temperature,
+                num_predict: request.max_tokens,
+            },
+        }
+    }
+
+    async fn post<B, T>(&self, path: &str, body: &B) -> Result<T>
+    where
+        B: Serialize,
+        T: DeserializeOwned
+    {
+        let response = self.http.post(format!("{}{}", self.api_base, path))
+            .json(body)
+            .send().await
+            .map_err(http_error)?;
+
+        Self::read_response(response).await
+    }
+
+    /// Returns the body of a response, or the error reported by the server
+    async fn read_response<T>(response: reqwest::Response) -> Result<T>
+    where
+        T: DeserializeOwned
+    {
+        let status = response.status();
+        let body = response.bytes().await.map_err(http_error)?;
+        if !status.is_success() {
+            let message = serde_json::from_slice::<OllamaError>(&body)
+                .map(|e| e.error)
+                .unwrap_or_else(|_| status.to_string());
+            return Err(RustGPTError::ResponseError(message));
+        }
+
+        Ok(serde_json::from_slice(&body)?)
+    }
+}
+
+fn http_error(e: reqwest::Error) -> RustGPTError {
+    RustGPTError::ClientError(OpenAIError::Reqwest(e))
+}
+
+/// Translates a message of the OpenAI API into one of the native API
+fn translate_message(message: &ChatCompletionRequestMessage) -> OllamaMessage {
+    let role = match message.role {
+        Role::System => "system",
+        Role::User => "user",
+        Role::Assistant => "assistant",
+        Role::Function => "tool",
+    };
+
+    OllamaMessage {
+        role: role.to_string(),
+        content: message.content.clone().unwrap_or_default(),
+    }
+}
+
+/// Splits a stream of bytes into lines. The last line is kept until the end of the stream, as
+/// it may not end with a line break.
+fn lines<S, B>(bytes: S) -> impl futures::Stream<Item=std::result::Result<String, OpenAIError>>
+where
+    S: futures::Stream<Item=reqwest::Result<B>>,
+    B: AsRef<[u8]>
+{
+    bytes
+        .map(Some)
+        .chain(stream::once(future::ready(None)))
+        .scan(Vec::new(), |buffer: &mut Vec<u8>, chunk| {
+            let lines = match chunk {
+                Some(Ok(chunk)) => {
+                    buffer.extend_from_slice(chunk.as_ref());
+
+                    let mut lines = Vec::new();
+                    while let Some(end) = buffer.iter().position(|&b| b == b'\n') {
+                        let line: Vec<u8> = buffer.drain(..=end).collect();
+                        lines.push(Ok(String::from_utf8_lossy(&line).trim().to_string()));
+                    }
+                    lines
+                }
+                Some(Err(e)) => vec![Err(OpenAIError::Reqwest(e))],
+                None => {
+                    let line: Vec<u8> = std::mem::take(buffer);
+                    vec![Ok(String::from_utf8_lossy(&line).trim().to_string())]
+                }
+            };
+
+            future::ready(Some(stream::iter(lines)))
+        })
+        .flatten()
+        .filter(|line| future::ready(!matches!(line, Ok(line) if line.is_empty())))
+}
+
+/// Translates a line of a streamed response into a fragment of the completion with the given
+/// index, if it has content.
+fn parse_stream_line(line: &str, index: u32, model: &str)
+                     -> Option<std::result::Result<CreateChatCompletionStreamResponse, OpenAIError>> {
+    let response: OllamaChatResponse = match serde_json::from_str(line) {
+        Ok(response) => response,
+        Err(e) => return Some(Err(OpenAIError::JSONDeserialize(e))),
+    };
+
+    if let Some(error) = response.error {
+        return Some(Err(OpenAIError::StreamError(error)));
+    }
+
+    let content = response.message.map(|m| m.content).filter(|c| !c.is_empty());
+    if content.is_none() && !response.done {
+        return None;
+    }
+
+    Some(Ok(CreateChatCompletionStreamResponse {
+        id: String::new(),
+        object: String::from("chat.completion.chunk"),
+        created: Utc::now().timestamp() as u32,
+        model: model.to_string(),
+        choices: vec![ChatCompletionResponseStreamMessage {
+            index,
+            delta: ChatCompletionStreamResponseDelta {
+                role: None,
+                content,
+                function_call: None,
+            },
+            finish_reason: response.done.then(|| String::from("stop")),
+        }],
+    }))
+}
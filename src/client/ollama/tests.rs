@@ -0,0 +1,98 @@
+use async_openai::types::ChatCompletionRequestMessageArgs;
+
+use super::*;
+
+#[test]
+fn local_models() {
+    let options = OllamaOptions::default()
+        .with_model("gpt-4", "llama2:13b");
+    assert_eq!(options.local_model("gpt-4"), "llama2:13b");
+
+    // Models that aren't mapped keep their name, unless there is a default model
+    assert_eq!(options.local_model("mistral"), "mistral");
+    let options = options.with_default_model("llama2");
+    assert_eq!(options.local_model("gpt-3.5-turbo"), "llama2");
+    assert_eq!(options.local_model("gpt-4"), "llama2:13b");
+
+    let options: OllamaOptions = serde_yaml::from_str("models:\n  gpt-4: mixtral\ndefault_model: llama2\n")
+        .expect("parse options");
+    assert_eq!(options.models().get("gpt-4").map(String::as_str), Some("mixtral"));
+    assert_eq!(options.default_model(), Some("llama2"));
+}
+
+#[test]
+fn translate_requests() {
+    let client = OllamaClient::build(reqwest::Client::new(), "http://localhost:11434/",
+                                     OllamaOptions::default().with_model("gpt-4", "llama2"));
+    assert_eq!(client.api_base, "http://localhost:11434");
+
+    let messages = [(Role::System, "Be brief"), (Role::User, "Hi"), (Role::Assistant, "Hello")]
+        .into_iter()
+        .map(|(role, content)| ChatCompletionRequestMessageArgs::default()
+            .role(role)
+            .content(content)
+            .build()
+            .expect("build message"))
+        .collect::<Vec<_>>();
+    let request = CreateChatCompletionRequest {
+        model: String::from("gpt-4"),
+        messages,
+        temperature: Some(0.5),
+        max_tokens: Some(64),
+        n: Some(2),
+        ..Default::default()
+    };
+
+    let ollama_request = client.chat_request(&request, false);
+    assert_eq!(ollama_request.model, "llama2");
+    assert_eq!(ollama_request.messages.iter().map(|m| m.role.as_str()).collect::<Vec<_>>(),
+               vec!["system", "user", "assistant"]);
+    assert_eq!(ollama_request.messages[1].content, "Hi");
+
+    let json = serde_json::to_value(&ollama_request).expect("serialize request");
+    assert_eq!(json["options"]["num_predict"], 64);
+    assert_eq!(json["stream"], false);
+
+    // Missing parameters are left to the server
+    let json = serde_json::to_value(OllamaParameters::default()).expect("serialize parameters");
+    assert_eq!(json, serde_json::json!({}));
+}
+
+#[tokio::test]
+async fn parse_streamed_responses() {
+    // The last line may not end with a line break
+    let chunks: Vec<reqwest::Result<&[u8]>> = vec![Ok(b"{\"done\":false}\n{\"do"), Ok(b"ne\":true}")];
+    let last: Vec<String> = lines(stream::iter(chunks))
+        .map(|line| line.expect("line"))
+        .collect()
+        .await;
+    assert_eq!(last, vec!["{\"done\":false}", "{\"done\":true}"]);
+
+    // Lines may be split across chunks
+    let chunks: Vec<reqwest::Result<&[u8]>> = vec![
+        Ok(b"{\"message\":{\"role\":\"assistant\",\"content\":\"Hel\"},\"done\":false}\n{\"mess"),
+        Ok(b"age\":{\"role\":\"assistant\",\"content\":\"lo\"},\"done\":false}\n\n"),
+        Ok(b"{\"message\":{\"role\":\"assistant\",\"content\":\"\"},\"done\":true}\n"),
+    ];
+    let lines: Vec<String> = lines(stream::iter(chunks))
+        .map(|line| line.expect("line"))
+        .collect()
+        .await;
+    assert_eq!(lines.len(), 3);
+
+    let responses: Vec<CreateChatCompletionStreamResponse> = lines.iter()
+        .filter_map(|line| parse_stream_line(line, 1, "llama2"))
+        .map(|response| response.expect("response"))
+        .collect();
+    let contents: Vec<Option<&str>> = responses.iter()
+        .map(|response| response.choices[0].delta.content.as_deref())
+        .collect();
+    assert_eq!(contents, vec![Some("Hel"), Some("lo"), None]);
+    assert!(responses.iter().all(|response| response.choices[0].index == 1));
+    assert_eq!(responses[2].choices[0].finish_reason.as_deref(), Some("stop"));
+
+    // Errors of the server and invalid lines are reported
+    assert!(matches!(parse_stream_line("{\"error\":\"model not found\"}", 0, "llama2"),
+        Some(Err(OpenAIError::StreamError(_)))));
+    assert!(matches!(parse_stream_line("not json", 0, "llama2"), Some(Err(OpenAIError::JSONDeserialize(_)))));
+}
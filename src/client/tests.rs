@@ -0,0 +1,112 @@
+use crate::test_util::TempDirectoryHandler;
+
+use super::*;
+
+#[tokio::test]
+async fn api_key_sources() {
+    let temp_dir = TempDirectoryHandler::build().expect("temp dir");
+
+    assert_eq!(ApiKeySource::Value(String::from("sk-1")).read().await.expect("value"), Some(String::from("sk-1")));
+    assert_eq!(ApiKeySource::None.read().await.expect("none"), None);
+    assert_eq!(ApiKeySource::Env(String::from("RGPT_TEST_MISSING_KEY")).read().await.expect("env"), None);
+
+    // Keys in files are trimmed
+    let path = temp_dir.path().join("key");
+    fs::write(&path, "sk-2\n").await.expect("write key");
+    assert_eq!(ApiKeySource::File(path).read().await.expect("file"), Some(String::from("sk-2")));
+
+    let missing = temp_dir.path().join("missing");
+    assert!(ApiKeySource::File(missing).read().await.is_err());
+}
+
+#[tokio::test]
+async fn create_clients() {
+    let options = ClientOptions::default()
+        .with_api_base("http://localhost:8080/v1/")
+        .with_api_key(ApiKeySource::None)
+        .with_org_id("org-1")
+        .with_header("X-Team", "research")
+        .with_timeout(Duration::from_secs(30))
+        .with_connect_timeout(Duration::from_secs(5))
+        .with_proxy("http://localhost:3128");
+    assert!(options.create_client().await.is_ok());
+
+    // Invalid settings are reported
+    let bad_header = ClientOptions::default().with_header("Bad Header", "value");
+    assert!(matches!(bad_header.create_client().await, Err(RustGPTError::InvalidSetting(_))));
+
+    let bad_proxy = ClientOptions::default().with_proxy("not a url");
+    assert!(matches!(bad_proxy.create_client().await, Err(RustGPTError::InvalidSetting(_))));
+
+    // Options are read from the configuration
+    let options: ClientOptions = serde_yaml::from_str("api_key:\n  env: MY_KEY\ntimeout_secs: 10\n")
+        .expect("parse options");
+    assert_eq!(options.api_key(), &ApiKeySource::Env(String::from("MY_KEY")));
+    assert_eq!(options.timeout(), Some(Duration::from_secs(10)));
+
+    // Timeouts are kept in whole seconds, without rounding short ones to zero
+    let options = ClientOptions::default()
+        .with_timeout(Duration::from_millis(1500))
+        .with_connect_timeout(Duration::from_millis(300));
+    assert_eq!(options.timeout(), Some(Duration::from_secs(2)));
+    assert_eq!(options.connect_timeout(), Some(Duration::from_secs(1)));
+}
+
+#[test]
+fn optional_api_keys() {
+    // Empty keys aren't sent
+    let keyless = OptionalKeyConfig::new(OpenAIConfig::new().with_api_key(""));
+    assert!(!keyless.headers().contains_key(AUTHORIZATION));
+    let keyless = OptionalKeyConfig::new(AzureConfig::new().with_api_key(""));
+    assert!(!keyless.headers().contains_key(AZURE_API_KEY_HEADER));
+
+    let config = OptionalKeyConfig::new(OpenAIConfig::new().with_api_key("sk-1").with_org_id("org-1"));
+    assert_eq!(config.headers().get(AUTHORIZATION).and_then(|value| value.to_str().ok()), Some("Bearer sk-1"));
+    assert!(config.headers().contains_key("OpenAI-Organization"));
+    let config = OptionalKeyConfig::new(AzureConfig::new().with_api_key("key-1"));
+    assert!(config.headers().contains_key(AZURE_API_KEY_HEADER));
+}
+
+#[tokio::test]
+async fn azure_deployments() {
+    let azure = AzureOptions::default()
+        .with_deployment("gpt-4", "gpt4-prod");
+    assert_eq!(azure.api_version(), DEFAULT_AZURE_API_VERSION);
+
+    // The endpoint of the resource is required
+    let options = ClientOptions::default()
+        .with_api_key(ApiKeySource::Value(String::from("key")))
+        .with_azure(azure.clone());
+    assert!(matches!(options.create_client().await, Err(RustGPTError::InvalidSetting(_))));
+
+    let client = options.with_api_base("https://example.openai.azure.com")
+        .create_client().await
+        .expect("azure client");
+    let ApiClient::Azure(clients) = client.as_ref() else {
+        panic!("expected an Azure client");
+    };
+    assert_eq!(clients.keys().collect::<Vec<_>>(), vec!["gpt-4"]);
+
+    // Models without a deployment fail before sending anything
+    let request = CreateEmbeddingRequest {
+        model: String::from("text-embedding-ada-002"),
+        ..Default::default()
+    };
+    assert!(matches!(client.embeddings(request).await, Err(RustGPTError::InvalidSetting(_))));
+
+    // Deployments are read from the configuration
+    let options: ClientOptions = serde_yaml::from_str("azure:\n  deployments:\n    gpt-4: gpt4-prod\n")
+        .expect("parse options");
+    assert_eq!(options.azure(), Some(&azure));
+}
+
+#[tokio::test]
+async fn ollama_clients() {
+    // The local server is used by default
+    let options: ClientOptions = serde_yaml::from_str("api_key: none\nollama:\n  default_model: llama2\n")
+        .expect("parse options");
+    assert_eq!(options.ollama().and_then(|o| o.default_model()), Some("llama2"));
+
+    let client = options.create_client().await.expect("create client");
+    assert!(matches!(client.as_ref(), ApiClient::Ollama(_)));
+}
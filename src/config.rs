@@ -0,0 +1,256 @@
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+use tokio::fs;
+
+use crate::client::ClientOptions;
+use crate::conversations::{ClientRef, CompletionModel, CompletionParametersBuilder};
+use crate::tui::TuiOptions;
+use crate::{Result, RustGPTError};
+
+/// Module with tests related to the configuration
+#[cfg(test)]
+mod tests;
+
+/// Environment variable with the path of the configuration file, overriding the default one
+pub const CONFIG_ENV: &str = "RGPT_CONFIG";
+
+/// Environment variables that override the values of the selected profile
+pub const PROFILE_ENV: &str = "RGPT_PROFILE";
+pub const MODEL_ENV: &str = "RGPT_MODEL";
+pub const TEMPERATURE_ENV: &str = "RGPT_TEMPERATURE";
+pub const MAX_TOKENS_ENV: &str = "RGPT_MAX_TOKENS";
+pub const N_ENV: &str = "RGPT_N";
+pub const API_BASE_ENV: &str = "OPENAI_API_BASE";
+pub const ORG_ID_ENV: &str = "OPENAI_ORG_ID";
+pub const WORKSPACE_ENV: &str = "RGPT_WORKSPACE";
+pub const SYSTEM_PROMPT_ENV: &str = "RGPT_SYSTEM_PROMPT";
+
+/// Name of the profile used when none is selected
+pub const DEFAULT_PROFILE: &str = "default";
+
+/// Settings of the application, stored as YAML in the user configuration directory.
+///
+/// # Examples
+///
+/// ```yaml
+/// default_profile: work
+/// profiles:
+///   work:
+///     model: gpt-4
+///     temperature: 0.2
+///     workspace: ~/conversations/work
+///   local:
+///     api_base: http://localhost:8080/v1
+///     api_key: none
+///     timeout_secs: 300
+///   offline:
+///     ollama:
+///       default_model: llama2
+/// tui:
+///   keymap:
+///     preset: vim
+///     chat:
+///       ctrl+s: send
+///       enter: null
+///   theme:
+///     user: LightBlue
+///     highlight: DarkGray
+/// ```
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+#[serde(default)]
+pub struct Config {
+    /// Profile used when none is selected, [DEFAULT_PROFILE] if not given
+    default_profile: Option<String>,
+    profiles: HashMap<String, Profile>,
+
+    /// Keymap and theme of the terminal interface
+    tui: TuiOptions,
+}
+
+/// Named group of settings. Every value is optional, missing values fall back to the built-in
+/// defaults.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+#[serde(default)]
+pub struct Profile {
+    model: Option<String>,
+    temperature: Option<f32>,
+    max_tokens: Option<u16>,
+    n: Option<u8>,
+    workspace: Option<PathBuf>,
+    system_prompt: Option<String>,
+
+    /// Settings of the connection to the API
+    #[serde(flatten)]
+    client: ClientOptions,
+}
+
+impl Config {
+    /// Returns the path of the configuration file: [CONFIG_ENV] if set, otherwise `rgpt/config.yaml`
+    /// within `$XDG_CONFIG_HOME` or `~/.config`.
+    pub fn default_path() -> Option<PathBuf> {
+        if let Ok(path) = std::env::var(CONFIG_ENV) {
+            return Some(PathBuf::from(path));
+        }
+
+        let config_dir = match std::env::var("XDG_CONFIG_HOME") {
+            Ok(dir) if !dir.is_empty() => PathBuf::from(dir),
+            _ => home_dir()?.join(".config"),
+        };
+
+        Some(config_dir.join("rgpt").join("config.yaml"))
+    }
+
+    /// Loads the configuration from its default path, or returns an empty one if the file doesn't
+    /// exist.
+    pub async fn load() -> Result<Self> {
+        match Self::default_path() {
+            Some(path) => Self::load_from(path).await,
+            None => Ok(Self::default()),
+        }
+    }
+
+    /// Loads the configuration from the given file, or returns an empty one if it doesn't exist.
+    pub async fn load_from<T>(path: T) -> Result<Self>
+    where
+        T: AsRef<Path>
+    {
+        if !fs::try_exists(path.as_ref()).await? {
+            return Ok(Self::default());
+        }
+
+        Ok(serde_yaml::from_slice(&fs::read(path).await?)?)
+    }
+
+    /// Returns the profile with the given name, or the default profile when None. The default
+    /// profile may not exist, in which case an empty profile is returned.
+    pub fn profile(&self, name: Option<&str>) -> Result<Profile> {
+        match name {
+            Some(name) => self.profiles.get(name)
+                .cloned()
+                .ok_or_else(|| RustGPTError::UnknownProfile(name.to_string())),
+            None => {
+                let name = self.default_profile.as_deref().unwrap_or(DEFAULT_PROFILE);
+                match self.profiles.get(name) {
+                    Some(profile) => Ok(profile.clone()),
+                    None if self.default_profile.is_some() => Err(RustGPTError::UnknownProfile(name.to_string())),
+                    None => Ok(Profile::default()),
+                }
+            }
+        }
+    }
+
+    pub fn tui(&self) -> &TuiOptions { &self.tui }
+
+    /// Returns the names of the profiles, sorted
+    pub fn profile_names(&self) -> Vec<&str> {
+        let mut names: Vec<&str> = self.profiles.keys().map(String::as_str).collect();
+        names.sort();
+        names
+    }
+}
+
+impl Profile {
+    pub fn model(&self) -> Option<&str> { self.model.as_deref() }
+    pub fn temperature(&self) -> Option<f32> { self.temperature }
+    pub fn max_tokens(&self) -> Option<u16> { self.max_tokens }
+    pub fn n(&self) -> Option<u8> { self.n }
+    pub fn workspace(&self) -> Option<&Path> { self.workspace.as_deref() }
+    pub fn system_prompt(&self) -> Option<&str> { self.system_prompt.as_deref() }
+    pub fn client_options(&self) -> &ClientOptions { &self.client }
+
+    /// Returns the profile with the values of the environment variables (e.g. [MODEL_ENV])
+    /// replacing its own ones.
+    pub fn with_env(self) -> Result<Self> {
+        self.with_variables(|name| std::env::var(name).ok())
+    }
+
+    /// Replaces the values of the profile with the variables found by the lookup function
+    fn with_variables<F>(mut self, lookup: F) -> Result<Self>
+    where
+        F: Fn(&str) -> Option<String>
+    {
+        let invalid = |name: &str, value: &str| RustGPTError::InvalidSetting(format!("{}={}", name, value));
+
+        if let Some(model) = lookup(MODEL_ENV) {
+            self.model = Some(model);
+        }
+        if let Some(value) = lookup(TEMPERATURE_ENV) {
+            self.temperature = Some(value.parse().map_err(|_| invalid(TEMPERATURE_ENV, &value))?);
+        }
+        if let Some(value) = lookup(MAX_TOKENS_ENV) {
+            self.max_tokens = Some(value.parse().map_err(|_| invalid(MAX_TOKENS_ENV, &value))?);
+        }
+        if let Some(value) = lookup(N_ENV) {
+            self.n = Some(value.parse().map_err(|_| invalid(N_ENV, &value))?);
+        }
+        if let Some(api_base) = lookup(API_BASE_ENV) {
+            self.client = self.client.with_api_base(&api_base);
+        }
+        if let Some(org_id) = lookup(ORG_ID_ENV) {
+            self.client = self.client.with_org_id(&org_id);
+        }
+        if let Some(workspace) = lookup(WORKSPACE_ENV) {
+            self.workspace = Some(PathBuf::from(workspace));
+        }
+        if let Some(system_prompt) = lookup(SYSTEM_PROMPT_ENV) {
+            self.system_prompt = Some(system_prompt);
+        }
+
+        Ok(self)
+    }
+
+    /// Returns a builder with the completion parameters of the profile, so other values can
+    /// still be changed before building.
+    pub fn parameters_builder(&self) -> Result<CompletionParametersBuilder> {
+        let mut builder = CompletionParametersBuilder::default();
+        if let Some(model) = &self.model {
+            builder.model(model.parse::<CompletionModel>()?);
+        }
+        if let Some(temperature) = self.temperature {
+            builder.temperature(temperature);
+        }
+        if let Some(max_tokens) = self.max_tokens {
+            builder.max_tokens(max_tokens);
+        }
+        if let Some(n) = self.n {
+            builder.n(n);
+        }
+
+        Ok(builder)
+    }
+
+    /// Replaces the settings of the connection to the API
+    pub fn with_client_options(mut self, options: ClientOptions) -> Self {
+        self.client = options;
+        self
+    }
+
+    /// Creates a client with the settings of the connection of the profile
+    pub async fn create_client(&self) -> Result<ClientRef> {
+        self.client.create_client().await
+    }
+
+    /// Returns the directory of the workspace, expanding a leading `~`, or `.` if not given
+    pub fn workspace_dir(&self) -> PathBuf {
+        match &self.workspace {
+            Some(workspace) => expand_home(workspace),
+            None => PathBuf::from("."),
+        }
+    }
+}
+
+fn home_dir() -> Option<PathBuf> {
+    std::env::var_os("HOME")
+        .filter(|home| !home.is_empty())
+        .map(PathBuf::from)
+}
+
+/// Replaces a leading `~` of the path with the home directory of the user
+fn expand_home(path: &Path) -> PathBuf {
+    match (path.strip_prefix("~"), home_dir()) {
+        (Ok(rest), Some(home)) => home.join(rest),
+        _ => path.to_path_buf(),
+    }
+}
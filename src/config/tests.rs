@@ -0,0 +1,105 @@
+use std::collections::HashMap;
+
+use crate::client::ApiKeySource;
+use crate::test_util::TempDirectoryHandler;
+
+use super::*;
+
+const CONFIG: &str = r#"
+default_profile: work
+profiles:
+  work:
+    model: gpt-4
+    temperature: 0.2
+    workspace: conversations/work
+    system_prompt: You review code
+  local:
+    api_base: http://localhost:8080/v1
+    api_key: none
+    headers:
+      X-Team: research
+    max_tokens: 1024
+"#;
+
+#[tokio::test]
+async fn load_profiles() {
+    let temp_dir = TempDirectoryHandler::build().expect("temp dir");
+
+    // Missing files are an empty configuration
+    let path = temp_dir.path().join("config.yaml");
+    let config = Config::load_from(&path).await.expect("missing config");
+    assert_eq!(config, Config::default());
+    assert_eq!(config.profile(None).expect("empty default profile"), Profile::default());
+
+    // Profiles by name, or the default one
+    fs::write(&path, CONFIG).await.expect("write config");
+    let config = Config::load_from(&path).await.expect("load config");
+    assert_eq!(config.profile_names(), vec!["local", "work"]);
+
+    let work = config.profile(None).expect("default profile");
+    assert_eq!(work.model(), Some("gpt-4"));
+    assert_eq!(work.workspace_dir(), PathBuf::from("conversations/work"));
+    assert_eq!(work.system_prompt(), Some("You review code"));
+
+    let local = config.profile(Some("local")).expect("local profile");
+    let options = local.client_options();
+    assert_eq!(options.api_base(), Some("http://localhost:8080/v1"));
+    assert_eq!(options.api_key(), &ApiKeySource::None);
+    assert_eq!(options.headers().get("X-Team").map(String::as_str), Some("research"));
+    assert_eq!(local.workspace_dir(), PathBuf::from("."));
+
+    assert!(matches!(config.profile(Some("missing")), Err(RustGPTError::UnknownProfile(_))));
+}
+
+#[test]
+fn parameters_precedence() {
+    let config: Config = serde_yaml::from_str(CONFIG).expect("parse config");
+    let profile = config.profile(Some("work")).expect("work profile");
+
+    // Profile values replace the defaults
+    let parameters = profile.parameters_builder().expect("builder")
+        .build().expect("parameters");
+    assert_eq!(parameters.model(), CompletionModel::GPT4);
+    assert_eq!(parameters.temperature(), 0.2);
+    assert_eq!(parameters.max_tokens(), 512);
+
+    // Environment values replace the profile ones
+    let variables = HashMap::from([(TEMPERATURE_ENV, "0.7"), (MAX_TOKENS_ENV, "100")]);
+    let profile = profile.with_variables(|name| variables.get(name).map(|v| v.to_string()))
+        .expect("profile with variables");
+    let parameters = profile.parameters_builder().expect("builder")
+        .build().expect("parameters");
+    assert_eq!(parameters.model(), CompletionModel::GPT4);
+    assert_eq!(parameters.temperature(), 0.7);
+    assert_eq!(parameters.max_tokens(), 100);
+
+    // Invalid values are reported
+    let result = Profile::default().with_variables(|name| (name == N_ENV).then(|| String::from("many")));
+    assert!(matches!(result, Err(RustGPTError::InvalidSetting(_))));
+}
+
+#[test]
+fn tui_options() {
+    use ratatui::style::Color;
+    use crate::tui::keymap::Preset;
+
+    let config: Config = serde_yaml::from_str(r#"
+tui:
+  keymap:
+    preset: vim
+  theme:
+    user: LightBlue
+    assistant: !Rgb [160, 224, 160]
+    highlight: DarkGray
+"#).expect("parse config");
+
+    assert_eq!(config.tui().keymap().preset(), Preset::Vim);
+    let theme = config.tui().theme();
+    assert_eq!(theme.role(&async_openai::types::Role::User).fg, Some(Color::LightBlue));
+    assert_eq!(theme.role(&async_openai::types::Role::Assistant).fg, Some(Color::Rgb(160, 224, 160)));
+    assert_eq!(theme.highlight().bg, Some(Color::DarkGray));
+
+    // Missing colors keep their defaults
+    assert_eq!(theme.muted(), crate::tui::theme::Theme::default().muted());
+    assert_eq!(Config::default().tui().keymap().preset(), Preset::Default);
+}
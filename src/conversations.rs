@@ -1,15 +1,20 @@
 use std::collections::{HashMap, HashSet, VecDeque};
-use std::path::PathBuf;
+use std::fmt::{Display, Formatter};
+use std::str::FromStr;
+use std::path::{Path, PathBuf};
 use std::sync::Arc;
 
-use async_openai::config::OpenAIConfig;
-use async_openai::types::{ChatCompletionRequestMessageArgs, CreateChatCompletionRequestArgs, Role};
+use async_openai::types::{ChatCompletionRequestMessageArgs, CreateChatCompletionRequest, CreateChatCompletionRequestArgs, Role};
+use chrono::{DateTime, Utc};
 use derive_builder::Builder;
+use futures::StreamExt;
 use serde::{Deserialize, Serialize};
 use tokio::fs;
 use uuid::Uuid;
 
-use crate::{Result, RustGPTError};
+use crate::{encryption, workspace, Result, RustGPTError};
+use crate::client::{ApiClient, OptionalKeyConfig};
+use crate::encryption::Secret;
 use crate::RustGPTError::BadMessage;
 
 /// Module with tests related to Conversations
@@ -26,14 +31,35 @@ pub enum CompletionModel {
     GPT4_32K,
 }
 
-impl ToString for CompletionModel {
-    fn to_string(&self) -> String {
-        match self {
+impl CompletionModel {
+    /// Every model, from the cheapest to the most capable
+    pub const ALL: [CompletionModel; 4] = [
+        CompletionModel::GPT35, CompletionModel::GPT35_16K, CompletionModel::GPT4, CompletionModel::GPT4_32K,
+    ];
+}
+
+impl Display for CompletionModel {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
             CompletionModel::GPT35 => "gpt-3.5-turbo",
             CompletionModel::GPT35_16K => "gpt-3.5-turbo-16k",
             CompletionModel::GPT4 => "gpt-4",
             CompletionModel::GPT4_32K => "gpt-4-32k",
-        }.to_string()
+        })
+    }
+}
+
+impl FromStr for CompletionModel {
+    type Err = RustGPTError;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "gpt-3.5-turbo" => Ok(CompletionModel::GPT35),
+            "gpt-3.5-turbo-16k" => Ok(CompletionModel::GPT35_16K),
+            "gpt-4" => Ok(CompletionModel::GPT4),
+            "gpt-4-32k" => Ok(CompletionModel::GPT4_32K),
+            _ => Err(RustGPTError::UnknownModel(s.to_string())),
+        }
     }
 }
 
@@ -85,19 +111,48 @@ impl CompletionParameters {
     }
 }
 
+impl From<&CompletionParameters> for CompletionParametersBuilder {
+    /// Returns a builder initialized with the given parameters, for changing some of them
+    fn from(parameters: &CompletionParameters) -> Self {
+        CompletionParametersBuilder {
+            temperature: Some(parameters.temperature),
+            n: Some(parameters.n),
+            model: Some(parameters.model),
+            max_tokens: Some(parameters.max_tokens),
+        }
+    }
+}
+
 impl CompletionParametersBuilder {
     /// Validates if the completion parameters are ok
     fn validate(&self) -> core::result::Result<(), String> {
-        // Temperature 0.0 <= x <= 2.0
         if let Some(temperature) = self.temperature {
-            match temperature {
-                i if i < 0.0 => Err("Temperature must be >0.0".to_string()),
-                i if i > 2.0 => Err("Temperature must be <2.0".to_string()),
-                _ => Ok(())
-            }
-        } else {
-            Ok(())
+            Self::validate_temperature(temperature)?;
+        }
+        if let Some(n) = self.n {
+            Self::validate_n(n)?;
+        }
+
+        Ok(())
+    }
+
+    /// Validates that the temperature is a number within 0.0 <= x <= 2.0
+    pub(crate) fn validate_temperature(temperature: f32) -> core::result::Result<(), String> {
+        match temperature {
+            i if i.is_nan() => Err("Temperature must be a number".to_string()),
+            i if i < 0.0 => Err("Temperature must be >0.0".to_string()),
+            i if i > 2.0 => Err("Temperature must be <2.0".to_string()),
+            _ => Ok(())
+        }
+    }
+
+    /// Validates that at least one completion is requested
+    pub(crate) fn validate_n(n: u8) -> core::result::Result<(), String> {
+        if n == 0 {
+            return Err("At least one completion must be requested".to_string());
         }
+
+        Ok(())
     }
 }
 
@@ -118,6 +173,18 @@ pub struct Message {
 
     /// Actual message
     content: String,
+
+    /// Moment when the message was created
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    created: Option<DateTime<Utc>>,
+
+    /// Model that generated the message, only available for completions
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    model: Option<String>,
+
+    /// Marks the message as a preferred response, e.g. for building datasets
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    preferred: bool,
 }
 
 
@@ -132,14 +199,7 @@ impl Message {
     ///
     /// returns: Message
     fn build(role: Role, content: String, parent_id: Option<Uuid>, sibling: Option<&Message>) -> Result<Self> {
-        // Check content
-        if content.is_empty() {
-            return Err(BadMessage("Message must have a content".to_string()));
-        }
-
-        if parent_id.is_none() && role != Role::System {
-            return Err(BadMessage("Parent can only be None when the role is System".to_string()));
-        }
+        Self::validate(&role, &content, parent_id)?;
 
         // Check sibling
         let index = match sibling {
@@ -154,12 +214,52 @@ impl Message {
             index,
             role,
             content,
+            created: Some(Utc::now()),
+            model: None,
+            preferred: false,
+        })
+    }
+
+    /// Checks the details shared by every message: it must have a content, and only System
+    /// messages can lack a parent.
+    fn validate(role: &Role, content: &str, parent_id: Option<Uuid>) -> Result<()> {
+        if content.is_empty() {
+            return Err(BadMessage("Message must have a content".to_string()));
+        }
+
+        if parent_id.is_none() && *role != Role::System {
+            return Err(BadMessage("Parent can only be None when the role is System".to_string()));
+        }
+
+        Ok(())
+    }
+
+    /// Recreates a message with all of its details, as when importing it from another source.
+    /// The same validations as [Message::build] are applied.
+    pub(crate) fn restore(id: Uuid, parent_id: Option<Uuid>, index: u8, role: Role, content: String,
+                          created: Option<DateTime<Utc>>, model: Option<String>) -> Result<Self> {
+        Self::validate(&role, &content, parent_id)?;
+
+        Ok(Message {
+            id,
+            parent_id,
+            index,
+            role,
+            content,
+            created,
+            model,
+            preferred: false,
         })
     }
+
     pub fn index(&self) -> u8 { self.index }
+    pub fn parent_id(&self) -> Option<Uuid> { self.parent_id }
     pub fn role(&self) -> &Role { &self.role }
     pub fn content(&self) -> &String { &self.content }
     pub fn id(&self) -> Uuid { self.id }
+    pub fn created(&self) -> Option<DateTime<Utc>> { self.created }
+    pub fn model(&self) -> Option<&str> { self.model.as_deref() }
+    pub fn is_preferred(&self) -> bool { self.preferred }
 }
 
 /// Represents a Conversation with OpenAI, with initial parameters and
@@ -175,6 +275,10 @@ pub struct Conversation {
     /// Path to where the file is stored
     #[serde(skip)]
     path: PathBuf,
+
+    /// Secret used for encrypting the file, if it's stored encrypted
+    #[serde(skip)]
+    secret: Option<Secret>,
 }
 
 impl Conversation {
@@ -186,7 +290,7 @@ impl Conversation {
     /// * `parameters`: Conversation parameters
     /// * `path`: Path to where the `Conversation` is being stored.
     /// * `system_message`: Starting message for the conversation (given to the "System"). Cannot
-    /// be emtpy.
+    ///   be emtpy.
     ///
     /// returns: Conversation
     ///
@@ -221,6 +325,38 @@ impl Conversation {
             interactions,
             path,
             name: String::new(),
+            secret: None,
+        })
+    }
+
+    /// Creates a Conversation from an already existing set of messages, as when importing it from
+    /// another source. There must be a single root message and every parent must be a part of
+    /// the given messages.
+    pub(crate) fn from_messages(parameters: CompletionParameters, path: PathBuf, name: String,
+                                messages: Vec<Message>) -> Result<Self> {
+        let interactions: HashMap<Uuid, Message> = messages.into_iter()
+            .map(|msg| (msg.id, msg))
+            .collect();
+
+        let roots = interactions.values()
+            .filter(|msg| msg.parent_id.is_none())
+            .count();
+        if roots != 1 {
+            return Err(BadMessage(format!("Conversation must have a single root message, found {}", roots)));
+        }
+
+        if interactions.values()
+            .filter_map(|msg| msg.parent_id)
+            .any(|parent_id| !interactions.contains_key(&parent_id)) {
+            return Err(RustGPTError::MessageNotPartOfConversation);
+        }
+
+        Ok(Conversation {
+            default_parameters: parameters,
+            interactions,
+            name,
+            path,
+            secret: None,
         })
     }
 
@@ -246,12 +382,13 @@ impl Conversation {
     pub fn get_latest_messages(&self) -> Vec<&Message> {
         // Gather all the IDs that are a parent of another message
         let mut parents = HashSet::new();
-        parents.extend(self.interactions.iter()
-            .filter_map(|(_, m)| m.parent_id));
+        parents.extend(self.interactions.values()
+            .filter_map(|m| m.parent_id));
 
-        // Find all the messages that are not the parent of another message
-        self.interactions.iter()
-            .filter_map(|(id, m)| if !parents.contains(id) { Some(m) } else { None })
+        // Find all the messages that are not the parent of another message, in depth-first order
+        // so the order is stable between loads
+        self.iter()
+            .filter(|m| !parents.contains(&m.id))
             .collect()
     }
 
@@ -300,30 +437,55 @@ impl Conversation {
     }
 
     /// Returns the root system message
-    fn get_root_message(&self) -> &Message {
-        self.interactions.iter()
-            .filter_map(|(_, msg)| match msg.parent_id {
-                Some(_) => None,
-                _ => Some(msg)
-            })
-            .next()
+    pub fn get_root_message(&self) -> &Message {
+        self.interactions.values()
+            .find(|msg| msg.parent_id.is_none())
             .expect("there should always be a root message")
     }
 
-    /// Returns all children of a given message
-    fn get_children(&self, parent_message_id: Uuid) -> Vec<&Message> {
-        let mut ret = self.interactions.iter()
-            .filter_map(|(_, msg)|
-                match msg.parent_id {
-                    Some(parent_id) if parent_id == parent_message_id => Some(msg),
-                    _ => None
-                })
+    /// Returns all children of a given message, sorted by their sibling index
+    pub fn get_children(&self, parent_message_id: Uuid) -> Vec<&Message> {
+        let mut ret = self.interactions.values()
+            .filter(|msg| msg.parent_id == Some(parent_message_id))
             .collect::<Vec<&Message>>();
 
         ret.sort_by_key(|msg| msg.index);
         ret
     }
 
+    /// Returns the children of every message with any, sorted by their sibling index. Cheaper
+    /// than calling [Conversation::get_children] for each message of the conversation.
+    pub fn get_children_map(&self) -> HashMap<Uuid, Vec<&Message>> {
+        let mut children: HashMap<Uuid, Vec<&Message>> = HashMap::new();
+        for msg in self.interactions.values() {
+            if let Some(parent_id) = msg.parent_id {
+                children.entry(parent_id).or_default().push(msg);
+            }
+        }
+
+        for siblings in children.values_mut() {
+            siblings.sort_by_key(|msg| msg.index);
+        }
+        children
+    }
+
+    /// Marks or unmarks a message as preferred
+    ///
+    /// # Arguments
+    ///
+    /// * `message_id`: Message to mark
+    /// * `preferred`: New value of the mark
+    ///
+    /// returns: Result<(), RustGPTError>
+    pub fn set_preferred(&mut self, message_id: Uuid, preferred: bool) -> Result<()> {
+        let Some(message) = self.interactions.get_mut(&message_id) else {
+            return Err(RustGPTError::MessageNotPartOfConversation);
+        };
+
+        message.preferred = preferred;
+        Ok(())
+    }
+
     /// Returns the siblings of a message, including the calling message
     pub fn get_message_siblings(&self, message_id: Uuid) -> Result<Vec<&Message>> {
         // Validate message id
@@ -365,9 +527,30 @@ impl Conversation {
             .collect())
     }
 
+    /// Returns the message a new query written after the given one is added to: the message
+    /// itself, or the parent of an unanswered query (e.g. after a failed completion), so the new
+    /// query becomes a sibling of it.
+    ///
+    /// # Arguments
+    ///
+    /// * `message_id`: Last message of the branch being continued
+    ///
+    /// returns: Result<Uuid, RustGPTError> Parent for [Conversation::add_queries]
+    pub fn query_parent(&self, message_id: Uuid) -> Result<Uuid> {
+        let Some(message) = self.interactions.get(&message_id) else {
+            return Err(RustGPTError::MessageNotPartOfConversation);
+        };
+
+        match (&message.role, message.parent_id) {
+            (Role::User, Some(parent_id)) => Ok(parent_id),
+            (Role::User, None) => Err(RustGPTError::InvalidMessageRole),
+            _ => Ok(message_id),
+        }
+    }
+
     /// Adds children to the given parent message. Validations is expected to have
     /// happened for message roles.
-    fn add_children_to_message(&mut self, parent_id: Uuid, messages: Vec<String>, role: Role) -> Result<Vec<Uuid>> {
+    pub(crate) fn add_children_to_message(&mut self, parent_id: Uuid, messages: Vec<String>, role: Role) -> Result<Vec<Uuid>> {
         // Get oldest sibling, if any
         let mut oldest_sibling = match self.get_children(parent_id).last() {
             Some(&sibling) => Some(sibling),
@@ -404,7 +587,96 @@ impl Conversation {
     /// Performs completions for the given message id
     pub async fn do_completion(&mut self, message_id: Uuid, client: ClientRef, n_completions: Option<u8>)
                                -> Result<Vec<&Message>> {
+        let parameters = self.parameters_with_n(n_completions);
+        let completion_request = self.completion_request(message_id, &parameters)?;
+        let model = client.answering_model(&completion_request.model);
 
+        // Perform the completion request
+        let completion = client.chat(completion_request).await?;
+        let responses: Vec<_> = completion.choices.into_iter()
+            .filter_map(|choice| choice.message.content)
+            .collect();
+
+        self.add_completions(message_id, responses, model)
+    }
+
+    /// Performs completions for the given message id, streaming the responses as they are
+    /// generated. The callback receives the index of the completion (starting at 0) and each
+    /// new fragment of its content.
+    ///
+    /// # Arguments
+    ///
+    /// * `message_id`: User message to complete
+    /// * `client`: Client for the completion
+    /// * `n_completions`: Number of completions, or the default ones if None
+    /// * `on_delta`: Called with every received fragment
+    ///
+    /// returns: Result<Vec<&Message, Global>, RustGPTError> Added messages
+    pub async fn do_completion_streamed<F>(&mut self, message_id: Uuid, client: ClientRef, n_completions: Option<u8>,
+                                           on_delta: F) -> Result<Vec<&Message>>
+    where
+        F: FnMut(usize, &str)
+    {
+        let mut completion = self.prepare_completion(message_id, n_completions)?;
+        let responses = completion.stream(client, on_delta).await?;
+
+        self.finish_completion(&completion, responses)
+    }
+
+    /// Prepares the completion of the given message, so it can be performed without borrowing
+    /// the conversation (e.g. in a background task). The responses are added afterwards with
+    /// [Conversation::finish_completion].
+    ///
+    /// # Arguments
+    ///
+    /// * `message_id`: User message to complete
+    /// * `n_completions`: Number of completions, or the default ones if None
+    ///
+    /// returns: Result<PendingCompletion, RustGPTError>
+    pub fn prepare_completion(&self, message_id: Uuid, n_completions: Option<u8>) -> Result<PendingCompletion> {
+        self.prepare_completion_with(message_id, self.parameters_with_n(n_completions))
+    }
+
+    /// Prepares the completion of the given message with other parameters than the default
+    /// ones, e.g. for trying another model once.
+    ///
+    /// # Arguments
+    ///
+    /// * `message_id`: User message to complete
+    /// * `parameters`: Parameters of this completion only
+    ///
+    /// returns: Result<PendingCompletion, RustGPTError>
+    pub fn prepare_completion_with(&self, message_id: Uuid, parameters: CompletionParameters)
+                                   -> Result<PendingCompletion> {
+        let request = self.completion_request(message_id, &parameters)?;
+
+        Ok(PendingCompletion {
+            message_id,
+            request,
+            parameters,
+            model: None,
+        })
+    }
+
+    /// Adds the responses of a prepared completion as children of its message
+    pub fn finish_completion(&mut self, completion: &PendingCompletion, responses: Vec<String>)
+                             -> Result<Vec<&Message>> {
+        let model = completion.model.clone()
+            .unwrap_or_else(|| completion.parameters.model.to_string());
+        self.add_completions(completion.message_id, responses, model)
+    }
+
+    /// Returns the default parameters, with the given number of completions if any
+    fn parameters_with_n(&self, n_completions: Option<u8>) -> CompletionParameters {
+        match n_completions {
+            Some(n) => self.default_parameters.with_n(n),
+            None => self.default_parameters.clone(),
+        }
+    }
+
+    /// Creates the request for completing the given message with the parameters
+    fn completion_request(&self, message_id: Uuid, parameters: &CompletionParameters)
+                          -> Result<CreateChatCompletionRequest> {
         // Validate that the given message is a user message
         let Some(message) = self.interactions.get(&message_id) else {
             return Err(RustGPTError::MessageNotPartOfConversation);
@@ -433,12 +705,6 @@ impl Conversation {
         messages.reverse();
 
         // Create the completions with the client
-        let parameters = if let Some(n) = n_completions {
-            self.default_parameters.with_n(n)
-        } else {
-            self.default_parameters.clone()
-        };
-
         let completion_request = CreateChatCompletionRequestArgs::default()
             .n(parameters.n)
             .model(parameters.model.to_string())
@@ -450,14 +716,22 @@ impl Conversation {
                 .collect::<Vec<_>>())
             .build()?;
 
-        // Perform the completion request
-        let completion = client.chat().create(completion_request).await?;
-        let responses: Vec<_> = completion.choices.into_iter()
-            .filter_map(|choice| choice.message.content)
-            .collect();
+        Ok(completion_request)
+    }
 
+    /// Adds the responses of a completion as children of the given message, generated by the
+    /// given model
+    fn add_completions(&mut self, message_id: Uuid, responses: Vec<String>, model: String)
+                       -> Result<Vec<&Message>> {
         let added_id = self.add_children_to_message(message_id, responses, Role::Assistant)?;
 
+        // Keep track of the model that generated the responses
+        for id in added_id.iter() {
+            if let Some(msg) = self.interactions.get_mut(id) {
+                msg.model = Some(model.clone());
+            }
+        }
+
         Ok(added_id.into_iter()
             .filter_map(|id| self.interactions.get(&id))
             .collect())
@@ -468,6 +742,21 @@ impl Conversation {
         &self.name
     }
 
+    /// Returns the parameters used for completions when none are specified
+    pub fn default_parameters(&self) -> &CompletionParameters {
+        &self.default_parameters
+    }
+
+    /// Changes the parameters used for completions when none are specified
+    pub fn set_default_parameters(&mut self, parameters: CompletionParameters) {
+        self.default_parameters = parameters;
+    }
+
+    /// Returns the path where the conversation is stored
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
 
     /// Sets a new name for the conversation
     ///
@@ -484,18 +773,35 @@ impl Conversation {
         name
     }
 
-    /// Tries to save the conversation to disk
+    /// Returns true if the conversation is stored encrypted
+    pub fn is_encrypted(&self) -> bool {
+        self.secret.is_some()
+    }
+
+    /// Sets the secret used for encrypting the conversation when it's saved. With `None` the
+    /// conversation is saved as plain text.
+    ///
+    /// # Arguments
+    ///
+    /// * `secret`: Secret for the encryption
+    pub fn set_secret(&mut self, secret: Option<Secret>) {
+        self.secret = secret;
+    }
+
+    /// Tries to save the conversation to disk, encrypting it if it has a secret
     pub async fn save(&self) -> Result<()>{
         // Serialize
         let data = serde_yaml::to_string(self)?;
+        let data = match &self.secret {
+            Some(secret) => encryption::encrypt(data.as_bytes(), secret)?,
+            None => data.into_bytes(),
+        };
 
-        // Save to the path
-        fs::write(&self.path, data.as_bytes()).await?;
-
-        Ok(())
+        workspace::write_atomically(&self.path, &data).await
     }
 
-    /// Tries to load a conversation from disk
+    /// Tries to load a conversation from disk. Encrypted conversations are decrypted with the
+    /// secret given through the environment (see [Secret::from_env]).
     ///
     /// # Arguments
     ///
@@ -506,19 +812,59 @@ impl Conversation {
     where
         T: Into<PathBuf>
     {
-        // Load file
         let path: PathBuf = path.into();
-        let data = fs::read_to_string(&path).await?;
+        let data = fs::read(&path).await?;
 
-        // Deserialize conversation
-        let mut conversation: Self = serde_yaml::from_str(&data)?;
+        let secret = if encryption::is_encrypted(&data) {
+            Secret::from_env().await?
+        } else {
+            None
+        };
+
+        Self::from_data(path, &data, secret.as_ref())
+    }
+
+    /// Tries to load a conversation from disk, using the given secret if it's encrypted
+    ///
+    /// # Arguments
+    ///
+    /// * `path`: Path to the conversation
+    /// * `secret`: Secret used for decrypting the conversation
+    ///
+    /// returns: Result<Conversation, RustGPTError>
+    pub async fn load_with_secret<T>(path: T, secret: Option<&Secret>) -> Result<Self>
+    where
+        T: Into<PathBuf>
+    {
+        let path: PathBuf = path.into();
+        let data = fs::read(&path).await?;
+
+        Self::from_data(path, &data, secret)
+    }
+
+    /// Deserializes a conversation from the contents of its file
+    pub(crate) fn from_data(path: PathBuf, data: &[u8], secret: Option<&Secret>) -> Result<Self> {
+        let mut conversation: Self = if encryption::is_encrypted(data) {
+            let Some(secret) = secret else {
+                return Err(RustGPTError::MissingSecret(path.display().to_string()));
+            };
+
+            serde_yaml::from_slice(&encryption::decrypt(data, secret)?)?
+        } else {
+            serde_yaml::from_slice(data)?
+        };
+
+        // Keep encrypted conversations encrypted when saving them again
+        if encryption::is_encrypted(data) {
+            conversation.secret = secret.cloned();
+        }
         conversation.path = path;
 
         Ok(conversation)
     }
 
     /// Returns a depth-first iterator of the conversation
-    pub fn iter(&self) -> ConversationIter{
+    pub fn iter(&self) -> ConversationIter<'_>{
         let mut current_stack = VecDeque::new();
         current_stack.push_front(self.get_root_message());
         
@@ -529,11 +875,65 @@ impl Conversation {
     }
 }
 
-type ClientRef = Arc<async_openai::Client<OpenAIConfig>>;
+/// Completion of a message prepared with [Conversation::prepare_completion]
+#[derive(Debug, Clone)]
+pub struct PendingCompletion {
+    message_id: Uuid,
+    request: CreateChatCompletionRequest,
+    parameters: CompletionParameters,
+
+    /// Model that generated the responses, known once the completion is performed. It may not
+    /// be the one of the parameters, e.g. the local model of Ollama.
+    model: Option<String>,
+}
+
+impl PendingCompletion {
+    /// Returns the message being completed
+    pub fn message_id(&self) -> Uuid { self.message_id }
+    pub fn parameters(&self) -> &CompletionParameters { &self.parameters }
+
+    /// Performs the completion, streaming the responses as they are generated. The callback
+    /// receives the index of the completion (starting at 0) and each new fragment of its content.
+    ///
+    /// returns: Result<Vec<String, Global>, RustGPTError> Content of each non-empty response
+    pub async fn stream<F>(&mut self, client: ClientRef, mut on_delta: F) -> Result<Vec<String>>
+    where
+        F: FnMut(usize, &str)
+    {
+        self.model = Some(client.answering_model(&self.request.model));
+
+        // Gather the fragments of each completion
+        let mut responses = vec![String::new(); self.parameters.n as usize];
+        let mut stream = client.chat_stream(self.request.clone()).await?;
+        while let Some(response) = stream.next().await {
+            for choice in response?.choices {
+                let Some(content) = choice.delta.content else {
+                    continue;
+                };
+
+                let index = choice.index as usize;
+                if index >= responses.len() {
+                    responses.resize(index + 1, String::new());
+                }
+
+                on_delta(index, &content);
+                responses[index].push_str(&content);
+            }
+        }
+
+        Ok(responses.into_iter()
+            .filter(|r| !r.is_empty())
+            .collect())
+    }
+}
+
+/// Shared reference to an OpenAI client
+pub type ClientRef = Arc<ApiClient>;
 
 /// Creates a new chat client
 pub fn create_chat_client() -> ClientRef{
-    Arc::new(async_openai::Client::new())
+    let config = OptionalKeyConfig::new(async_openai::config::OpenAIConfig::default());
+    Arc::new(ApiClient::OpenAI(async_openai::Client::with_config(config)))
 }
 
 /// Allows depth first iteration over a conversation
@@ -546,9 +946,7 @@ impl<'a> Iterator for ConversationIter<'a>{
     type Item = &'a Message;
 
     fn next(&mut self) -> Option<Self::Item> {
-        let Some(current_message) = self.current_stack.pop_front() else {
-            return None
-        };
+        let current_message = self.current_stack.pop_front()?;
 
         // Get children
         let current_id = current_message.id;
@@ -1,3 +1,5 @@
+use crate::client::ClientOptions;
+use crate::client::ollama::OllamaOptions;
 use crate::test_util::TempDirectoryHandler;
 
 use super::*;
@@ -176,4 +178,109 @@ async fn conversation_iter() {
     for (expected, message) in expected_content.iter().zip(conversation.iter()){
         assert_eq!(expected, &message.content, "Expected {} in message {:?}", expected, message);
     }
-}
\ No newline at end of file
+
+    // The map of children agrees with the children of each message
+    let children = conversation.get_children_map();
+    assert_eq!(children.len(), 4);
+    for message in conversation.iter() {
+        let expected = conversation.get_children(message.id);
+        assert_eq!(children.get(&message.id).cloned().unwrap_or_default(), expected);
+    }
+}
+
+#[test]
+fn parameters_builder_from_parameters() {
+    let parameters = CompletionParametersBuilder::default()
+        .temperature(0.5)
+        .max_tokens(100)
+        .build().expect("parameters");
+
+    // Unchanged values are kept
+    let changed = CompletionParametersBuilder::from(&parameters)
+        .n(3)
+        .build().expect("changed parameters");
+    assert_eq!(changed.temperature(), 0.5);
+    assert_eq!(changed.max_tokens(), 100);
+    assert_eq!(changed.n(), 3);
+    assert_eq!(changed.model(), parameters.model());
+
+    // Changes are still validated
+    assert!(CompletionParametersBuilder::from(&parameters).temperature(3.0).build().is_err());
+    assert!(CompletionParametersBuilder::from(&parameters).temperature(f32::NAN).build().is_err());
+    assert!(CompletionParametersBuilder::from(&parameters).n(0).build().is_err());
+}
+
+#[test]
+fn prepare_completion_parameters() {
+    let parameters = CompletionParametersBuilder::default().build().expect("parameters");
+    let mut conversation = Conversation::build(parameters.clone(), PathBuf::from("test.yaml"), "System")
+        .expect("conversation");
+    let root_id = conversation.get_root_message().id();
+    let query_id = conversation.add_queries(root_id, vec![String::from("Query")]).expect("query")[0].id();
+
+    // Default parameters, with the given number of completions
+    let completion = conversation.prepare_completion(query_id, Some(3)).expect("default completion");
+    assert_eq!(completion.parameters(), &parameters.with_n(3));
+
+    // Other parameters, leaving the default ones unchanged
+    let other = CompletionParametersBuilder::default()
+        .model(CompletionModel::GPT4)
+        .temperature(0.2)
+        .build().expect("other parameters");
+    let completion = conversation.prepare_completion_with(query_id, other.clone()).expect("other completion");
+    assert_eq!(completion.parameters(), &other);
+    assert_eq!(completion.request.model, "gpt-4");
+    assert_eq!(conversation.default_parameters(), &parameters);
+
+    assert!(conversation.prepare_completion_with(root_id, other).is_err());
+}
+
+#[tokio::test]
+async fn answering_model() {
+    let parameters = CompletionParametersBuilder::default().build().expect("parameters");
+    let mut conversation = Conversation::build(parameters, PathBuf::from("test.yaml"), "System")
+        .expect("conversation");
+    let root_id = conversation.get_root_message().id();
+    let query_id = conversation.add_queries(root_id, vec![String::from("Query")]).expect("query")[0].id();
+
+    // Nothing listens on the address, but the client already knows which model answers
+    let client = ClientOptions::default()
+        .with_api_base("http://127.0.0.1:9")
+        .with_ollama(OllamaOptions::default().with_default_model("llama2"))
+        .create_client().await
+        .expect("client");
+    let mut completion = conversation.prepare_completion(query_id, None).expect("completion");
+    assert!(completion.stream(client, |_, _| {}).await.is_err());
+
+    let responses = conversation.finish_completion(&completion, vec![String::from("Response")])
+        .expect("responses");
+    assert_eq!(responses[0].model(), Some("llama2"));
+}
+
+#[test]
+fn message_validation() {
+    let parent = Some(Uuid::new_v4());
+
+    // Created and restored messages are validated alike
+    for (role, content, parent_id) in [(Role::User, "", parent), (Role::User, "Query", None)] {
+        assert!(Message::build(role.clone(), content.to_string(), parent_id, None).is_err());
+        assert!(Message::restore(Uuid::new_v4(), parent_id, 1, role, content.to_string(), None, None).is_err());
+    }
+
+    assert!(Message::build(Role::System, String::from("System"), None, None).is_ok());
+    assert!(Message::restore(Uuid::new_v4(), parent, 2, Role::User, String::from("Query"), None, None).is_ok());
+}
+
+#[test]
+fn query_parent() {
+    let parameters = CompletionParametersBuilder::default().build().expect("parameters");
+    let mut conversation = Conversation::build(parameters, PathBuf::from("test.yaml"), "System")
+        .expect("conversation");
+    let root_id = conversation.get_root_message().id();
+    let query_id = conversation.add_queries(root_id, vec![String::from("Query")]).expect("query")[0].id();
+
+    // Answered messages are continued, unanswered queries are replaced by a sibling
+    assert_eq!(conversation.query_parent(root_id).expect("root"), root_id);
+    assert_eq!(conversation.query_parent(query_id).expect("query"), root_id);
+    assert!(conversation.query_parent(Uuid::new_v4()).is_err());
+}
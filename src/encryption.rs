@@ -0,0 +1,154 @@
+use std::fmt::{Debug, Formatter};
+use std::path::Path;
+
+use argon2::Argon2;
+use chacha20poly1305::aead::{Aead, KeyInit};
+use chacha20poly1305::{ChaCha20Poly1305, Key, Nonce};
+use rand::RngCore;
+use tokio::fs;
+use tokio::io::AsyncReadExt;
+
+use crate::{Result, RustGPTError};
+
+/// Module with tests related to encryption
+#[cfg(test)]
+mod tests;
+
+/// Environment variable with the passphrase used for encrypted conversations
+pub const PASSPHRASE_ENV: &str = "RGPT_PASSPHRASE";
+
+/// Environment variable with the path to a keyfile used for encrypted conversations
+pub const KEYFILE_ENV: &str = "RGPT_KEYFILE";
+
+/// Bytes at the start of every encrypted file
+const MAGIC: &[u8; 8] = b"RGPTENC1";
+const SALT_LEN: usize = 16;
+const NONCE_LEN: usize = 12;
+const HEADER_LEN: usize = MAGIC.len() + SALT_LEN + NONCE_LEN;
+
+/// Secret from which the encryption keys are derived. Every encrypted file uses its own random
+/// salt, so the same secret produces a different key for each file.
+#[derive(Clone, PartialEq)]
+pub struct Secret {
+    material: Vec<u8>,
+}
+
+impl Secret {
+    /// Creates a secret from a passphrase
+    pub fn from_passphrase(passphrase: &str) -> Result<Self> {
+        if passphrase.is_empty() {
+            return Err(RustGPTError::Encryption(String::from("passphrase cannot be empty")));
+        }
+
+        Ok(Secret { material: passphrase.as_bytes().to_vec() })
+    }
+
+    /// Creates a secret from the contents of a keyfile
+    pub async fn from_keyfile<T>(path: T) -> Result<Self>
+    where
+        T: AsRef<Path>
+    {
+        let material = fs::read(path).await?;
+        if material.is_empty() {
+            return Err(RustGPTError::Encryption(String::from("keyfile cannot be empty")));
+        }
+
+        Ok(Secret { material })
+    }
+
+    /// Creates a secret from the [KEYFILE_ENV] or [PASSPHRASE_ENV] environment variables (in that
+    /// order), if any of them is set.
+    pub async fn from_env() -> Result<Option<Self>> {
+        if let Ok(path) = std::env::var(KEYFILE_ENV) {
+            return Ok(Some(Self::from_keyfile(path).await?));
+        }
+
+        match std::env::var(PASSPHRASE_ENV) {
+            Ok(passphrase) => Ok(Some(Self::from_passphrase(&passphrase)?)),
+            Err(_) => Ok(None),
+        }
+    }
+
+    /// Derives the key for a file with the given salt
+    fn derive_key(&self, salt: &[u8]) -> Result<Key> {
+        let mut key = Key::default();
+        Argon2::default()
+            .hash_password_into(&self.material, salt, &mut key)
+            .map_err(|e| RustGPTError::Encryption(e.to_string()))?;
+
+        Ok(key)
+    }
+}
+
+impl Debug for Secret {
+    /// Never shows the secret material
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.write_str("Secret(..)")
+    }
+}
+
+/// Returns true if the data has been encrypted with [encrypt]
+pub fn is_encrypted(data: &[u8]) -> bool {
+    data.starts_with(MAGIC)
+}
+
+/// Returns true if the file has been encrypted with [encrypt], reading only its header
+pub async fn is_encrypted_file(path: &Path) -> Result<bool> {
+    let mut header = Vec::with_capacity(MAGIC.len());
+    fs::File::open(path).await?
+        .take(MAGIC.len() as u64)
+        .read_to_end(&mut header).await?;
+
+    Ok(is_encrypted(&header))
+}
+
+/// Encrypts the data with a key derived from the secret. The result contains a header with the
+/// random salt and nonce, followed by the authenticated ciphertext.
+///
+/// # Arguments
+///
+/// * `plaintext`: Data to encrypt
+/// * `secret`: Secret from which the key is derived
+///
+/// returns: Result<Vec<u8, Global>, RustGPTError>
+pub fn encrypt(plaintext: &[u8], secret: &Secret) -> Result<Vec<u8>> {
+    let mut salt = [0u8; SALT_LEN];
+    let mut nonce = [0u8; NONCE_LEN];
+    let mut rng = rand::thread_rng();
+    rng.fill_bytes(&mut salt);
+    rng.fill_bytes(&mut nonce);
+
+    let cipher = ChaCha20Poly1305::new(&secret.derive_key(&salt)?);
+    let ciphertext = cipher.encrypt(Nonce::from_slice(&nonce), plaintext)
+        .map_err(|_| RustGPTError::Encryption(String::from("couldn't encrypt data")))?;
+
+    let mut data = Vec::with_capacity(HEADER_LEN + ciphertext.len());
+    data.extend_from_slice(MAGIC);
+    data.extend_from_slice(&salt);
+    data.extend_from_slice(&nonce);
+    data.extend_from_slice(&ciphertext);
+
+    Ok(data)
+}
+
+/// Decrypts data created with [encrypt]. Fails if the secret is not the one used for encrypting
+/// or if the data has been tampered with.
+///
+/// # Arguments
+///
+/// * `data`: Encrypted data, including the header
+/// * `secret`: Secret from which the key is derived
+///
+/// returns: Result<Vec<u8, Global>, RustGPTError>
+pub fn decrypt(data: &[u8], secret: &Secret) -> Result<Vec<u8>> {
+    if !is_encrypted(data) || data.len() < HEADER_LEN {
+        return Err(RustGPTError::Encryption(String::from("data is not encrypted")));
+    }
+
+    let salt = &data[MAGIC.len()..MAGIC.len() + SALT_LEN];
+    let nonce = &data[MAGIC.len() + SALT_LEN..HEADER_LEN];
+
+    let cipher = ChaCha20Poly1305::new(&secret.derive_key(salt)?);
+    cipher.decrypt(Nonce::from_slice(nonce), &data[HEADER_LEN..])
+        .map_err(|_| RustGPTError::Encryption(String::from("wrong secret or corrupted data")))
+}
@@ -0,0 +1,58 @@
+use crate::conversations::{CompletionParametersBuilder, Conversation};
+use crate::test_util::TempDirectoryHandler;
+
+use super::*;
+
+#[test]
+fn encryption_roundtrip() {
+    let secret = Secret::from_passphrase("correct horse").expect("secret");
+    let plaintext = b"name: secret conversation";
+
+    let encrypted = encrypt(plaintext, &secret).expect("encrypt");
+    assert!(is_encrypted(&encrypted));
+    assert!(!is_encrypted(plaintext));
+
+    // Salt and nonce are random for each encryption
+    let encrypted_again = encrypt(plaintext, &secret).expect("encrypt again");
+    assert_ne!(encrypted, encrypted_again);
+
+    let decrypted = decrypt(&encrypted, &secret).expect("decrypt");
+    assert_eq!(decrypted, plaintext);
+
+    // Wrong secret
+    let wrong = Secret::from_passphrase("battery staple").expect("wrong secret");
+    assert!(decrypt(&encrypted, &wrong).is_err());
+
+    // Tampered data
+    let mut tampered = encrypted.clone();
+    *tampered.last_mut().unwrap() ^= 1;
+    assert!(decrypt(&tampered, &secret).is_err());
+
+    assert!(Secret::from_passphrase("").is_err());
+}
+
+#[tokio::test]
+async fn encrypted_conversation() {
+    let temp_dir = TempDirectoryHandler::build().expect("temp dir");
+    let path = temp_dir.path().join("secret.yaml");
+    let secret = Secret::from_passphrase("correct horse").expect("secret");
+
+    let parameters = CompletionParametersBuilder::default().build().expect("parameters");
+    let mut conversation = Conversation::build(parameters, path.clone(), "Proprietary prompt")
+        .expect("conversation");
+    conversation.set_secret(Some(secret.clone()));
+    conversation.save().await.expect("save");
+
+    // Nothing is stored as plain text
+    let data = fs::read(&path).await.expect("read file");
+    assert!(is_encrypted(&data));
+    assert!(!String::from_utf8_lossy(&data).contains("Proprietary"));
+
+    // Secret is required for loading
+    let error = Conversation::load_with_secret(&path, None).await.expect_err("missing secret");
+    assert!(matches!(error, RustGPTError::MissingSecret(_)));
+
+    let loaded = Conversation::load_with_secret(&path, Some(&secret)).await.expect("load");
+    assert!(loaded.is_encrypted());
+    assert_eq!(conversation, loaded);
+}
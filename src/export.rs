@@ -0,0 +1,237 @@
+use std::collections::HashMap;
+use std::fmt::Write;
+
+use async_openai::types::Role;
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::conversations::{Conversation, Message};
+use crate::Result;
+
+/// Module with tests related to exporting conversations
+#[cfg(test)]
+mod tests;
+
+/// Selects which messages of a conversation are exported.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum ExportScope {
+    /// A single chain of messages going through the given anchor (or the first branch when no
+    /// anchor is given), as returned by [Conversation::get_message_list].
+    Branch(Option<Uuid>),
+
+    /// Every message in the conversation, including all the alternate branches.
+    Tree,
+}
+
+/// A single example in the OpenAI fine-tuning format
+#[derive(Debug, Serialize, Deserialize, PartialEq)]
+pub(crate) struct FinetuneRecord {
+    pub(crate) messages: Vec<FinetuneMessage>,
+}
+
+/// A message within a fine-tuning example
+#[derive(Debug, Serialize, Deserialize, PartialEq)]
+pub(crate) struct FinetuneMessage {
+    pub(crate) role: Role,
+    pub(crate) content: String,
+}
+
+/// Returns a human readable title for the role of a message
+fn role_title(role: &Role) -> &'static str {
+    match role {
+        Role::System => "System",
+        Role::User => "User",
+        Role::Assistant => "Assistant",
+        Role::Function => "Function",
+    }
+}
+
+/// Returns the title used for the exported document
+fn document_title(conversation: &Conversation) -> &str {
+    if conversation.name().is_empty() {
+        "Conversation"
+    } else {
+        conversation.name()
+    }
+}
+
+/// Renders the conversation as a Markdown document, with a heading for each message role.
+///
+/// When exporting the full tree every heading is tagged with the position of the message in
+/// the tree (e.g. `1.2.1` is the first answer to the second query of the system message).
+///
+/// # Arguments
+///
+/// * `conversation`: Conversation to export
+/// * `scope`: Which messages should be exported
+///
+/// returns: Result<String, RustGPTError>
+pub fn to_markdown(conversation: &Conversation, scope: ExportScope) -> Result<String> {
+    let mut output = format!("# {}\n", document_title(conversation));
+
+    match scope {
+        ExportScope::Branch(anchor) => {
+            for message in conversation.get_message_list(anchor)? {
+                let _ = write!(output, "\n## {}\n\n{}\n", role_title(message.role()), message.content());
+            }
+        }
+        ExportScope::Tree => {
+            // Messages are visited depth first, so the parent label is always known
+            let mut labels: HashMap<Uuid, String> = HashMap::new();
+            for message in conversation.iter() {
+                let label = match message.parent_id().and_then(|id| labels.get(&id)) {
+                    Some(parent_label) => format!("{}.{}", parent_label, message.index()),
+                    None => message.index().to_string(),
+                };
+
+                let _ = write!(output, "\n## {} ({})\n\n{}\n", role_title(message.role()), label, message.content());
+                labels.insert(message.id(), label);
+            }
+        }
+    }
+
+    Ok(output)
+}
+
+/// Renders the conversation as a standalone HTML page. When exporting the full tree, every point
+/// where the conversation branches is rendered as a set of collapsible sections, with only the
+/// first branch expanded.
+///
+/// # Arguments
+///
+/// * `conversation`: Conversation to export
+/// * `scope`: Which messages should be exported
+///
+/// returns: Result<String, RustGPTError>
+pub fn to_html(conversation: &Conversation, scope: ExportScope) -> Result<String> {
+    let title = escape_html(document_title(conversation));
+
+    let mut body = String::new();
+    match scope {
+        ExportScope::Branch(anchor) => {
+            for message in conversation.get_message_list(anchor)? {
+                write_html_message(&mut body, message);
+            }
+        }
+        ExportScope::Tree => write_html_branch(&mut body, conversation, conversation.get_root_message()),
+    }
+
+    Ok(format!(r#"<!DOCTYPE html>
+<html lang="en">
+<head>
+<meta charset="utf-8">
+<title>{title}</title>
+<style>
+body {{ font-family: sans-serif; max-width: 60em; margin: 2em auto; padding: 0 1em; }}
+.message {{ border-left: 4px solid #ccc; margin: 1em 0; padding: 0.5em 1em; }}
+.message h2 {{ font-size: 1em; margin: 0 0 0.5em 0; }}
+.message .content {{ white-space: pre-wrap; }}
+.system {{ border-color: #888; background: #f4f4f4; }}
+.user {{ border-color: #2f6fd0; }}
+.assistant {{ border-color: #2a9d5c; }}
+.function {{ border-color: #b07c1c; }}
+details.branch {{ margin-left: 1em; }}
+details.branch > summary {{ cursor: pointer; color: #555; }}
+</style>
+</head>
+<body>
+<h1>{title}</h1>
+{body}</body>
+</html>
+"#))
+}
+
+/// Writes a single message as an HTML section
+fn write_html_message(output: &mut String, message: &Message) {
+    let _ = writeln!(output, r#"<section class="message {}"><h2>{}</h2><div class="content">{}</div></section>"#,
+                     message.role(),
+                     role_title(message.role()),
+                     escape_html(message.content()));
+}
+
+/// Writes the branch that starts with the given message, including all of its alternate branches.
+fn write_html_branch(output: &mut String, conversation: &Conversation, start: &Message) {
+    let mut current = start;
+    loop {
+        write_html_message(output, current);
+
+        let children = conversation.get_children(current.id());
+        match children.len() {
+            0 => break,
+            1 => current = children[0],
+            total => {
+                // Show each branch as a collapsible section
+                for (i, child) in children.into_iter().enumerate() {
+                    let open = if i == 0 { " open" } else { "" };
+                    let _ = writeln!(output, r#"<details class="branch"{}><summary>Branch {}/{}</summary>"#, open, i + 1, total);
+                    write_html_branch(output, conversation, child);
+                    let _ = writeln!(output, "</details>");
+                }
+                break;
+            }
+        }
+    }
+}
+
+/// Escapes the characters that have a special meaning in HTML
+fn escape_html(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&#39;"),
+            _ => escaped.push(c),
+        }
+    }
+
+    escaped
+}
+
+/// Renders the conversation in the JSONL format used by OpenAI for fine-tuning, with one
+/// `{"messages": [...]}` record for every chain of messages from the root to a leaf. Only the
+/// chains that end with an answer from the assistant are exported.
+///
+/// # Arguments
+///
+/// * `conversation`: Conversation to export
+/// * `scope`: Use [ExportScope::Tree] for exporting every chain, or [ExportScope::Branch] for the
+///   chain that goes through the anchor
+/// * `preferred_only`: Only export the chains that end with a message marked as preferred
+///
+/// returns: Result<String, RustGPTError>
+pub fn to_finetune_jsonl(conversation: &Conversation, scope: ExportScope, preferred_only: bool) -> Result<String> {
+    let chains = match scope {
+        ExportScope::Branch(anchor) => vec![conversation.get_message_list(anchor)?],
+        ExportScope::Tree => conversation.get_latest_messages().into_iter()
+            .map(|leaf| conversation.get_message_list(Some(leaf.id())))
+            .collect::<Result<Vec<_>>>()?,
+    };
+
+    let mut output = String::new();
+    for chain in chains {
+        let Some(leaf) = chain.last() else {
+            continue;
+        };
+
+        if *leaf.role() != Role::Assistant || (preferred_only && !leaf.is_preferred()) {
+            continue;
+        }
+
+        let record = FinetuneRecord {
+            messages: chain.iter()
+                .map(|msg| FinetuneMessage {
+                    role: msg.role().clone(),
+                    content: msg.content().clone(),
+                })
+                .collect(),
+        };
+
+        output.push_str(&serde_json::to_string(&record)?);
+        output.push('\n');
+    }
+
+    Ok(output)
+}
@@ -0,0 +1,97 @@
+use std::path::PathBuf;
+
+use crate::conversations::CompletionParametersBuilder;
+
+use super::*;
+
+/// Builds a conversation with two alternate queries, the first one with an answer
+fn branched_conversation() -> Conversation {
+    let params = CompletionParametersBuilder::default().build()
+        .expect("completion parameters");
+    let mut conversation = Conversation::build(params, PathBuf::new(), "System <prompt>")
+        .expect("build conversation");
+    conversation.set_name("Export test".to_string());
+
+    let root_id = conversation.get_root_message().id();
+    let queries = conversation.add_children_to_message(
+        root_id,
+        vec![String::from("Q1"), String::from("Q2")],
+        Role::User)
+        .expect("add queries");
+
+    conversation.add_children_to_message(
+        queries[0],
+        vec![String::from("A1")],
+        Role::Assistant)
+        .expect("add answer");
+
+    conversation
+}
+
+#[test]
+fn markdown_export() {
+    let conversation = branched_conversation();
+
+    // Single branch
+    let markdown = to_markdown(&conversation, ExportScope::Branch(None))
+        .expect("export branch");
+    assert_eq!(markdown, "# Export test\n\n## System\n\nSystem <prompt>\n\n## User\n\nQ1\n\n## Assistant\n\nA1\n");
+
+    // Full tree
+    let markdown = to_markdown(&conversation, ExportScope::Tree)
+        .expect("export tree");
+    assert!(markdown.contains("## User (1.1)\n\nQ1"));
+    assert!(markdown.contains("## Assistant (1.1.1)\n\nA1"));
+    assert!(markdown.contains("## User (1.2)\n\nQ2"));
+
+    // Unknown anchor
+    assert!(to_markdown(&conversation, ExportScope::Branch(Some(Uuid::new_v4()))).is_err());
+}
+
+#[test]
+fn html_export() {
+    let conversation = branched_conversation();
+
+    let html = to_html(&conversation, ExportScope::Branch(None))
+        .expect("export branch");
+    assert!(html.contains("<title>Export test</title>"));
+    assert!(html.contains("System &lt;prompt&gt;"), "Content should be escaped");
+    assert!(!html.contains("Q2"), "Only the first branch should be exported");
+    assert!(!html.contains("<details"));
+
+    let html = to_html(&conversation, ExportScope::Tree)
+        .expect("export tree");
+    assert!(html.contains(r#"<details class="branch" open><summary>Branch 1/2</summary>"#));
+    assert!(html.contains(r#"<details class="branch"><summary>Branch 2/2</summary>"#));
+    assert!(html.contains("Q2"));
+}
+
+#[test]
+fn finetune_export() {
+    let mut conversation = branched_conversation();
+
+    // Only the chain ending with an answer is exported
+    let jsonl = to_finetune_jsonl(&conversation, ExportScope::Tree, false)
+        .expect("export tree");
+    let records: Vec<FinetuneRecord> = jsonl.lines()
+        .map(|line| serde_json::from_str(line).expect("valid record"))
+        .collect();
+    assert_eq!(records.len(), 1);
+    let contents: Vec<_> = records[0].messages.iter().map(|m| m.content.as_str()).collect();
+    assert_eq!(contents, vec!["System <prompt>", "Q1", "A1"]);
+    assert!(jsonl.starts_with(r#"{"messages":[{"role":"system","#));
+
+    // Nothing has been marked as preferred yet
+    let jsonl = to_finetune_jsonl(&conversation, ExportScope::Tree, true)
+        .expect("export preferred");
+    assert!(jsonl.is_empty());
+
+    let answer_id = conversation.get_latest_messages().into_iter()
+        .find(|msg| *msg.role() == Role::Assistant)
+        .expect("answer")
+        .id();
+    conversation.set_preferred(answer_id, true).expect("mark preferred");
+    let jsonl = to_finetune_jsonl(&conversation, ExportScope::Tree, true)
+        .expect("export preferred");
+    assert_eq!(jsonl.lines().count(), 1);
+}
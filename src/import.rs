@@ -0,0 +1,60 @@
+use std::path::{Path, PathBuf};
+
+/// Importer for the `conversations.json` data export of the ChatGPT web application
+pub mod chatgpt;
+
+/// Importer for the JSONL files used for fine-tuning OpenAI models
+pub mod finetune;
+
+/// System message used as root for the imported conversations that don't have one
+pub const DEFAULT_SYSTEM_MESSAGE: &str = "You are a helpful assistant.";
+
+/// Describes an element of the imported data that couldn't be converted
+#[derive(Debug, Clone, PartialEq)]
+pub struct SkippedItem {
+    /// Where the item comes from (e.g. title of the imported conversation)
+    source: String,
+
+    /// Identifier of the item within its source
+    id: String,
+
+    /// Why the item was skipped
+    reason: String,
+}
+
+impl SkippedItem {
+    pub(crate) fn new(source: &str, id: &str, reason: String) -> Self {
+        SkippedItem {
+            source: source.to_string(),
+            id: id.to_string(),
+            reason,
+        }
+    }
+
+    pub fn source(&self) -> &str { &self.source }
+    pub fn id(&self) -> &str { &self.id }
+    pub fn reason(&self) -> &str { &self.reason }
+}
+
+/// Summary of an import operation
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct ImportReport {
+    /// Paths of the conversations that were stored
+    imported: Vec<PathBuf>,
+
+    /// Elements of the imported data that were left out
+    skipped: Vec<SkippedItem>,
+}
+
+impl ImportReport {
+    pub fn imported(&self) -> &[PathBuf] { &self.imported }
+    pub fn skipped(&self) -> &[SkippedItem] { &self.skipped }
+
+    pub(crate) fn add_imported(&mut self, path: &Path) {
+        self.imported.push(path.to_path_buf());
+    }
+
+    pub(crate) fn add_skipped(&mut self, items: impl IntoIterator<Item=SkippedItem>) {
+        self.skipped.extend(items);
+    }
+}
@@ -0,0 +1,318 @@
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+
+use async_openai::types::Role;
+use chrono::{DateTime, TimeZone, Utc};
+use serde::Deserialize;
+use serde_json::Value;
+use tokio::fs;
+use uuid::Uuid;
+
+use crate::conversations::{CompletionModel, CompletionParametersBuilder, Conversation, Message};
+use crate::import::{DEFAULT_SYSTEM_MESSAGE, ImportReport, SkippedItem};
+use crate::workspace::Workspace;
+use crate::{Result, RustGPTError};
+
+/// Module with tests related to the ChatGPT importer
+#[cfg(test)]
+mod tests;
+
+/// Name used for conversations that were exported without a title
+const DEFAULT_TITLE: &str = "Imported conversation";
+
+/// A single conversation from the export
+#[derive(Debug, Deserialize)]
+struct ExportedConversation {
+    #[serde(default)]
+    title: Option<String>,
+
+    #[serde(default)]
+    id: Option<String>,
+
+    mapping: HashMap<String, ExportedNode>,
+}
+
+/// A node of the conversation tree
+#[derive(Debug, Deserialize)]
+struct ExportedNode {
+    #[serde(default)]
+    message: Option<ExportedMessage>,
+
+    #[serde(default)]
+    parent: Option<String>,
+
+    #[serde(default)]
+    children: Vec<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct ExportedMessage {
+    author: ExportedAuthor,
+
+    #[serde(default)]
+    create_time: Option<f64>,
+
+    content: ExportedContent,
+
+    #[serde(default)]
+    metadata: ExportedMetadata,
+}
+
+#[derive(Debug, Deserialize)]
+struct ExportedAuthor {
+    role: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct ExportedContent {
+    content_type: String,
+
+    #[serde(default)]
+    parts: Vec<Value>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct ExportedMetadata {
+    #[serde(default)]
+    model_slug: Option<String>,
+
+    #[serde(default)]
+    is_visually_hidden_from_conversation: bool,
+}
+
+/// Message that will be kept from the export, before being attached to its final parent
+struct PendingMessage {
+    id: Uuid,
+    parent_id: Option<Uuid>,
+    index: u8,
+    role: Role,
+    content: String,
+    created: Option<DateTime<Utc>>,
+    model: Option<String>,
+}
+
+/// Result of inspecting an exported node
+enum NodeContent {
+    /// The node can be converted to a message
+    Keep(Role, String),
+
+    /// The node has nothing worth keeping (e.g. the empty root of the tree)
+    Ignore,
+
+    /// The node holds something that can't be represented in a conversation
+    Unsupported(String),
+}
+
+/// Imports all the conversations of a ChatGPT data export into the workspace. Each conversation
+/// is stored in its own file, preserving branches, roles, timestamps and model names, and is
+/// encrypted with the secret of the workspace if it has one.
+///
+/// # Arguments
+///
+/// * `workspace`: Workspace where the conversations are stored
+/// * `path`: Path to the `conversations.json` file of the export
+///
+/// returns: Result<ImportReport, RustGPTError> Summary with the stored and the skipped elements
+pub async fn import_file<T>(workspace: &Workspace, path: T) -> Result<ImportReport>
+where
+    T: AsRef<Path>
+{
+    let data = fs::read_to_string(path).await?;
+    import(workspace, &data).await
+}
+
+/// Imports all the conversations of the contents of a `conversations.json` file into the workspace.
+pub async fn import(workspace: &Workspace, data: &str) -> Result<ImportReport> {
+    let exported: Vec<ExportedConversation> = serde_json::from_str(data)?;
+
+    let mut report = ImportReport::default();
+    for (i, exported) in exported.iter().enumerate() {
+        let title = exported.title.as_deref()
+            .filter(|t| !t.trim().is_empty())
+            .unwrap_or(DEFAULT_TITLE);
+        let path = workspace.new_conversation_path(title).await?;
+
+        match convert(exported, path) {
+            Ok((mut conversation, skipped)) => {
+                conversation.set_secret(workspace.secret().cloned());
+                workspace.save_conversation(&conversation).await?;
+                report.add_imported(conversation.path());
+                report.add_skipped(skipped);
+            }
+            Err(e) => {
+                let id = exported.id.clone().unwrap_or_else(|| i.to_string());
+                report.add_skipped([SkippedItem::new(title, &id, e.to_string())]);
+            }
+        }
+    }
+
+    Ok(report)
+}
+
+/// Converts a single exported conversation, returning the nodes that were left out.
+fn convert(exported: &ExportedConversation, path: PathBuf) -> Result<(Conversation, Vec<SkippedItem>)> {
+    let title = exported.title.as_deref()
+        .filter(|t| !t.trim().is_empty())
+        .unwrap_or(DEFAULT_TITLE);
+
+    // Nodes without a (known) parent are the roots of the tree
+    let mut roots: Vec<&String> = exported.mapping.iter()
+        .filter(|(_, node)| match &node.parent {
+            Some(parent) => !exported.mapping.contains_key(parent),
+            None => true,
+        })
+        .map(|(id, _)| id)
+        .collect();
+    if roots.is_empty() {
+        return Err(RustGPTError::Import(format!("conversation '{}' has no root node", title)));
+    }
+    roots.sort();
+
+    // Depth first traversal, attaching kept messages to their closest kept ancestor. Nodes are
+    // only visited once, so cycles and nodes listed by several parents can't repeat messages.
+    let mut pending: Vec<PendingMessage> = Vec::new();
+    let mut skipped = Vec::new();
+    let mut children_count: HashMap<Option<Uuid>, u8> = HashMap::new();
+    let mut visited: HashSet<&String> = HashSet::new();
+
+    let mut stack: Vec<(&String, Option<Uuid>)> = roots.into_iter().rev()
+        .map(|id| (id, None))
+        .collect();
+    while let Some((node_id, parent_id)) = stack.pop() {
+        let Some(node) = exported.mapping.get(node_id) else {
+            skipped.push(SkippedItem::new(title, node_id, String::from("node is referenced but missing")));
+            continue;
+        };
+        if !visited.insert(node_id) {
+            skipped.push(SkippedItem::new(title, node_id, String::from("node is repeated in the tree")));
+            continue;
+        }
+
+        let mut attach_to = parent_id;
+        match node_content(node) {
+            NodeContent::Keep(role, content) => {
+                let count = children_count.entry(parent_id).or_insert(0);
+                if let Some(index) = count.checked_add(1) {
+                    *count = index;
+
+                    let message = node.message.as_ref().expect("kept nodes have a message");
+                    let id = Uuid::parse_str(node_id).unwrap_or_else(|_| Uuid::new_v4());
+                    pending.push(PendingMessage {
+                        id,
+                        parent_id,
+                        index,
+                        role,
+                        content,
+                        created: message.create_time.and_then(timestamp),
+                        model: message.metadata.model_slug.clone(),
+                    });
+                    attach_to = Some(id);
+                } else {
+                    skipped.push(SkippedItem::new(title, node_id, String::from("too many alternate branches")));
+                    continue;
+                }
+            }
+            NodeContent::Ignore => {}
+            NodeContent::Unsupported(reason) => skipped.push(SkippedItem::new(title, node_id, reason)),
+        }
+
+        for child in node.children.iter().rev() {
+            stack.push((child, attach_to));
+        }
+    }
+
+    // Use the exported system message as root, or create one if there isn't a single one
+    let top_level: Vec<usize> = pending.iter().enumerate()
+        .filter(|(_, msg)| msg.parent_id.is_none())
+        .map(|(i, _)| i)
+        .collect();
+
+    let mut messages = Vec::with_capacity(pending.len() + 1);
+    let is_single_system = top_level.len() == 1 && pending[top_level[0]].role == Role::System;
+    let root_id = if is_single_system {
+        None
+    } else {
+        let root = Message::restore(Uuid::new_v4(), None, 1, Role::System,
+                                    DEFAULT_SYSTEM_MESSAGE.to_string(), None, None)?;
+        let root_id = root.id();
+        messages.push(root);
+        Some(root_id)
+    };
+
+    // Use the latest known model as the default for continuing the conversation
+    let model = pending.iter()
+        .filter_map(|msg| Some((msg.created?, msg.model.as_deref()?)))
+        .max_by_key(|(created, _)| *created)
+        .map(|(_, slug)| model_from_slug(slug))
+        .unwrap_or(CompletionModel::GPT35);
+
+    for msg in pending {
+        let parent_id = msg.parent_id.or(root_id);
+        messages.push(Message::restore(msg.id, parent_id, msg.index, msg.role, msg.content, msg.created, msg.model)?);
+    }
+
+    let parameters = CompletionParametersBuilder::default()
+        .model(model)
+        .build()
+        .map_err(|e| RustGPTError::Import(e.to_string()))?;
+    let conversation = Conversation::from_messages(parameters, path, title.to_string(), messages)?;
+
+    Ok((conversation, skipped))
+}
+
+/// Checks if the node can be converted into a message
+fn node_content(node: &ExportedNode) -> NodeContent {
+    let Some(message) = &node.message else {
+        return NodeContent::Ignore;
+    };
+
+    if message.metadata.is_visually_hidden_from_conversation {
+        return NodeContent::Ignore;
+    }
+
+    let role = match message.author.role.as_str() {
+        "system" => Role::System,
+        "user" => Role::User,
+        "assistant" => Role::Assistant,
+        other => return NodeContent::Unsupported(format!("unsupported role '{}'", other)),
+    };
+
+    let content_type = message.content.content_type.as_str();
+    if content_type != "text" && content_type != "multimodal_text" {
+        return NodeContent::Unsupported(format!("unsupported content type '{}'", content_type));
+    }
+
+    // Only text parts are kept, attachments like images are left out
+    let text_parts: Vec<&str> = message.content.parts.iter()
+        .filter_map(|part| part.as_str())
+        .collect();
+    let content = text_parts.join("\n");
+
+    if content.trim().is_empty() {
+        if text_parts.len() < message.content.parts.len() {
+            NodeContent::Unsupported(String::from("message only contains attachments"))
+        } else {
+            NodeContent::Ignore
+        }
+    } else {
+        NodeContent::Keep(role, content)
+    }
+}
+
+/// Converts a timestamp in seconds (with fractional part) to a date
+fn timestamp(seconds: f64) -> Option<DateTime<Utc>> {
+    let secs = seconds.trunc() as i64;
+    let nanos = (seconds.fract() * 1e9) as u32;
+    Utc.timestamp_opt(secs, nanos).single()
+}
+
+/// Returns the completion model that better matches the given model name
+fn model_from_slug(slug: &str) -> CompletionModel {
+    slug.parse().unwrap_or_else(|_| {
+        if slug.starts_with("gpt-4") {
+            CompletionModel::GPT4
+        } else {
+            CompletionModel::GPT35
+        }
+    })
+}
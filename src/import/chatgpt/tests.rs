@@ -0,0 +1,158 @@
+use crate::encryption::Secret;
+use crate::test_util::TempDirectoryHandler;
+
+use super::*;
+
+/// Small export with a hidden system message, a regenerated answer and a code interpreter call
+const EXPORT: &str = r#"[{
+    "title": "Rust lifetimes",
+    "id": "conversation-1",
+    "mapping": {
+        "root": {"id": "root", "message": null, "parent": null, "children": ["c0ffee00-0000-4000-8000-000000000001"]},
+        "c0ffee00-0000-4000-8000-000000000001": {
+            "id": "c0ffee00-0000-4000-8000-000000000001",
+            "message": {"author": {"role": "system"}, "create_time": null,
+                        "content": {"content_type": "text", "parts": [""]},
+                        "metadata": {"is_visually_hidden_from_conversation": true}},
+            "parent": "root", "children": ["c0ffee00-0000-4000-8000-000000000002"]
+        },
+        "c0ffee00-0000-4000-8000-000000000002": {
+            "id": "c0ffee00-0000-4000-8000-000000000002",
+            "message": {"author": {"role": "user"}, "create_time": 1690000000.5,
+                        "content": {"content_type": "text", "parts": ["What is a lifetime?"]}},
+            "parent": "c0ffee00-0000-4000-8000-000000000001",
+            "children": ["c0ffee00-0000-4000-8000-000000000003", "c0ffee00-0000-4000-8000-000000000004", "code"]
+        },
+        "c0ffee00-0000-4000-8000-000000000003": {
+            "id": "c0ffee00-0000-4000-8000-000000000003",
+            "message": {"author": {"role": "assistant"}, "create_time": 1690000010.0,
+                        "content": {"content_type": "text", "parts": ["A scope."]},
+                        "metadata": {"model_slug": "gpt-4"}},
+            "parent": "c0ffee00-0000-4000-8000-000000000002", "children": []
+        },
+        "c0ffee00-0000-4000-8000-000000000004": {
+            "id": "c0ffee00-0000-4000-8000-000000000004",
+            "message": {"author": {"role": "assistant"}, "create_time": 1690000020.0,
+                        "content": {"content_type": "text", "parts": ["A region of code."]},
+                        "metadata": {"model_slug": "text-davinci-002-render-sha"}},
+            "parent": "c0ffee00-0000-4000-8000-000000000002", "children": []
+        },
+        "code": {
+            "id": "code",
+            "message": {"author": {"role": "assistant"}, "create_time": 1690000030.0,
+                        "content": {"content_type": "code", "text": "print(1)"}},
+            "parent": "c0ffee00-0000-4000-8000-000000000002", "children": ["tool"]
+        },
+        "tool": {
+            "id": "tool",
+            "message": {"author": {"role": "tool"}, "create_time": 1690000031.0,
+                        "content": {"content_type": "execution_output", "text": "1"}},
+            "parent": "code", "children": []
+        }
+    }
+}, {
+    "title": "Broken",
+    "id": "conversation-2",
+    "mapping": {}
+}]"#;
+
+#[test]
+fn convert_conversation() {
+    let exported: Vec<ExportedConversation> = serde_json::from_str(EXPORT)
+        .expect("parse export");
+
+    let (conversation, skipped) = convert(&exported[0], PathBuf::from("test.yaml"))
+        .expect("convert conversation");
+    assert_eq!(conversation.name(), "Rust lifetimes");
+
+    // The hidden system message is replaced by the default one
+    let root = conversation.get_root_message();
+    assert_eq!(root.content(), DEFAULT_SYSTEM_MESSAGE);
+
+    // Query with both answers as branches
+    let queries = conversation.get_children(root.id());
+    assert_eq!(queries.len(), 1);
+    let query = queries[0];
+    assert_eq!(query.id(), Uuid::parse_str("c0ffee00-0000-4000-8000-000000000002").unwrap());
+    assert_eq!(query.created(), Utc.timestamp_opt(1690000000, 500_000_000).single());
+
+    let answers = conversation.get_children(query.id());
+    let contents: Vec<_> = answers.iter().map(|m| m.content().as_str()).collect();
+    assert_eq!(contents, vec!["A scope.", "A region of code."]);
+    assert_eq!(answers[0].index(), 1);
+    assert_eq!(answers[1].index(), 2);
+    assert_eq!(answers[0].model(), Some("gpt-4"));
+
+    // Latest model was the default ChatGPT one
+    assert_eq!(conversation.default_parameters().model(), CompletionModel::GPT35);
+
+    // Code interpreter call and its output are reported
+    let skipped_ids: Vec<_> = skipped.iter().map(|s| s.id()).collect();
+    assert_eq!(skipped_ids, vec!["code", "tool"]);
+    assert_eq!(skipped[0].reason(), "unsupported content type 'code'");
+    assert_eq!(skipped[1].reason(), "unsupported role 'tool'");
+}
+
+#[test]
+fn repeated_nodes() {
+    // The answer lists the query as its child, and is listed twice by it
+    let export = r#"[{
+        "title": "Cycle",
+        "mapping": {
+            "query": {"message": {"author": {"role": "user"}, "content": {"content_type": "text", "parts": ["Hi"]}},
+                      "parent": null, "children": ["answer", "answer"]},
+            "answer": {"message": {"author": {"role": "assistant"},
+                                   "content": {"content_type": "text", "parts": ["Hello"]}},
+                       "parent": "query", "children": ["query"]}
+        }
+    }]"#;
+    let exported: Vec<ExportedConversation> = serde_json::from_str(export).expect("parse export");
+
+    let (conversation, skipped) = convert(&exported[0], PathBuf::from("test.yaml"))
+        .expect("convert conversation");
+    assert_eq!(conversation.iter().count(), 3);
+
+    let skipped: Vec<_> = skipped.iter().map(|s| (s.id(), s.reason())).collect();
+    assert_eq!(skipped, vec![("query", "node is repeated in the tree"), ("answer", "node is repeated in the tree")]);
+}
+
+#[tokio::test]
+async fn import_into_workspace() {
+    let temp_dir = TempDirectoryHandler::build().expect("temp dir");
+    let workspace = Workspace::open(temp_dir.path()).await.expect("workspace");
+    workspace.rebuild_index().await.expect("create index");
+
+    let report = import(&workspace, EXPORT).await.expect("import");
+    assert_eq!(report.imported(), &[temp_dir.path().join("rust-lifetimes.yaml")]);
+
+    // The index of the workspace includes the imported messages
+    let index = workspace.index().await.expect("load index").expect("index");
+    assert_eq!(index.query("region", 10).len(), 1);
+
+    // Broken conversation is reported as a whole
+    let broken = report.skipped().last().expect("broken conversation");
+    assert_eq!(broken.source(), "Broken");
+    assert_eq!(broken.id(), "conversation-2");
+
+    // Stored conversation can be loaded back
+    let conversation = Conversation::load(&report.imported()[0]).await
+        .expect("load imported");
+    assert_eq!(conversation.name(), "Rust lifetimes");
+    assert_eq!(conversation.iter().count(), 4);
+}
+
+#[tokio::test]
+async fn import_into_encrypted_workspace() {
+    let temp_dir = TempDirectoryHandler::build().expect("temp dir");
+    let secret = Secret::from_passphrase("secret").expect("secret");
+    let workspace = Workspace::open(temp_dir.path()).await.expect("workspace")
+        .with_secret(Some(secret.clone()));
+
+    // Imported conversations get the secret of the workspace
+    let report = import(&workspace, EXPORT).await.expect("import");
+    let path = &report.imported()[0];
+    assert!(Conversation::load_with_secret(path, None).await.is_err());
+    let conversation = Conversation::load_with_secret(path, Some(&secret)).await
+        .expect("load imported");
+    assert!(conversation.is_encrypted());
+}
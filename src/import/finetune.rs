@@ -0,0 +1,175 @@
+use std::collections::HashMap;
+use std::path::Path;
+
+use async_openai::types::Role;
+use tokio::fs;
+use uuid::Uuid;
+
+use crate::conversations::{CompletionParametersBuilder, Conversation, Message};
+use crate::export::FinetuneRecord;
+use crate::import::{DEFAULT_SYSTEM_MESSAGE, ImportReport, SkippedItem};
+use crate::workspace::Workspace;
+use crate::{Result, RustGPTError};
+
+/// Module with tests related to the fine-tuning importer
+#[cfg(test)]
+mod tests;
+
+/// Conversation being built from the examples that share the same system message
+struct PendingConversation {
+    messages: Vec<Message>,
+
+    /// Already added messages by (parent, role, content), so shared prefixes become a single branch
+    known: HashMap<(Uuid, String, String), Uuid>,
+
+    /// Number of children of each message
+    children_count: HashMap<Uuid, u8>,
+}
+
+impl PendingConversation {
+    fn build(system_message: &str) -> Result<Self> {
+        let root = Message::restore(Uuid::new_v4(), None, 1, Role::System,
+                                    system_message.to_string(), None, None)?;
+
+        Ok(PendingConversation {
+            messages: vec![root],
+            known: HashMap::new(),
+            children_count: HashMap::new(),
+        })
+    }
+
+    fn root_id(&self) -> Uuid {
+        self.messages[0].id()
+    }
+
+    /// Adds the chain of messages after the root, reusing the messages that already exist
+    fn add_chain(&mut self, chain: &[(Role, String)]) -> Result<()> {
+        // Find where the chain starts to differ, so nothing is added if there are too many branches
+        let mut parent_id = self.root_id();
+        let mut new_from = chain.len();
+        for (i, (role, content)) in chain.iter().enumerate() {
+            match self.known.get(&(parent_id, role.to_string(), content.clone())) {
+                Some(id) => parent_id = *id,
+                None => {
+                    new_from = i;
+                    break;
+                }
+            }
+        }
+
+        if new_from < chain.len() && self.children_count.get(&parent_id).copied().unwrap_or(0) == u8::MAX {
+            return Err(RustGPTError::Import(String::from("too many alternate branches")));
+        }
+
+        for (role, content) in chain[new_from..].iter() {
+            let count = self.children_count.entry(parent_id).or_insert(0);
+            *count += 1;
+
+            let message = Message::restore(Uuid::new_v4(), Some(parent_id), *count, role.clone(),
+                                           content.clone(), None, None)?;
+            let id = message.id();
+            self.known.insert((parent_id, role.to_string(), content.clone()), id);
+            self.messages.push(message);
+
+            parent_id = id;
+        }
+
+        Ok(())
+    }
+}
+
+/// Imports a JSONL file in the OpenAI fine-tuning format into the workspace. Examples that share
+/// the same system message are merged into a single conversation, where the examples that share
+/// their first messages become branches of the same tree.
+///
+/// # Arguments
+///
+/// * `workspace`: Workspace where the conversations are stored
+/// * `path`: Path to the JSONL file
+///
+/// returns: Result<ImportReport, RustGPTError> Summary with the stored and the skipped examples
+pub async fn import_file<T>(workspace: &Workspace, path: T) -> Result<ImportReport>
+where
+    T: AsRef<Path>
+{
+    let path = path.as_ref();
+    let name = path.file_stem()
+        .map(|stem| stem.to_string_lossy().to_string())
+        .unwrap_or_else(|| String::from("finetune"));
+
+    let data = fs::read_to_string(path).await?;
+    import(workspace, &name, &data).await
+}
+
+/// Imports the contents of a fine-tuning JSONL file into the workspace, naming the conversations
+/// after the given name.
+pub async fn import(workspace: &Workspace, name: &str, data: &str) -> Result<ImportReport> {
+    let mut report = ImportReport::default();
+
+    // Group the examples by system message, keeping the order in which they appear
+    let mut conversations: Vec<(String, PendingConversation)> = Vec::new();
+    for (i, line) in data.lines().enumerate() {
+        if line.trim().is_empty() {
+            continue;
+        }
+
+        let line_id = format!("line {}", i + 1);
+        let mut messages = match serde_json::from_str::<FinetuneRecord>(line) {
+            Ok(record) => record.messages,
+            Err(e) => {
+                report.add_skipped([SkippedItem::new(name, &line_id, e.to_string())]);
+                continue;
+            }
+        };
+
+        if messages.iter().any(|msg| msg.content.is_empty()) {
+            report.add_skipped([SkippedItem::new(name, &line_id, String::from("example has an empty message"))]);
+            continue;
+        }
+
+        let system_message = match messages.first() {
+            Some(first) if first.role == Role::System => messages.remove(0).content,
+            _ => DEFAULT_SYSTEM_MESSAGE.to_string(),
+        };
+        if messages.is_empty() {
+            report.add_skipped([SkippedItem::new(name, &line_id, String::from("example has no messages"))]);
+            continue;
+        }
+
+        let position = match conversations.iter().position(|(system, _)| *system == system_message) {
+            Some(position) => position,
+            None => {
+                conversations.push((system_message.clone(), PendingConversation::build(&system_message)?));
+                conversations.len() - 1
+            }
+        };
+
+        let chain: Vec<_> = messages.into_iter()
+            .map(|msg| (msg.role, msg.content))
+            .collect();
+        if let Err(e) = conversations[position].1.add_chain(&chain) {
+            report.add_skipped([SkippedItem::new(name, &line_id, e.to_string())]);
+        }
+    }
+
+    // Store the conversations
+    let parameters = CompletionParametersBuilder::default()
+        .build()
+        .map_err(|e| RustGPTError::Import(e.to_string()))?;
+    let total = conversations.len();
+    for (i, (_, pending)) in conversations.into_iter().enumerate() {
+        let conversation_name = if total == 1 {
+            name.to_string()
+        } else {
+            format!("{} {}", name, i + 1)
+        };
+
+        let path = workspace.new_conversation_path(&conversation_name).await?;
+        let mut conversation = Conversation::from_messages(parameters.clone(), path, conversation_name, pending.messages)?;
+        conversation.set_secret(workspace.secret().cloned());
+        workspace.save_conversation(&conversation).await?;
+        report.add_imported(conversation.path());
+    }
+
+    Ok(report)
+}
@@ -0,0 +1,43 @@
+use crate::test_util::TempDirectoryHandler;
+
+use super::*;
+
+const EXAMPLES: &str = r#"{"messages": [{"role": "system", "content": "Be brief"}, {"role": "user", "content": "Hi"}, {"role": "assistant", "content": "Hello"}]}
+{"messages": [{"role": "system", "content": "Be brief"}, {"role": "user", "content": "Hi"}, {"role": "assistant", "content": "Hey"}]}
+
+{"messages": [{"role": "user", "content": "What is 2+2?"}, {"role": "assistant", "content": "4"}]}
+not json
+{"messages": [{"role": "system", "content": "Be brief"}]}
+"#;
+
+#[tokio::test]
+async fn import_examples() {
+    let temp_dir = TempDirectoryHandler::build().expect("temp dir");
+    let workspace = Workspace::open(temp_dir.path()).await.expect("workspace");
+
+    let report = import(&workspace, "dataset", EXAMPLES).await.expect("import");
+
+    // Invalid lines are reported
+    let skipped: Vec<_> = report.skipped().iter().map(|s| s.id()).collect();
+    assert_eq!(skipped, vec!["line 5", "line 6"]);
+
+    // One conversation per system message
+    assert_eq!(report.imported().len(), 2);
+
+    let conversation = Conversation::load(&report.imported()[0]).await.expect("first conversation");
+    assert_eq!(conversation.name(), "dataset 1");
+    let root = conversation.get_root_message();
+    assert_eq!(root.content(), "Be brief");
+
+    // Examples with the same prefix share the query
+    let queries = conversation.get_children(root.id());
+    assert_eq!(queries.len(), 1);
+    let answers: Vec<_> = conversation.get_children(queries[0].id()).into_iter()
+        .map(|msg| (msg.index(), msg.content().as_str()))
+        .collect();
+    assert_eq!(answers, vec![(1, "Hello"), (2, "Hey")]);
+
+    let conversation = Conversation::load(&report.imported()[1]).await.expect("second conversation");
+    assert_eq!(conversation.get_root_message().content(), DEFAULT_SYSTEM_MESSAGE);
+    assert_eq!(conversation.iter().count(), 3);
+}
@@ -10,6 +10,27 @@ pub mod tui;
 /// Contains the related classes for handling conversations and completions with ChatGPT.
 pub mod conversations;
 
+/// Contains the settings of the connection to the API.
+pub mod client;
+
+/// Contains the configuration file and its profiles.
+pub mod config;
+
+/// Contains the encryption of conversations stored on disk.
+pub mod encryption;
+
+/// Contains the exporters for rendering conversations in other formats.
+pub mod export;
+
+/// Contains the importers for bringing conversations from other applications.
+pub mod import;
+
+/// Contains the search of messages across conversations.
+pub mod search;
+
+/// Contains the handling of directories where conversations are stored.
+pub mod workspace;
+
 #[derive(Error, Debug)]
 pub enum RustGPTError {
     #[error("Couldn't create initial directory: {0}")]
@@ -21,6 +42,9 @@ pub enum RustGPTError {
     #[error("Error while serializing/deserializing")]
     Serialization(#[from] serde_yaml::Error),
 
+    #[error("Error while serializing/deserializing JSON")]
+    JsonSerialization(#[from] serde_json::Error),
+
     #[error("Couldn't write conversation {0} to disk")]
     WriteConversation(String),
 
@@ -47,6 +71,30 @@ pub enum RustGPTError {
 
     #[error("The given message role is invalid for the current requirement")]
     InvalidMessageRole,
+
+    #[error("Unknown completion model: {0}")]
+    UnknownModel(String),
+
+    #[error("Couldn't import data: {0}")]
+    Import(String),
+
+    #[error("Error while encrypting/decrypting: {0}")]
+    Encryption(String),
+
+    #[error("Conversation {0} is encrypted and no secret was given")]
+    MissingSecret(String),
+
+    #[error("Invalid search pattern")]
+    InvalidPattern(#[from] regex::Error),
+
+    #[error("The search index was created by another version and must be rebuilt")]
+    OutdatedIndex,
+
+    #[error("Unknown profile: {0}")]
+    UnknownProfile(String),
+
+    #[error("Invalid setting: {0}")]
+    InvalidSetting(String),
 }
 
 pub type Result<T> = core::result::Result<T, RustGPTError>;
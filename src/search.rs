@@ -0,0 +1,286 @@
+use std::path::{Path, PathBuf};
+
+use async_openai::types::Role;
+use chrono::{DateTime, Utc};
+use regex::{Regex, RegexBuilder};
+use uuid::Uuid;
+
+use tokio::fs;
+
+use crate::conversations::{Conversation, Message};
+use crate::encryption::{self, Secret};
+use crate::workspace::Workspace;
+use crate::{Result, RustGPTError};
+
+/// Persistent index for ranked keyword searches
+pub mod index;
+
+/// Similarity searches over message embeddings
+pub mod semantic;
+
+/// Module with tests related to searching conversations
+#[cfg(test)]
+mod tests;
+
+/// Number of characters shown at each side of a match in a snippet
+const SNIPPET_CONTEXT: usize = 40;
+
+/// Describes which messages should be found by a search.
+///
+/// # Examples
+///
+/// ```
+/// use async_openai::types::Role;
+/// use rust_gpt::search::SearchQuery;
+///
+/// let query = SearchQuery::substring("lifetime", false)
+///     .with_role(Role::Assistant);
+/// assert!(query.matches_text("Lifetimes are scopes"));
+///
+/// let query = SearchQuery::regex(r"fn \w+\(").expect("valid regex");
+/// assert!(query.matches_text("pub fn main() {}"));
+/// ```
+#[derive(Debug, Clone)]
+pub struct SearchQuery {
+    pattern: Regex,
+    role: Option<Role>,
+    since: Option<DateTime<Utc>>,
+    until: Option<DateTime<Utc>>,
+}
+
+impl SearchQuery {
+    /// Creates a query that finds the messages containing the given text
+    ///
+    /// # Arguments
+    ///
+    /// * `text`: Text to find
+    /// * `case_sensitive`: If the case of the text must match
+    pub fn substring(text: &str, case_sensitive: bool) -> Self {
+        let pattern = RegexBuilder::new(&regex::escape(text))
+            .case_insensitive(!case_sensitive)
+            .build()
+            .expect("escaped text is always a valid pattern");
+
+        Self::with_pattern(pattern)
+    }
+
+    /// Creates a query that finds the messages matching the given regular expression
+    pub fn regex(pattern: &str) -> Result<Self> {
+        Ok(Self::with_pattern(Regex::new(pattern)?))
+    }
+
+    fn with_pattern(pattern: Regex) -> Self {
+        SearchQuery {
+            pattern,
+            role: None,
+            since: None,
+            until: None,
+        }
+    }
+
+    /// Only finds messages with the given role
+    pub fn with_role(mut self, role: Role) -> Self {
+        self.role = Some(role);
+        self
+    }
+
+    /// Only finds messages created at or after the given date
+    pub fn with_since(mut self, since: DateTime<Utc>) -> Self {
+        self.since = Some(since);
+        self
+    }
+
+    /// Only finds messages created before the given date
+    pub fn with_until(mut self, until: DateTime<Utc>) -> Self {
+        self.until = Some(until);
+        self
+    }
+
+    /// Returns true if the text matches the pattern of the query
+    pub fn matches_text(&self, text: &str) -> bool {
+        self.pattern.is_match(text)
+    }
+
+    /// Checks the filters of the query and returns the snippet of the first match, if any
+    fn find(&self, message: &Message) -> Option<String> {
+        if let Some(role) = &self.role {
+            if message.role() != role {
+                return None;
+            }
+        }
+
+        // Messages without a date can't satisfy a date filter
+        if self.since.is_some() || self.until.is_some() {
+            let created = message.created()?;
+            if self.since.is_some_and(|since| created < since) || self.until.is_some_and(|until| created >= until) {
+                return None;
+            }
+        }
+
+        let found = self.pattern.find(message.content())?;
+        Some(snippet(message.content(), found.start(), found.end()))
+    }
+}
+
+/// A message that matched a search
+#[derive(Debug, Clone, PartialEq)]
+pub struct SearchHit {
+    path: PathBuf,
+    conversation_name: String,
+    message_id: Uuid,
+    role: Role,
+    created: Option<DateTime<Utc>>,
+    snippet: String,
+    score: Option<f32>,
+}
+
+impl SearchHit {
+    /// Path to the conversation file
+    pub fn path(&self) -> &PathBuf { &self.path }
+    pub fn conversation_name(&self) -> &str { &self.conversation_name }
+    pub fn message_id(&self) -> Uuid { self.message_id }
+    pub fn role(&self) -> &Role { &self.role }
+    pub fn created(&self) -> Option<DateTime<Utc>> { self.created }
+
+    /// Part of the message around the first match
+    pub fn snippet(&self) -> &str { &self.snippet }
+
+    /// Relevance of the message, only available for ranked searches
+    pub fn score(&self) -> Option<f32> { self.score }
+}
+
+/// A conversation of the workspace that was left out of a search
+#[derive(Debug, Clone, PartialEq)]
+pub struct SkippedConversation {
+    path: PathBuf,
+
+    /// Why the conversation couldn't be searched
+    reason: String,
+}
+
+impl SkippedConversation {
+    pub fn path(&self) -> &Path { &self.path }
+    pub fn reason(&self) -> &str { &self.reason }
+}
+
+/// Results of a search over a workspace
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct SearchReport {
+    hits: Vec<SearchHit>,
+
+    /// Conversations that couldn't be searched, e.g. because they are encrypted
+    skipped: Vec<SkippedConversation>,
+}
+
+impl SearchReport {
+    pub fn hits(&self) -> &[SearchHit] { &self.hits }
+    pub fn skipped(&self) -> &[SkippedConversation] { &self.skipped }
+}
+
+impl From<Vec<SearchHit>> for SearchReport {
+    fn from(hits: Vec<SearchHit>) -> Self {
+        SearchReport { hits, skipped: Vec::new() }
+    }
+}
+
+/// Finds the messages of a conversation that match the query, in depth-first order.
+pub fn search_conversation(conversation: &Conversation, query: &SearchQuery) -> Vec<SearchHit> {
+    conversation.iter()
+        .filter_map(|message| {
+            let snippet = query.find(message)?;
+
+            Some(SearchHit {
+                path: conversation.path().to_path_buf(),
+                conversation_name: conversation.name().to_string(),
+                message_id: message.id(),
+                role: message.role().clone(),
+                created: message.created(),
+                snippet,
+                score: None,
+            })
+        })
+        .collect()
+}
+
+/// Finds the messages that match the query in every conversation of the workspace.
+///
+/// # Arguments
+///
+/// * `workspace`: Workspace with the conversations
+/// * `query`: What to find
+///
+/// returns: Result<SearchReport, RustGPTError> Matches sorted by conversation path. Encrypted
+/// conversations are only searched with the secret of the environment, and the ones that can't be
+/// loaded are skipped.
+pub async fn search_workspace(workspace: &Workspace, query: &SearchQuery) -> Result<SearchReport> {
+    let secret = Secret::from_env().await?;
+    let (conversations, skipped) = load_conversations(workspace, secret.as_ref()).await?;
+
+    Ok(SearchReport {
+        hits: conversations.iter()
+            .flat_map(|conversation| search_conversation(conversation, query))
+            .collect(),
+        skipped,
+    })
+}
+
+/// Loads the conversations of the workspace, skipping the encrypted ones when there is no secret
+/// and the ones that can't be read
+pub(crate) async fn load_conversations(workspace: &Workspace, secret: Option<&Secret>)
+                                       -> Result<(Vec<Conversation>, Vec<SkippedConversation>)> {
+    let mut conversations = Vec::new();
+    let mut skipped = Vec::new();
+    for path in workspace.conversation_paths().await? {
+        let data = fs::read(&path).await?;
+        if secret.is_none() && encryption::is_encrypted(&data) {
+            skipped.push(SkippedConversation { path, reason: String::from("the conversation is encrypted") });
+            continue;
+        }
+
+        match Conversation::from_data(path.clone(), &data, secret) {
+            Ok(conversation) => conversations.push(conversation),
+            Err(RustGPTError::Serialization(e)) => {
+                skipped.push(SkippedConversation { path, reason: format!("invalid conversation file ({})", e) });
+            }
+            Err(e) => skipped.push(SkippedConversation { path, reason: e.to_string() }),
+        }
+    }
+
+    Ok((conversations, skipped))
+}
+
+/// Returns the part of the text around the given range, in a single line
+fn snippet(text: &str, start: usize, end: usize) -> String {
+    let before: Vec<char> = text[..start].chars().rev().take(SNIPPET_CONTEXT + 1).collect();
+    let after: Vec<char> = text[end..].chars().take(SNIPPET_CONTEXT + 1).collect();
+
+    let mut snippet = String::new();
+    if before.len() > SNIPPET_CONTEXT {
+        snippet.push('…');
+    }
+    snippet.extend(before.iter().take(SNIPPET_CONTEXT).rev());
+    snippet.push_str(&text[start..end]);
+    snippet.extend(after.iter().take(SNIPPET_CONTEXT));
+    if after.len() > SNIPPET_CONTEXT {
+        snippet.push('…');
+    }
+
+    snippet.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+/// Splits the text in lowercase words, ignoring single characters
+pub(crate) fn tokenize(text: &str) -> Vec<String> {
+    text.split(|c: char| !c.is_alphanumeric())
+        .filter(|word| word.chars().count() > 1)
+        .map(|word| word.to_lowercase())
+        .collect()
+}
+
+/// Returns the key of a conversation within the index and the embeddings cache. Conversations
+/// are always stored directly within the workspace directory, so their file name is enough.
+pub(crate) fn conversation_key(path: &Path) -> String {
+    path.file_name()
+        .unwrap_or(path.as_os_str())
+        .to_string_lossy()
+        .to_string()
+}
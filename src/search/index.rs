@@ -0,0 +1,266 @@
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+
+use async_openai::types::Role;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use tokio::fs;
+use uuid::Uuid;
+
+use crate::conversations::Conversation;
+use crate::search::{conversation_key, SearchHit, tokenize};
+use crate::{workspace, Result, RustGPTError};
+
+/// Module with tests related to the search index
+#[cfg(test)]
+mod tests;
+
+/// Name of the index file within a workspace
+pub const INDEX_FILE: &str = ".rgpt-index.json";
+
+/// Version of the format of the index file, the index must be rebuilt when it changes
+const INDEX_VERSION: u32 = 1;
+
+/// Maximum number of characters kept from each message for showing in the results
+const PREVIEW_LENGTH: usize = 120;
+
+/// BM25 parameters
+const K1: f32 = 1.2;
+const B: f32 = 0.75;
+
+/// Occurrences of a term in a message
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+struct Posting {
+    conversation: String,
+    message: Uuid,
+    frequency: u32,
+}
+
+/// Details of an indexed message, so results can be shown without loading the conversation
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+struct IndexedMessage {
+    role: Role,
+    created: Option<DateTime<Utc>>,
+    length: u32,
+    preview: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+struct IndexedConversation {
+    name: String,
+    messages: HashMap<Uuid, IndexedMessage>,
+
+    /// Terms that appear in the conversation, for removing its postings
+    terms: Vec<String>,
+}
+
+/// Inverted index of the messages of a workspace, stored next to the conversations. It allows
+/// ranking messages by keywords (using BM25) without loading the conversations.
+///
+/// Encrypted conversations are never indexed, as the index is stored as plain text.
+#[derive(Debug, Serialize, Deserialize, PartialEq)]
+pub struct SearchIndex {
+    version: u32,
+
+    /// Indexed conversations by file name
+    conversations: HashMap<String, IndexedConversation>,
+
+    /// Postings of each term
+    terms: HashMap<String, Vec<Posting>>,
+
+    /// Directory of the workspace
+    #[serde(skip)]
+    directory: PathBuf,
+}
+
+impl SearchIndex {
+    /// Creates an empty index for the workspace in the given directory
+    pub fn build<T>(directory: T) -> Self
+    where
+        T: Into<PathBuf>
+    {
+        SearchIndex {
+            version: INDEX_VERSION,
+            conversations: HashMap::new(),
+            terms: HashMap::new(),
+            directory: directory.into(),
+        }
+    }
+
+    /// Loads the index of the workspace in the given directory, if it has one.
+    pub async fn load<T>(directory: T) -> Result<Option<Self>>
+    where
+        T: Into<PathBuf>
+    {
+        let directory: PathBuf = directory.into();
+        let path = directory.join(INDEX_FILE);
+        if !fs::try_exists(&path).await? {
+            return Ok(None);
+        }
+
+        let data = fs::read(&path).await?;
+        let mut index: Self = serde_json::from_slice(&data)?;
+        if index.version != INDEX_VERSION {
+            return Err(RustGPTError::OutdatedIndex);
+        }
+        index.directory = directory;
+
+        Ok(Some(index))
+    }
+
+    /// Stores the index within the workspace directory
+    pub async fn save(&self) -> Result<()> {
+        let data = serde_json::to_vec(self)?;
+        workspace::write_atomically(&self.directory.join(INDEX_FILE), &data).await
+    }
+
+    /// Returns the number of indexed messages
+    pub fn len(&self) -> usize {
+        self.conversations.values()
+            .map(|c| c.messages.len())
+            .sum()
+    }
+
+    /// Returns true if no message has been indexed
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Adds the conversation to the index, replacing the previous version if it was already
+    /// indexed. Encrypted conversations are removed instead.
+    pub fn update(&mut self, conversation: &Conversation) {
+        let key = conversation_key(conversation.path());
+        self.remove_key(&key);
+
+        if conversation.is_encrypted() {
+            return;
+        }
+
+        let mut indexed = IndexedConversation {
+            name: conversation.name().to_string(),
+            messages: HashMap::new(),
+            terms: Vec::new(),
+        };
+
+        let mut conversation_terms = HashSet::new();
+        for message in conversation.iter() {
+            let tokens = tokenize(message.content());
+
+            let mut frequencies: HashMap<String, u32> = HashMap::new();
+            for token in tokens.iter() {
+                *frequencies.entry(token.clone()).or_insert(0) += 1;
+            }
+
+            for (term, frequency) in frequencies {
+                self.terms.entry(term.clone()).or_default()
+                    .push(Posting {
+                        conversation: key.clone(),
+                        message: message.id(),
+                        frequency,
+                    });
+                conversation_terms.insert(term);
+            }
+
+            indexed.messages.insert(message.id(), IndexedMessage {
+                role: message.role().clone(),
+                created: message.created(),
+                length: tokens.len() as u32,
+                preview: message.content().chars().take(PREVIEW_LENGTH).collect::<String>()
+                    .split_whitespace().collect::<Vec<_>>().join(" "),
+            });
+        }
+
+        indexed.terms = conversation_terms.into_iter().collect();
+        self.conversations.insert(key, indexed);
+    }
+
+    /// Removes the conversation stored in the given path from the index
+    pub fn remove(&mut self, path: &Path) {
+        let key = conversation_key(path);
+        self.remove_key(&key);
+    }
+
+    fn remove_key(&mut self, key: &str) {
+        let Some(indexed) = self.conversations.remove(key) else {
+            return;
+        };
+
+        for term in indexed.terms {
+            if let Some(postings) = self.terms.get_mut(&term) {
+                postings.retain(|p| p.conversation != key);
+                if postings.is_empty() {
+                    self.terms.remove(&term);
+                }
+            }
+        }
+    }
+
+    /// Returns the messages that better match the keywords, sorted by their score.
+    ///
+    /// # Arguments
+    ///
+    /// * `keywords`: Words to find, messages with more matching words rank higher
+    /// * `limit`: Maximum number of results
+    ///
+    /// returns: Vec<SearchHit, Global>
+    pub fn query(&self, keywords: &str, limit: usize) -> Vec<SearchHit> {
+        let total_messages = self.len() as f32;
+        if total_messages == 0.0 {
+            return Vec::new();
+        }
+
+        let average_length = self.conversations.values()
+            .flat_map(|c| c.messages.values())
+            .map(|m| m.length as f32)
+            .sum::<f32>() / total_messages;
+
+        let mut terms = tokenize(keywords);
+        terms.sort();
+        terms.dedup();
+
+        let mut scores: HashMap<(&str, Uuid), f32> = HashMap::new();
+        for term in terms {
+            let Some(postings) = self.terms.get(&term) else {
+                continue;
+            };
+
+            let frequency = postings.len() as f32;
+            let idf = ((total_messages - frequency + 0.5) / (frequency + 0.5) + 1.0).ln();
+            for posting in postings {
+                let Some(message) = self.message(&posting.conversation, posting.message) else {
+                    continue;
+                };
+
+                let tf = posting.frequency as f32;
+                let length_norm = 1.0 - B + B * message.length as f32 / average_length.max(1.0);
+                *scores.entry((posting.conversation.as_str(), posting.message)).or_insert(0.0) +=
+                    idf * tf * (K1 + 1.0) / (tf + K1 * length_norm);
+            }
+        }
+
+        let mut ranked: Vec<_> = scores.into_iter().collect();
+        ranked.sort_by(|(a_key, a), (b_key, b)| b.total_cmp(a).then_with(|| a_key.cmp(b_key)));
+
+        ranked.into_iter()
+            .take(limit)
+            .filter_map(|((key, message_id), score)| {
+                let conversation = self.conversations.get(key)?;
+                let message = conversation.messages.get(&message_id)?;
+
+                Some(SearchHit {
+                    path: self.directory.join(key),
+                    conversation_name: conversation.name.clone(),
+                    message_id,
+                    role: message.role.clone(),
+                    created: message.created,
+                    snippet: message.preview.clone(),
+                    score: Some(score),
+                })
+            })
+            .collect()
+    }
+
+    fn message(&self, key: &str, message_id: Uuid) -> Option<&IndexedMessage> {
+        self.conversations.get(key)?.messages.get(&message_id)
+    }
+}
@@ -0,0 +1,114 @@
+use std::path::PathBuf;
+
+use crate::conversations::CompletionParametersBuilder;
+use crate::encryption::Secret;
+use crate::test_util::TempDirectoryHandler;
+use crate::workspace::Workspace;
+
+use super::*;
+
+/// Builds a conversation with a single query for each of the given contents
+fn conversation_with(path: PathBuf, queries: &[&str]) -> Conversation {
+    let parameters = CompletionParametersBuilder::default().build().expect("parameters");
+    let mut conversation = Conversation::build(parameters, path, "System")
+        .expect("conversation");
+
+    let root_id = conversation.get_root_message().id();
+    conversation.add_queries(root_id, queries.iter().map(|q| q.to_string()).collect())
+        .expect("add queries");
+
+    conversation
+}
+
+#[test]
+fn ranked_queries() {
+    let directory = PathBuf::from("workspace");
+    let mut index = SearchIndex::build(&directory);
+
+    let first = conversation_with(directory.join("first.yaml"), &[
+        "Lifetimes lifetimes everywhere",
+        "The borrow checker validates lifetimes",
+        "Traits and generics",
+    ]);
+    let second = conversation_with(directory.join("second.yaml"), &["Async runtimes"]);
+    index.update(&first);
+    index.update(&second);
+    assert_eq!(index.len(), 6);
+
+    // Higher frequency ranks first, more matching keywords rank first
+    let hits = index.query("lifetimes", 10);
+    assert_eq!(hits.len(), 2);
+    assert_eq!(hits[0].snippet(), "Lifetimes lifetimes everywhere");
+    assert!(hits[0].score() > hits[1].score());
+    assert_eq!(hits[0].path(), &directory.join("first.yaml"));
+
+    let hits = index.query("borrow lifetimes", 1);
+    assert_eq!(hits.len(), 1);
+    assert_eq!(hits[0].snippet(), "The borrow checker validates lifetimes");
+
+    // Updating replaces the previous content
+    let first = conversation_with(directory.join("first.yaml"), &["Closures"]);
+    index.update(&first);
+    assert!(index.query("lifetimes", 10).is_empty());
+    assert_eq!(index.query("closures", 10).len(), 1);
+
+    // Removed and encrypted conversations can't be found
+    index.remove(&directory.join("first.yaml"));
+    assert!(index.query("closures", 10).is_empty());
+
+    let mut second = second;
+    second.set_secret(Some(Secret::from_passphrase("secret").expect("secret")));
+    index.update(&second);
+    assert!(index.is_empty());
+    assert!(index.terms.is_empty());
+}
+
+#[tokio::test]
+async fn workspace_index() {
+    let temp_dir = TempDirectoryHandler::build().expect("temp dir");
+    let workspace = Workspace::open(temp_dir.path()).await.expect("workspace");
+
+    // Without an index nothing is created on save
+    let path = workspace.new_conversation_path("Runtimes").await.expect("path");
+    let conversation = conversation_with(path.clone(), &["Async runtimes"]);
+    workspace.save_conversation(&conversation).await.expect("save");
+    assert!(workspace.index().await.expect("load index").is_none());
+
+    let index = workspace.rebuild_index().await.expect("rebuild");
+    assert_eq!(index.query("runtimes", 10).len(), 1);
+
+    // The index is kept up to date on save and delete
+    let other_path = workspace.new_conversation_path("Macros").await.expect("path");
+    let other = conversation_with(other_path.clone(), &["Declarative macros"]);
+    workspace.save_conversation(&other).await.expect("save other");
+    let index = workspace.index().await.expect("load index").expect("index exists");
+    assert_eq!(index.query("macros", 10)[0].path(), &other_path);
+
+    workspace.delete_conversation(&path).await.expect("delete");
+    let index = workspace.index().await.expect("load index").expect("index exists");
+    assert!(index.query("runtimes", 10).is_empty());
+    assert_eq!(index.len(), 2);
+}
+
+#[tokio::test]
+async fn rebuild_with_encrypted_conversations() {
+    let temp_dir = TempDirectoryHandler::build().expect("temp dir");
+    let workspace = Workspace::open(temp_dir.path()).await.expect("workspace");
+
+    let path = workspace.new_conversation_path("Plain").await.expect("path");
+    workspace.save_conversation(&conversation_with(path, &["Plain runtimes"])).await.expect("save");
+
+    // Encrypted conversations are skipped without asking for their secret
+    let secret_path = workspace.new_conversation_path("Secret").await.expect("path");
+    let mut secret = conversation_with(secret_path, &["Secret runtimes"]);
+    secret.set_secret(Some(Secret::from_passphrase("secret").expect("secret")));
+    secret.save().await.expect("save secret");
+
+    let index = workspace.rebuild_index().await.expect("rebuild");
+    assert_eq!(index.query("runtimes", 10).len(), 1);
+
+    // .. and the index is written without leaving temporary files
+    let saved = workspace.index().await.expect("load index").expect("index exists");
+    assert_eq!(saved.len(), index.len());
+    assert!(!temp_dir.path().join(format!("{}.tmp", INDEX_FILE)).exists());
+}
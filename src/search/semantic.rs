@@ -0,0 +1,285 @@
+use std::collections::HashMap;
+use std::future::Future;
+use std::path::PathBuf;
+
+use async_openai::types::{CreateEmbeddingRequestArgs, EmbeddingInput};
+use serde::{Deserialize, Serialize};
+use tokio::fs;
+use uuid::Uuid;
+
+use crate::conversations::{ClientRef, Conversation};
+use crate::search::{conversation_key, load_conversations, SearchHit, SearchReport, tokenize};
+use crate::workspace::{self, Workspace};
+use crate::Result;
+
+/// Module with tests related to semantic searches
+#[cfg(test)]
+mod tests;
+
+/// Name of the file with the cached embeddings within a workspace
+pub const EMBEDDINGS_FILE: &str = ".rgpt-embeddings.json";
+
+/// Default OpenAI model for embeddings
+pub const DEFAULT_EMBEDDING_MODEL: &str = "text-embedding-ada-002";
+
+/// Maximum number of texts sent in a single embeddings request
+const BATCH_SIZE: usize = 64;
+
+/// Maximum number of characters of a message that are embedded
+const MAX_TEXT_LENGTH: usize = 8000;
+
+/// Number of characters shown from the found messages
+const PREVIEW_LENGTH: usize = 120;
+
+/// Converts texts into vectors, where texts with a similar meaning have similar vectors.
+pub trait EmbeddingBackend {
+    /// Identifier of the model, cached vectors from another model are discarded
+    fn model(&self) -> &str;
+
+    /// Returns the vector of each text, in the same order
+    fn embed(&self, texts: &[String]) -> impl Future<Output=Result<Vec<Vec<f32>>>> + Send;
+}
+
+/// Embeddings created through the OpenAI API
+pub struct OpenAIEmbeddings {
+    client: ClientRef,
+    model: String,
+}
+
+impl OpenAIEmbeddings {
+    /// Creates a backend with the given client and model (e.g. [DEFAULT_EMBEDDING_MODEL])
+    pub fn build(client: ClientRef, model: &str) -> Self {
+        OpenAIEmbeddings {
+            client,
+            model: model.to_string(),
+        }
+    }
+}
+
+impl EmbeddingBackend for OpenAIEmbeddings {
+    fn model(&self) -> &str {
+        &self.model
+    }
+
+    async fn embed(&self, texts: &[String]) -> Result<Vec<Vec<f32>>> {
+        let mut vectors = Vec::with_capacity(texts.len());
+        for batch in texts.chunks(BATCH_SIZE) {
+            let input: Vec<String> = batch.iter()
+                .map(|text| text.chars().take(MAX_TEXT_LENGTH).collect())
+                .collect();
+
+            let request = CreateEmbeddingRequestArgs::default()
+                .model(self.model.clone())
+                .input(EmbeddingInput::StringArray(input))
+                .build()?;
+
+            let mut response = self.client.embeddings(request).await?;
+            response.data.sort_by_key(|embedding| embedding.index);
+            vectors.extend(response.data.into_iter().map(|embedding| embedding.embedding));
+        }
+
+        Ok(vectors)
+    }
+}
+
+/// Local stand-in for an embeddings model, hashing the words of the text into a fixed number of
+/// dimensions. It only finds texts that share words, but it works offline.
+pub struct HashingEmbeddings {
+    dimensions: usize,
+}
+
+impl HashingEmbeddings {
+    pub fn build(dimensions: usize) -> Self {
+        HashingEmbeddings { dimensions: dimensions.max(1) }
+    }
+
+    fn embed_text(&self, text: &str) -> Vec<f32> {
+        let mut vector = vec![0.0; self.dimensions];
+        for token in tokenize(text) {
+            // FNV-1a, so vectors are stable between executions
+            let hash = token.bytes()
+                .fold(0xcbf29ce484222325u64, |hash, b| (hash ^ b as u64).wrapping_mul(0x100000001b3));
+            let sign = if hash >> 63 == 0 { 1.0 } else { -1.0 };
+            vector[(hash % self.dimensions as u64) as usize] += sign;
+        }
+
+        vector
+    }
+}
+
+impl Default for HashingEmbeddings {
+    fn default() -> Self {
+        Self::build(256)
+    }
+}
+
+impl EmbeddingBackend for HashingEmbeddings {
+    fn model(&self) -> &str {
+        "local-hashing"
+    }
+
+    async fn embed(&self, texts: &[String]) -> Result<Vec<Vec<f32>>> {
+        Ok(texts.iter().map(|text| self.embed_text(text)).collect())
+    }
+}
+
+/// Vectors of the messages of a workspace, stored next to the conversations so only new messages
+/// have to be embedded. Encrypted conversations are never embedded.
+#[derive(Debug, Serialize, Deserialize, PartialEq)]
+pub struct EmbeddingCache {
+    model: String,
+
+    /// Vectors of each message, by conversation file name
+    vectors: HashMap<String, HashMap<Uuid, Vec<f32>>>,
+
+    /// Directory of the workspace
+    #[serde(skip)]
+    directory: PathBuf,
+}
+
+impl EmbeddingCache {
+    /// Loads the cache of the workspace in the given directory, or creates an empty one if it
+    /// doesn't exist or was created with another model.
+    pub async fn load<T>(directory: T, model: &str) -> Result<Self>
+    where
+        T: Into<PathBuf>
+    {
+        let directory: PathBuf = directory.into();
+        let path = directory.join(EMBEDDINGS_FILE);
+
+        let cache = if fs::try_exists(&path).await? {
+            let mut cache: Self = serde_json::from_slice(&fs::read(&path).await?)?;
+            cache.directory = directory.clone();
+            Some(cache)
+        } else {
+            None
+        };
+
+        Ok(match cache {
+            Some(cache) if cache.model == model => cache,
+            _ => EmbeddingCache {
+                model: model.to_string(),
+                vectors: HashMap::new(),
+                directory,
+            },
+        })
+    }
+
+    /// Stores the cache within the workspace directory
+    pub async fn save(&self) -> Result<()> {
+        workspace::write_atomically(&self.directory.join(EMBEDDINGS_FILE), &serde_json::to_vec(self)?).await
+    }
+
+    /// Embeds the messages of the conversation that don't have a vector yet, and forgets the ones
+    /// of messages that don't exist anymore.
+    ///
+    /// returns: Result<usize, RustGPTError> Number of embedded messages
+    pub async fn update<B>(&mut self, conversation: &Conversation, backend: &B) -> Result<usize>
+    where
+        B: EmbeddingBackend
+    {
+        let key = conversation_key(conversation.path());
+        if conversation.is_encrypted() {
+            self.vectors.remove(&key);
+            return Ok(0);
+        }
+
+        let vectors = self.vectors.entry(key).or_default();
+        let (new_ids, new_texts): (Vec<Uuid>, Vec<String>) = conversation.iter()
+            .filter(|msg| !vectors.contains_key(&msg.id()))
+            .map(|msg| (msg.id(), msg.content().clone()))
+            .unzip();
+
+        if !new_texts.is_empty() {
+            let embedded = backend.embed(&new_texts).await?;
+            vectors.extend(new_ids.into_iter().zip(embedded));
+        }
+
+        let current: Vec<Uuid> = conversation.iter().map(|msg| msg.id()).collect();
+        vectors.retain(|id, _| current.contains(id));
+
+        Ok(new_texts.len())
+    }
+
+    /// Returns the messages with the most similar vectors, by cosine similarity.
+    ///
+    /// returns: Vec<(String, Uuid, f32), Global> Conversation file name, message and similarity
+    pub fn nearest(&self, vector: &[f32], limit: usize) -> Vec<(String, Uuid, f32)> {
+        let mut scored: Vec<_> = self.vectors.iter()
+            .flat_map(|(key, messages)| messages.iter()
+                .map(move |(id, v)| (key.clone(), *id, cosine_similarity(vector, v))))
+            .collect();
+
+        scored.sort_by(|(a_key, a_id, a), (b_key, b_id, b)| b.total_cmp(a)
+            .then_with(|| (a_key, a_id).cmp(&(b_key, b_id))));
+        scored.truncate(limit);
+
+        scored
+    }
+}
+
+/// Finds the messages of the workspace with the most similar meaning to the text. Messages that
+/// haven't been embedded yet are embedded first, updating the cache of the workspace.
+///
+/// # Arguments
+///
+/// * `workspace`: Workspace with the conversations
+/// * `backend`: Backend used for creating the embeddings
+/// * `text`: Text to find
+/// * `limit`: Maximum number of results
+///
+/// returns: Result<SearchReport, RustGPTError> Results sorted by similarity. Encrypted conversations
+/// are never embedded, so they are skipped along with the ones that can't be loaded.
+pub async fn search_workspace<B>(workspace: &Workspace, backend: &B, text: &str, limit: usize) -> Result<SearchReport>
+where
+    B: EmbeddingBackend
+{
+    let mut cache = EmbeddingCache::load(workspace.path(), backend.model()).await?;
+
+    // Bring the cache up to date
+    let (loaded, skipped) = load_conversations(workspace, None).await?;
+    let mut conversations = HashMap::new();
+    for conversation in loaded {
+        cache.update(&conversation, backend).await?;
+        conversations.insert(conversation_key(conversation.path()), conversation);
+    }
+    cache.vectors.retain(|key, _| conversations.contains_key(key));
+    cache.save().await?;
+
+    let Some(query) = backend.embed(&[text.to_string()]).await?.pop() else {
+        return Ok(SearchReport { hits: Vec::new(), skipped });
+    };
+
+    let hits = cache.nearest(&query, limit).into_iter()
+        .filter_map(|(key, message_id, score)| {
+            let conversation = conversations.get(&key)?;
+            let message = conversation.iter().find(|msg| msg.id() == message_id)?;
+
+            Some(SearchHit {
+                path: conversation.path().to_path_buf(),
+                conversation_name: conversation.name().to_string(),
+                message_id,
+                role: message.role().clone(),
+                created: message.created(),
+                snippet: message.content().chars().take(PREVIEW_LENGTH).collect::<String>()
+                    .split_whitespace().collect::<Vec<_>>().join(" "),
+                score: Some(score),
+            })
+        })
+        .collect();
+
+    Ok(SearchReport { hits, skipped })
+}
+
+/// Returns the cosine of the angle between two vectors, 0 if any of them is empty
+pub fn cosine_similarity(a: &[f32], b: &[f32]) -> f32 {
+    let dot: f32 = a.iter().zip(b).map(|(x, y)| x * y).sum();
+    let norm_a = a.iter().map(|x| x * x).sum::<f32>().sqrt();
+    let norm_b = b.iter().map(|x| x * x).sum::<f32>().sqrt();
+
+    if norm_a == 0.0 || norm_b == 0.0 {
+        0.0
+    } else {
+        dot / (norm_a * norm_b)
+    }
+}
@@ -0,0 +1,95 @@
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use async_openai::types::Role;
+
+use crate::conversations::CompletionParametersBuilder;
+use crate::test_util::TempDirectoryHandler;
+
+use super::*;
+
+/// Backend that counts how many texts have been embedded
+#[derive(Default)]
+struct CountingEmbeddings {
+    embedded: AtomicUsize,
+    inner: HashingEmbeddings,
+}
+
+impl EmbeddingBackend for CountingEmbeddings {
+    fn model(&self) -> &str {
+        "counting"
+    }
+
+    async fn embed(&self, texts: &[String]) -> Result<Vec<Vec<f32>>> {
+        self.embedded.fetch_add(texts.len(), Ordering::SeqCst);
+        self.inner.embed(texts).await
+    }
+}
+
+#[test]
+fn similarity() {
+    assert!((cosine_similarity(&[1.0, 0.0], &[2.0, 0.0]) - 1.0).abs() < 1e-6);
+    assert!(cosine_similarity(&[1.0, 0.0], &[0.0, 1.0]).abs() < 1e-6);
+    assert_eq!(cosine_similarity(&[0.0, 0.0], &[1.0, 1.0]), 0.0);
+
+    // Hashing embeddings are stable and similar for shared words
+    let backend = HashingEmbeddings::default();
+    let a = backend.embed_text("borrow checker rules");
+    assert_eq!(a, backend.embed_text("borrow checker rules"));
+    let b = backend.embed_text("the borrow checker");
+    let c = backend.embed_text("async runtimes");
+    assert!(cosine_similarity(&a, &b) > cosine_similarity(&a, &c));
+}
+
+#[tokio::test]
+async fn workspace_semantic_search() {
+    let temp_dir = TempDirectoryHandler::build().expect("temp dir");
+    let workspace = Workspace::open(temp_dir.path()).await.expect("workspace");
+
+    let parameters = CompletionParametersBuilder::default().build().expect("parameters");
+    let path = workspace.new_conversation_path("Rust").await.expect("path");
+    let mut conversation = Conversation::build(parameters, path, "You are a Rust expert")
+        .expect("conversation");
+    let root_id = conversation.get_root_message().id();
+    conversation.add_children_to_message(
+        root_id,
+        vec![String::from("How does the borrow checker work?"), String::from("Which async runtime should I use?")],
+        Role::User)
+        .expect("add queries");
+    workspace.save_conversation(&conversation).await.expect("save");
+
+    let backend = CountingEmbeddings::default();
+    let report = search_workspace(&workspace, &backend, "borrow checker", 1).await
+        .expect("search");
+    let hits = report.hits();
+    assert_eq!(hits.len(), 1);
+    assert_eq!(hits[0].snippet(), "How does the borrow checker work?");
+    assert!(hits[0].score().unwrap() > 0.0);
+
+    // Three messages plus the query
+    assert_eq!(backend.embedded.load(Ordering::SeqCst), 4);
+
+    // Only the new message and the query are embedded
+    conversation.add_children_to_message(root_id, vec![String::from("What is a trait?")], Role::User)
+        .expect("add query");
+    workspace.save_conversation(&conversation).await.expect("save");
+
+    let report = search_workspace(&workspace, &backend, "trait", 3).await
+        .expect("search again");
+    assert_eq!(report.hits()[0].snippet(), "What is a trait?");
+    assert_eq!(backend.embedded.load(Ordering::SeqCst), 6);
+
+    // Conversations that can't be loaded are skipped, without being embedded
+    let invalid_path = workspace.path().join("invalid.yaml");
+    fs::write(&invalid_path, "not: [a conversation").await.expect("write");
+    let report = search_workspace(&workspace, &backend, "trait", 3).await
+        .expect("search with an invalid conversation");
+    assert_eq!(report.hits()[0].snippet(), "What is a trait?");
+    assert_eq!(report.skipped().len(), 1);
+    assert_eq!(report.skipped()[0].path(), invalid_path.as_path());
+    assert_eq!(backend.embedded.load(Ordering::SeqCst), 7);
+
+    // The cache is replaced as a whole
+    let cache = EmbeddingCache::load(workspace.path(), backend.model()).await.expect("load cache");
+    assert_eq!(cache.vectors.len(), 1);
+    assert!(!workspace.path().join(format!("{}.tmp", EMBEDDINGS_FILE)).exists());
+}
@@ -0,0 +1,117 @@
+use chrono::Duration;
+
+use crate::conversations::CompletionParametersBuilder;
+use crate::test_util::TempDirectoryHandler;
+
+use super::*;
+
+/// Builds a conversation about lifetimes stored in the given path
+fn lifetimes_conversation(path: PathBuf) -> Conversation {
+    let parameters = CompletionParametersBuilder::default().build().expect("parameters");
+    let mut conversation = Conversation::build(parameters, path, "You are a Rust expert")
+        .expect("conversation");
+    conversation.set_name(String::from("Lifetimes"));
+
+    let root_id = conversation.get_root_message().id();
+    let query = conversation.add_children_to_message(
+        root_id,
+        vec![String::from("What is a lifetime?")],
+        Role::User)
+        .expect("add query")[0];
+    conversation.add_children_to_message(
+        query,
+        vec![String::from("A lifetime is the\nscope for which a reference is valid."),
+             String::from("Lifetimes are checked by the borrow checker.")],
+        Role::Assistant)
+        .expect("add answers");
+
+    conversation
+}
+
+#[test]
+fn conversation_search() {
+    let conversation = lifetimes_conversation(PathBuf::from("lifetimes.yaml"));
+
+    // Case insensitive substring
+    let hits = search_conversation(&conversation, &SearchQuery::substring("LIFETIME", false));
+    assert_eq!(hits.len(), 3);
+    assert_eq!(hits[0].conversation_name(), "Lifetimes");
+    assert_eq!(hits[1].snippet(), "A lifetime is the scope for which a reference is v…");
+
+    assert!(search_conversation(&conversation, &SearchQuery::substring("LIFETIME", true)).is_empty());
+
+    // Role filter
+    let query = SearchQuery::substring("lifetime", false).with_role(Role::User);
+    let hits = search_conversation(&conversation, &query);
+    assert_eq!(hits.len(), 1);
+    assert_eq!(*hits[0].role(), Role::User);
+
+    // Regex
+    let query = SearchQuery::regex(r"borrow\s+checker").expect("regex");
+    let hits = search_conversation(&conversation, &query);
+    assert_eq!(hits.len(), 1);
+    assert!(conversation.get_message_list(Some(hits[0].message_id())).is_ok());
+    assert!(SearchQuery::regex("(unclosed").is_err());
+
+    // Date filters
+    let now = Utc::now();
+    let query = SearchQuery::substring("lifetime", false).with_since(now + Duration::hours(1));
+    assert!(search_conversation(&conversation, &query).is_empty());
+    let query = SearchQuery::substring("lifetime", false)
+        .with_since(now - Duration::hours(1))
+        .with_until(now + Duration::hours(1));
+    assert_eq!(search_conversation(&conversation, &query).len(), 3);
+}
+
+#[test]
+fn long_snippets() {
+    let text = format!("{}needle{}", "a".repeat(100), "b".repeat(100));
+    let start = text.find("needle").unwrap();
+    let snippet = snippet(&text, start, start + "needle".len());
+    assert_eq!(snippet, format!("…{}needle{}…", "a".repeat(SNIPPET_CONTEXT), "b".repeat(SNIPPET_CONTEXT)));
+}
+
+#[tokio::test]
+async fn workspace_search() {
+    let temp_dir = TempDirectoryHandler::build().expect("temp dir");
+    let workspace = Workspace::open(temp_dir.path()).await.expect("workspace");
+
+    let path = workspace.new_conversation_path("Lifetimes").await.expect("path");
+    lifetimes_conversation(path.clone()).save().await.expect("save");
+
+    let report = search_workspace(&workspace, &SearchQuery::substring("borrow", false)).await
+        .expect("search");
+    assert_eq!(report.hits().len(), 1);
+    assert_eq!(report.hits()[0].path(), &path);
+    assert!(report.skipped().is_empty());
+}
+
+#[tokio::test]
+async fn skipped_conversations() {
+    let temp_dir = TempDirectoryHandler::build().expect("temp dir");
+    let workspace = Workspace::open(temp_dir.path()).await.expect("workspace");
+
+    let path = workspace.new_conversation_path("Lifetimes").await.expect("path");
+    lifetimes_conversation(path.clone()).save().await.expect("save");
+
+    let encrypted_path = workspace.new_conversation_path("Secret").await.expect("path");
+    let mut encrypted = lifetimes_conversation(encrypted_path.clone());
+    encrypted.set_secret(Some(Secret::from_passphrase("secret").expect("secret")));
+    encrypted.save().await.expect("save");
+
+    let invalid_path = workspace.path().join("invalid.yaml");
+    fs::write(&invalid_path, "not: [a conversation").await.expect("write");
+
+    // Conversations that can't be loaded don't fail the search
+    let (conversations, skipped) = load_conversations(&workspace, None).await.expect("load");
+    assert_eq!(conversations.len(), 1);
+    let skipped_paths: Vec<&Path> = skipped.iter().map(SkippedConversation::path).collect();
+    assert_eq!(skipped_paths, vec![invalid_path.as_path(), encrypted_path.as_path()]);
+    assert_eq!(skipped[1].reason(), "the conversation is encrypted");
+
+    // .. and encrypted ones are searched with their secret
+    let secret = Secret::from_passphrase("secret").expect("secret");
+    let (conversations, skipped) = load_conversations(&workspace, Some(&secret)).await.expect("load");
+    assert_eq!(conversations.len(), 2);
+    assert_eq!(skipped.len(), 1);
+}
@@ -1,15 +1,71 @@
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+use std::process::ExitStatus;
 use std::time::Duration;
 
 use crossterm::{
-    event::{self, Event, KeyCode},
+    event::{DisableBracketedPaste, EnableBracketedPaste, Event, EventStream, KeyCode, KeyEvent, KeyEventKind,
+            KeyModifiers},
     execute,
     terminal::{disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen},
 };
-use ratatui::backend::CrosstermBackend;
-use ratatui::Terminal;
-use ratatui::widgets::{Block, Borders};
+use ratatui::backend::{Backend, CrosstermBackend};
+use ratatui::layout::{Constraint, Direction, Layout, Rect};
+use ratatui::style::{Modifier, Style};
+use ratatui::text::{Line, Span};
+use ratatui::widgets::{Block, Borders, Clear, Paragraph, Wrap};
+use ratatui::{Frame, Terminal};
+use futures::StreamExt;
+use serde::{Deserialize, Serialize};
 use thiserror::Error;
 use tokio::io;
+use tokio::sync::mpsc::{self, UnboundedReceiver, UnboundedSender};
+use tokio::task::JoinHandle;
+use uuid::Uuid;
+
+use crate::conversations::{ClientRef, CompletionParameters, CompletionParametersBuilder, Conversation,
+                           PendingCompletion};
+use crate::import::DEFAULT_SYSTEM_MESSAGE;
+use crate::RustGPTError;
+use crate::tui::chat::{ChatAction, ChatScreen};
+use crate::tui::clipboard::{Clipboard, ClipboardTarget};
+use crate::tui::keymap::{Action, KeyContext, Keymap, KeymapOptions};
+use crate::tui::parameters::{ParametersAction, ParametersDialog, ParametersTarget};
+use crate::tui::sidebar::{ConversationEntry, Sidebar, SidebarAction};
+use crate::tui::state::SessionState;
+use crate::tui::theme::Theme;
+use crate::tui::tree::TreePanel;
+use crate::workspace::Workspace;
+
+/// Contains the chat screen over the active branch of a conversation
+pub mod chat;
+
+/// Contains the navigator over the whole tree of a conversation
+pub mod tree;
+
+/// Contains the list of the conversations of the workspace
+pub mod sidebar;
+
+/// Contains the state of the TUI kept between launches
+pub mod state;
+
+/// Contains the rendering of Markdown messages into styled text
+pub mod markdown;
+
+/// Contains the access to the clipboard, which also works over SSH
+pub mod clipboard;
+
+/// Contains the editor of the query being written
+pub mod editor;
+
+/// Contains the bindings of keys to the actions of the application
+pub mod keymap;
+
+/// Contains the colors of the application
+pub mod theme;
+
+/// Contains the dialog for changing the parameters of the completions
+pub mod parameters;
 
 #[derive(Error, Debug)]
 pub enum ApplicationError {
@@ -17,7 +73,7 @@ pub enum ApplicationError {
     IOError(#[from] io::Error),
 
     #[error("Error while processing input.")]
-    InputError(#[from] tokio::sync::mpsc::error::SendError<ApplicationMessage>),
+    InputError,
 
     #[error("Error on async task")]
     TokioError(#[from] tokio::task::JoinError),
@@ -25,14 +81,88 @@ pub enum ApplicationError {
     #[error("No terminal was created")]
     NoTerminal,
 
+    #[error("Error on conversation")]
+    ConversationError(#[from] RustGPTError),
 }
 
 pub type Result<T> = std::result::Result<T, ApplicationError>;
 
-/// Represents different messages that can be sent by the input
+/// Settings of the TUI in the configuration file
+///
+/// # Examples
+///
+/// ```yaml
+/// keymap:
+///   preset: vim
+/// theme:
+///   user: LightBlue
+/// ```
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+#[serde(default)]
+pub struct TuiOptions {
+    keymap: KeymapOptions,
+    theme: Theme,
+}
+
+impl TuiOptions {
+    pub fn keymap(&self) -> &KeymapOptions { &self.keymap }
+    pub fn theme(&self) -> &Theme { &self.theme }
+}
+
+impl From<mpsc::error::SendError<ApplicationMessage>> for ApplicationError {
+    /// The message isn't kept, it's only sent when the application has stopped
+    fn from(_: mpsc::error::SendError<ApplicationMessage>) -> Self {
+        ApplicationError::InputError
+    }
+}
+
+/// Lines scrolled by the page keys
+const PAGE_LINES: u16 = 10;
+
+/// Maximum width of the tree panel
+const MAX_TREE_WIDTH: u16 = 40;
+
+/// Maximum width of the sidebar
+const MAX_SIDEBAR_WIDTH: u16 = 36;
+
+/// Interval between ticks, which animate the running completions
+const TICK_INTERVAL: Duration = Duration::from_millis(150);
+
+/// Part of the screen receiving the input
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Focus {
+    Sidebar,
+    Input,
+    Tree,
+}
+
+/// Represents different messages that change the state of the application. They are sent by
+/// the input, the ticks and the background tasks, and applied one at a time.
+#[derive(Debug)]
 pub enum ApplicationMessage {
     /// Quits the application
     Quit,
+
+    /// A key was pressed
+    Key(KeyEvent),
+
+    /// Text was pasted
+    Paste(String),
+
+    /// The terminal changed its size
+    Resize,
+
+    /// The input of the terminal can't be read anymore, which quits with the error
+    InputFailed(io::Error),
+
+    /// Time passed, for animations
+    Tick,
+
+    /// A fragment of a response was received
+    CompletionDelta { path: PathBuf, index: usize, delta: String },
+
+    /// A completion finished, with the content of its responses
+    CompletionFinished { path: PathBuf, completion: Box<PendingCompletion>, result: crate::Result<Vec<String>> },
 }
 
 /// Represents a basic application running conversations.
@@ -40,95 +170,951 @@ pub struct Application {
     // Signal if the application should keep running or not
     keep_running: bool,
 
-    n: u64,
+    // Client for the completions
+    client: ClientRef,
+
+    // Directory with the conversations, and the state of the TUI kept within it
+    workspace: Workspace,
+    state: SessionState,
+
+    // List of the conversations of the workspace, and if it is shown
+    sidebar: Sidebar,
+    show_sidebar: bool,
+
+    // Chat over the opened conversation, if any
+    chat: Option<ChatScreen>,
+
+    // Navigator of the tree of the conversation, and if it is shown
+    tree: TreePanel,
+    show_tree: bool,
+
+    // Part of the screen receiving the input
+    focus: Focus,
+
+    // Bindings of the keys, and if their help is shown
+    keymap: Keymap,
+    show_help: bool,
+
+    // Colors of the screen
+    theme: Theme,
+
+    // Dialog changing the parameters of the chat, if open
+    parameters_dialog: Option<ParametersDialog>,
+
+    // Message shown when there is no chat, e.g. errors while opening a conversation
+    status: Option<String>,
+
+    // Parameters and system message of new conversations
+    parameters: CompletionParameters,
+    system_prompt: String,
+
+    // Channel where every message for the application is sent
+    sender: UnboundedSender<ApplicationMessage>,
+    receiver: Option<UnboundedReceiver<ApplicationMessage>>,
+
+    // Task reading the input of the terminal, stopped while an external editor runs
+    input: Option<JoinHandle<Result<()>>>,
+
+    // Task of the running completion, if any
+    completion: Option<JoinHandle<()>>,
+
+    // Clipboard, created once something is copied
+    clipboard: Option<Clipboard>,
 
     // Backend terminal
     terminal: Option<Terminal<CrosstermBackend<std::io::Stdout>>>,
 }
 
 impl Application {
-    /// Runs the application in async mode
+    /// Creates an application over the conversations of the workspace
+    ///
+    /// # Arguments
+    ///
+    /// * `workspace`: Workspace whose conversations are listed
+    /// * `client`: Client for the completions
+    ///
+    /// returns: Result<Application, ApplicationError>
+    pub async fn build(workspace: Workspace, client: ClientRef) -> Result<Self> {
+        let entries = sidebar::load_entries(&workspace).await?;
+
+        // A broken state file only loses the state
+        let state = SessionState::load(&workspace).await.unwrap_or_default();
+        let (sender, receiver) = mpsc::unbounded_channel();
+        let keymap = Keymap::default();
+
+        Ok(Application {
+            keep_running: true,
+            client,
+            workspace,
+            state,
+            sidebar: Sidebar::build(entries).with_hint(keymap.sidebar_hint()),
+            show_sidebar: true,
+            chat: None,
+            tree: TreePanel::default(),
+            show_tree: true,
+            keymap,
+            show_help: false,
+            theme: Theme::default(),
+            parameters_dialog: None,
+            focus: Focus::Sidebar,
+            status: None,
+            parameters: CompletionParametersBuilder::default().build()
+                .expect("default parameters are valid"),
+            system_prompt: DEFAULT_SYSTEM_MESSAGE.to_string(),
+            sender,
+            receiver: Some(receiver),
+            input: None,
+            completion: None,
+            clipboard: None,
+            terminal: None,
+        })
+    }
+
+    /// Uses the parameters for the conversations created from the application
+    pub fn with_parameters(mut self, parameters: CompletionParameters) -> Self {
+        self.parameters = parameters;
+        self
+    }
+
+    /// Uses the system message for the conversations created from the application
+    pub fn with_system_prompt(mut self, system_prompt: &str) -> Self {
+        self.system_prompt = system_prompt.to_string();
+        self
+    }
+
+    /// Uses the keymap for handling the keys
+    pub fn with_keymap(mut self, keymap: Keymap) -> Self {
+        self.sidebar.set_hint(keymap.sidebar_hint());
+        self.keymap = keymap;
+        self
+    }
+
+    /// Uses the colors of the theme
+    pub fn with_theme(mut self, theme: Theme) -> Self {
+        self.theme = theme;
+        self
+    }
+
+    pub fn chat(&self) -> Option<&ChatScreen> { self.chat.as_ref() }
+    pub fn tree(&self) -> &TreePanel { &self.tree }
+    pub fn sidebar(&self) -> &Sidebar { &self.sidebar }
+    pub fn focus(&self) -> Focus { self.focus }
+
+    /// Opens the conversation stored at the given path, remembering it for the next launch
+    pub async fn open(&mut self, path: &Path) -> crate::Result<()> {
+        let conversation = self.workspace.load_conversation(path).await?;
+        self.show_conversation(conversation);
+
+        self.state.set_last_opened(&self.workspace, Some(path));
+        self.state.save(&self.workspace).await?;
+        Ok(())
+    }
+
+    /// Opens the conversation opened last, if it still exists
+    pub async fn open_last(&mut self) -> crate::Result<()> {
+        match self.state.last_opened(&self.workspace) {
+            Some(path) if path.exists() => self.open(&path).await,
+            _ => Ok(()),
+        }
+    }
+
+    /// Shows the conversation in the chat, with its unsent query. The query of the conversation
+    /// shown before is kept.
+    fn show_conversation(&mut self, conversation: Conversation) {
+        self.keep_draft();
+        self.sidebar.select_path(conversation.path());
+
+        let mut chat = ChatScreen::build(conversation).with_hint(self.keymap.hint());
+        if let Some(draft) = self.state.draft(&self.workspace, chat.conversation().path()) {
+            chat.editor_mut().set_text(draft);
+        }
+        self.chat = Some(chat);
+        self.tree = TreePanel::default();
+        self.focus = Focus::Input;
+        self.status = None;
+    }
+
+    /// Keeps the unsent query of the chat in the state, if its conversation isn't encrypted
+    fn keep_draft(&mut self) {
+        if let Some(chat) = self.chat.as_ref() {
+            self.state.set_conversation_draft(&self.workspace, chat.conversation(), chat.input());
+        }
+    }
+
+    /// Runs the application in async mode. Every change comes as a message through the channel
+    /// of the application, and the screen is only drawn again after changes.
     pub async fn run(&mut self) -> Result<()> {
+        let Some(mut receiver) = self.receiver.take() else {
+            return Ok(());
+        };
+
         self.setup_terminal()?;
+        self.input = Some(tokio::spawn(read_input(self.sender.clone())));
+        let ticks = tokio::spawn(send_ticks(self.sender.clone()));
 
-        while self.keep_running {
-            self.render().await?;
-            self.handle_input().await?;
+        let mut result = self.render().await;
+        while self.keep_running && result.is_ok() {
+            let Some(message) = receiver.recv().await else {
+                break;
+            };
+
+            result = match self.update(message).await {
+                Ok(true) => self.render().await,
+                other => other.map(|_| ()),
+            };
+        }
+
+        if let Some(input) = self.input.take() {
+            input.abort();
+        }
+        ticks.abort();
+        if let Some(completion) = self.completion.take() {
+            completion.abort();
         }
 
+        // The terminal is restored even on errors
         self.restore_terminal()?;
 
-        Ok(())
+        self.keep_draft();
+        self.state.save(&self.workspace).await?;
+
+        result
     }
 
     /// Renders the scene once
     async fn render(&mut self) -> Result<()> {
         // Get terminal
-        let Some(terminal) = &mut self.terminal else {
+        let Some(mut terminal) = self.terminal.take() else {
             return Err(ApplicationError::NoTerminal);
         };
 
         // Draw the terminal
-        self.n += 1;
-        terminal.draw(|frame| {
-            let greeting = Block::default()
-                .title(format!("Hello from ChatGPT {}", self.n))
-                .borders(Borders::ALL);
-
-            frame.render_widget(greeting, frame.size());
-        })?;
+        let result = terminal.draw(|frame| self.draw(frame)).map(|_| ());
+        self.terminal = Some(terminal);
+        result?;
 
         Ok(())
     }
 
+    /// Draws every part of the screen on the frame
+    fn draw<B: Backend>(&self, frame: &mut Frame<B>) {
+        let area = frame.size();
+
+        let mut constraints = Vec::new();
+        if self.show_sidebar {
+            constraints.push(Constraint::Length((area.width / 4).min(MAX_SIDEBAR_WIDTH)));
+        }
+        if self.show_tree && self.chat.is_some() {
+            constraints.push(Constraint::Length((area.width / 4).min(MAX_TREE_WIDTH)));
+        }
+        constraints.push(Constraint::Min(1));
+
+        let chunks = Layout::default()
+            .direction(Direction::Horizontal)
+            .constraints(constraints)
+            .split(area);
+        let mut chunks = chunks.iter().copied();
+
+        if self.show_sidebar {
+            let opened = self.chat.as_ref().map(|chat| chat.conversation().path());
+            self.sidebar.render(frame, chunks.next().expect("sidebar area"), opened, self.focus == Focus::Sidebar,
+                                &self.theme);
+        }
+
+        match &self.chat {
+            Some(chat) => {
+                if self.show_tree {
+                    let active_branch: HashSet<_> = chat.messages().iter().map(|msg| msg.id()).collect();
+                    self.tree.render(frame, chunks.next().expect("tree area"), chat.conversation(), &active_branch,
+                                     self.focus == Focus::Tree, &self.theme);
+                }
+                chat.render(frame, chunks.next().expect("chat area"), &self.theme);
+            }
+            None => self.draw_placeholder(frame, chunks.next().expect("chat area")),
+        }
+
+        if let Some(dialog) = &self.parameters_dialog {
+            dialog.render(frame, area, &self.theme);
+        }
+        if self.show_help {
+            self.draw_help(frame, area);
+        }
+    }
+
+    /// Draws the keys of the keymap over the rest of the screen
+    fn draw_help<B: Backend>(&self, frame: &mut Frame<B>, area: Rect) {
+        let mut lines = Vec::new();
+        for (context, actions) in self.keymap.help() {
+            if !lines.is_empty() {
+                lines.push(Line::default());
+            }
+            lines.push(Line::from(Span::styled(context.title(), Style::default().add_modifier(Modifier::BOLD))));
+
+            for (action, keys) in actions {
+                let keys: Vec<String> = keys.iter().map(|key| key.to_string()).collect();
+                lines.push(Line::from(vec![
+                    Span::styled(format!("  {:<24}", keys.join(", ")), self.theme.accent()),
+                    Span::raw(action.description(context)),
+                ]));
+            }
+        }
+
+        let popup = centered(area, 90, lines.len() as u16 + 2);
+
+        let block = Block::default()
+            .title(" Keys (press any key to close) ")
+            .borders(Borders::ALL)
+            .border_style(self.theme.border(true));
+        frame.render_widget(Clear, popup);
+        frame.render_widget(Paragraph::new(lines).block(block), popup);
+    }
+
+    /// Draws the help shown when no conversation is open
+    fn draw_placeholder<B: Backend>(&self, frame: &mut Frame<B>, area: Rect) {
+        let key = |action| self.keymap.keys(KeyContext::List, action).first()
+            .map(|key| key.to_string())
+            .unwrap_or_else(|| String::from("its key"));
+        let mut text = format!("No conversation is open. Select one in the list and press {}, or press {} to \
+            create a new one.", key(Action::Open), key(Action::New));
+        if let Some(status) = &self.status {
+            text.push_str("\n\n");
+            text.push_str(status);
+        }
+
+        let paragraph = Paragraph::new(text)
+            .block(Block::default().borders(Borders::ALL))
+            .wrap(Wrap { trim: true });
+        frame.render_widget(paragraph, area);
+    }
+
     fn setup_terminal(&mut self) -> Result<()> {
         let mut stdout = std::io::stdout();
         enable_raw_mode()?;
-        execute!(stdout, EnterAlternateScreen)?;
+        execute!(stdout, EnterAlternateScreen, EnableBracketedPaste)?;
 
         self.terminal = Some(Terminal::new(CrosstermBackend::new(stdout))?);
         Ok(())
     }
 
     fn restore_terminal(&mut self) -> Result<()> {
-        if let Some(terminal) = self.terminal.as_mut().take() {
+        if let Some(terminal) = self.terminal.as_mut() {
             disable_raw_mode()?;
-            execute!(terminal.backend_mut(), LeaveAlternateScreen,)?;
+            execute!(terminal.backend_mut(), DisableBracketedPaste, LeaveAlternateScreen)?;
             terminal.show_cursor()?;
         }
 
         Ok(())
     }
 
+    /// Applies a message to the state of the application, returns if the screen changed
+    async fn update(&mut self, message: ApplicationMessage) -> Result<bool> {
+        match message {
+            ApplicationMessage::Quit => {
+                self.keep_running = false;
+                Ok(false)
+            }
+            ApplicationMessage::Key(key) => {
+                self.handle_key(key).await?;
+                Ok(true)
+            }
+            ApplicationMessage::Paste(text) => {
+                self.paste(&text);
+                Ok(true)
+            }
+            ApplicationMessage::Resize => Ok(true),
+            ApplicationMessage::InputFailed(e) => Err(e.into()),
+            ApplicationMessage::Tick => Ok(self.chat.as_mut().map(|chat| chat.tick()).unwrap_or(false)),
+            ApplicationMessage::CompletionDelta { path, index, delta } => {
+                match self.chat.as_mut().filter(|chat| chat.conversation().path() == path) {
+                    Some(chat) => {
+                        chat.push_delta(index, &delta);
+                        Ok(true)
+                    }
+                    None => Ok(false),
+                }
+            }
+            ApplicationMessage::CompletionFinished { path, completion, result } => {
+                self.completion = None;
+                if let Err(e) = self.finish_completion(&path, &completion, result).await {
+                    if let Some(chat) = self.chat.as_mut().filter(|chat| chat.conversation().path() == path) {
+                        chat.cancel_pending();
+                    }
+                    self.show_status(format!("{}. Press Enter to try again", e));
+                }
+                Ok(true)
+            }
+        }
+    }
+
+    async fn handle_key(&mut self, key: KeyEvent) -> Result<()> {
+        // Any key closes the help
+        if self.show_help {
+            self.show_help = false;
+            return Ok(());
+        }
+
+        // The dialog receives every key until it's closed
+        if let Some(dialog) = self.parameters_dialog.as_mut() {
+            if let Some(action) = dialog.handle_key(key) {
+                self.parameters_dialog = None;
+                self.apply_parameters(action).await;
+            }
+            return Ok(());
+        }
+
+        // Keys used in every part of the screen, unless text is being typed in the sidebar
+        let editing = self.focus == Focus::Sidebar && self.sidebar.is_editing();
+        let handled = match self.keymap.action(KeyContext::Global, &key) {
+            Some(Action::Quit) => self.quit()?,
+            Some(Action::ToggleSidebar) => self.toggle_sidebar(),
+            Some(Action::ToggleTree) => self.toggle_tree(),
+            Some(Action::SwitchFocus) if !editing => self.switch_focus(),
+            Some(Action::ShowHelp) => {
+                self.show_help = true;
+                true
+            }
+            _ => false,
+        };
+        if handled {
+            return Ok(());
+        }
+
+        match self.focus {
+            Focus::Tree => self.handle_tree_key(key),
+            Focus::Input if self.chat.is_some() => self.handle_chat_key(key).await?,
+            _ => self.handle_sidebar_key(key).await?,
+        }
+
+        Ok(())
+    }
+
+    async fn handle_chat_key(&mut self, key: KeyEvent) -> Result<()> {
+        let Some(chat) = self.chat.as_mut() else {
+            return Ok(());
+        };
+
+        if chat.is_prompting() {
+            if let Some(action) = chat.handle_prompt_key(key) {
+                self.perform_chat_action(action).await;
+            }
+            return Ok(());
+        }
+
+        let Some(action) = self.keymap.action(KeyContext::Chat, &key) else {
+            // Other characters are typed, unless they are chords
+            if let KeyCode::Char(c) = key.code {
+                if !key.modifiers.intersects(KeyModifiers::CONTROL | KeyModifiers::ALT) {
+                    chat.editor_mut().insert_char(c);
+                }
+            }
+            return Ok(());
+        };
+
+        match action {
+            Action::Send => self.send().await,
+            Action::NewLine => chat.editor_mut().insert_newline(),
+            Action::Cancel if chat.selected().is_some() => chat.clear_selection(),
+            Action::Cancel if chat.is_pending() => self.cancel_completion(),
+            Action::Cancel => {
+                self.quit()?;
+            }
+            Action::SelectPrevious => chat.select_previous(),
+            Action::SelectNext => chat.select_next(),
+            Action::PreviousSibling => Self::switch_sibling(chat, -1),
+            Action::NextSibling => Self::switch_sibling(chat, 1),
+            Action::ScrollUp => chat.scroll_up(1),
+            Action::ScrollDown => chat.scroll_down(1),
+            Action::PageUp => chat.scroll_up(PAGE_LINES),
+            Action::PageDown => chat.scroll_down(PAGE_LINES),
+            Action::ToggleRaw => chat.toggle_raw(),
+            Action::Copy => chat.open_copy_prompt(),
+            Action::SaveCode => chat.open_save_prompt(),
+            Action::OpenEditor => self.edit_externally().await?,
+            Action::EditParameters => {
+                // The parameters of the next completion are changed again while they are set
+                let dialog = match chat.next_parameters() {
+                    Some(parameters) => ParametersDialog::build(parameters, ParametersTarget::Next),
+                    None => ParametersDialog::build(chat.conversation().default_parameters(), ParametersTarget::Default),
+                };
+                self.parameters_dialog = Some(dialog);
+            }
+            Action::Undo => {
+                chat.editor_mut().undo();
+            }
+            Action::Left => chat.editor_mut().move_left(),
+            Action::Right => chat.editor_mut().move_right(),
+            Action::WordLeft => chat.editor_mut().move_word_left(),
+            Action::WordRight => chat.editor_mut().move_word_right(),
+            Action::LineStart => chat.editor_mut().move_line_start(),
+            Action::LineEnd => chat.editor_mut().move_line_end(),
+
+            // The history is browsed from the first and last lines
+            Action::Up => {
+                let editor = chat.editor_mut();
+                if !editor.move_up() {
+                    editor.history_previous();
+                }
+            }
+            Action::Down => {
+                let editor = chat.editor_mut();
+                if !editor.move_down() {
+                    editor.history_next();
+                }
+            }
+            Action::DeleteBackward => chat.editor_mut().delete_backward(),
+            Action::DeleteForward => chat.editor_mut().delete_forward(),
+            Action::DeleteWord => chat.editor_mut().delete_word_backward(),
+            _ => {}
+        }
+
+        Ok(())
+    }
+
+    fn handle_tree_key(&mut self, key: KeyEvent) {
+        let Some(chat) = self.chat.as_mut() else {
+            return;
+        };
+
+        let conversation = chat.conversation();
+        match self.keymap.action(KeyContext::List, &key) {
+            Some(Action::Cancel) => self.focus = Focus::Input,
+            Some(Action::Up) => self.tree.move_selection(conversation, -1),
+            Some(Action::Down) => self.tree.move_selection(conversation, 1),
+            Some(Action::PageUp) => self.tree.move_selection(conversation, -(PAGE_LINES as isize)),
+            Some(Action::PageDown) => self.tree.move_selection(conversation, PAGE_LINES as isize),
+            Some(Action::First) => self.tree.select_edge(conversation, false),
+            Some(Action::Last) => self.tree.select_edge(conversation, true),
+            Some(Action::Collapse) => self.tree.collapse(conversation),
+            Some(Action::Expand) => self.tree.expand(conversation),
+            Some(Action::Toggle) => self.tree.toggle(conversation),
+            Some(Action::Open) => {
+                // Show the branch of the selected node in the chat
+                let selected = self.tree.selected().unwrap_or_else(|| conversation.get_root_message().id());
+                if let Err(e) = chat.set_anchor(selected) {
+                    chat.set_status(Some(e.to_string()));
+                }
+            }
+            _ => {}
+        }
+    }
+
+    async fn handle_sidebar_key(&mut self, key: KeyEvent) -> Result<()> {
+        let action = match self.keymap.action(KeyContext::List, &key).filter(|_| !self.sidebar.is_editing()) {
+            // Leaving the list goes back to the chat, or quits without one
+            Some(Action::Cancel) if !self.sidebar.clear_filter() => {
+                if self.chat.is_some() {
+                    self.focus = Focus::Input;
+                } else {
+                    self.quit()?;
+                }
+                None
+            }
+            Some(Action::Cancel) => None,
+            Some(Action::Up) => self.sidebar.move_selection(-1),
+            Some(Action::Down) => self.sidebar.move_selection(1),
+            Some(Action::PageUp) => self.sidebar.move_selection(-(PAGE_LINES as isize)),
+            Some(Action::PageDown) => self.sidebar.move_selection(PAGE_LINES as isize),
+            Some(Action::First) => self.sidebar.select_edge(false),
+            Some(Action::Last) => self.sidebar.select_edge(true),
+            Some(Action::Open) => self.sidebar.open(),
+            Some(Action::Filter) => self.sidebar.start_filter(),
+            Some(Action::New) => self.sidebar.start_create(),
+            Some(Action::Rename) => self.sidebar.start_rename(),
+            Some(Action::Delete) => self.sidebar.start_delete(),
+            _ => self.sidebar.handle_key(key),
+        };
+
+        // Failed actions are reported, the application keeps running
+        if let Some(action) = action {
+            if let Err(e) = self.perform(action).await {
+                self.show_status(e.to_string());
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Performs an action requested from the sidebar
+    async fn perform(&mut self, action: SidebarAction) -> crate::Result<()> {
+        match action {
+            SidebarAction::Open(path) => self.open(&path).await?,
+            SidebarAction::Create(name) => {
+                let path = self.workspace.new_conversation_path(&name).await?;
+                let mut conversation = Conversation::build(self.parameters.clone(), path.clone(), &self.system_prompt)?;
+                conversation.set_name(name);
+                conversation.set_secret(self.workspace.secret().cloned());
+                self.workspace.save_conversation(&conversation).await?;
+
+                self.sidebar.update_entry(ConversationEntry::from(&conversation));
+                self.show_conversation(conversation);
 
-    async fn handle_input(&mut self) -> Result<()> {
-        if event::poll(Duration::from_millis(100))? {
-            match event::read()? {
-                Event::FocusGained => {}
-                Event::FocusLost => {}
-                Event::Key(key) => {
-                    match key.code {
-                        KeyCode::Esc => self.keep_running = false,
-                        _ => {}
+                self.state.set_last_opened(&self.workspace, Some(&path));
+                self.state.save(&self.workspace).await?;
+            }
+            SidebarAction::Rename(path, name) => {
+                let entry = match self.chat.as_mut().filter(|chat| chat.conversation().path() == path) {
+                    Some(chat) => {
+                        chat.set_name(name);
+                        self.workspace.save_conversation(chat.conversation()).await?;
+                        ConversationEntry::from(chat.conversation())
+                    }
+                    None => {
+                        let mut conversation = self.workspace.load_conversation(&path).await?;
+                        conversation.set_name(name);
+                        self.workspace.save_conversation(&conversation).await?;
+                        ConversationEntry::from(&conversation)
                     }
+                };
+                self.sidebar.update_entry(entry);
+            }
+            SidebarAction::Delete(path) => {
+                self.workspace.delete_conversation(&path).await?;
+
+                if self.chat.as_ref().map(|chat| chat.conversation().path() == path).unwrap_or(false) {
+                    self.chat = None;
+                    self.focus = Focus::Sidebar;
+                }
+                if self.state.last_opened(&self.workspace).as_deref() == Some(path.as_path()) {
+                    self.state.set_last_opened(&self.workspace, None);
                 }
-                Event::Mouse(_) => {}
-                _ => {}
+                self.state.set_draft(&self.workspace, &path, "");
+                self.state.save(&self.workspace).await?;
+                self.sidebar.set_entries(sidebar::load_entries(&self.workspace).await?);
             }
         }
 
         Ok(())
     }
-}
 
-impl Default for Application {
-    /// Returns a default application
-    fn default() -> Self {
-        Application {
-            terminal: None,
-            keep_running: true,
+    /// Inserts pasted text in the input of the chat
+    fn paste(&mut self, text: &str) {
+        let Some(chat) = self.chat.as_mut().filter(|_| self.focus == Focus::Input) else {
+            return;
+        };
+
+        if chat.is_prompting() {
+            for c in text.chars().filter(|c| !c.is_control()) {
+                chat.handle_prompt_key(KeyEvent::from(KeyCode::Char(c)));
+            }
+        } else {
+            chat.editor_mut().insert_str(text);
+        }
+    }
+
+    /// Edits the query of the chat in the editor of the user, given by $VISUAL or $EDITOR. The
+    /// editor takes the terminal until it exits. The draft is given to the editor through a
+    /// plaintext file, so it isn't available for encrypted conversations.
+    async fn edit_externally(&mut self) -> Result<()> {
+        let Some(chat) = self.chat.as_mut() else {
+            return Ok(());
+        };
+        if chat.conversation().is_encrypted() {
+            chat.set_status(Some(String::from("The external editor isn't available for encrypted conversations")));
+            return Ok(());
+        }
+
+        let path = std::env::temp_dir().join(format!("rgpt-draft-{}.md", Uuid::new_v4()));
+        tokio::fs::write(&path, chat.input()).await?;
+
+        // The input is read by the editor meanwhile
+        if let Some(input) = self.input.take() {
+            input.abort();
+            let _ = input.await;
+        }
+        self.restore_terminal()?;
+        let status = run_editor(&path).await;
+        self.setup_terminal()?;
+        self.input = Some(tokio::spawn(read_input(self.sender.clone())));
+
+        let text = match status {
+            Ok(status) if status.success() => tokio::fs::read_to_string(&path).await.map_err(|e| e.to_string()),
+            Ok(status) => Err(format!("The editor exited with {}", status)),
+            Err(e) => Err(format!("Couldn't run the editor: {}", e)),
+        };
+        let _ = tokio::fs::remove_file(&path).await;
+
+        if let Some(chat) = self.chat.as_mut() {
+            match text {
+                Ok(text) => chat.editor_mut().set_text(text.trim_end_matches('\n')),
+                Err(e) => chat.set_status(Some(e)),
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Performs an action requested from the chat, reporting the result in its status bar
+    async fn perform_chat_action(&mut self, action: ChatAction) {
+        let Some(chat) = self.chat.as_mut() else {
+            return;
+        };
+
+        let block = match &action {
+            ChatAction::Copy(block) => *block,
+            ChatAction::Save(block, _) => Some(*block),
+        };
+        let Some(text) = chat.selected_text(block) else {
+            chat.set_status(Some(format!("The message has no code block {}", block.unwrap_or_default())));
+            return;
+        };
+        let what = match block {
+            Some(number) => format!("code block {}", number),
+            None => String::from("message"),
+        };
+
+        let status = match action {
+            ChatAction::Copy(_) => {
+                let clipboard = self.clipboard.get_or_insert_with(Clipboard::build);
+                match clipboard.copy(&text) {
+                    Ok(ClipboardTarget::System) => format!("Copied {} to the clipboard", what),
+                    Ok(ClipboardTarget::Terminal) => format!("Copied {} to the clipboard of the terminal", what),
+                    Err(e) => format!("Couldn't copy the {}: {}", what, e),
+                }
+            }
+            ChatAction::Save(_, path) => match tokio::fs::write(&path, text).await {
+                Ok(()) => format!("Saved {} to {}", what, path.display()),
+                Err(e) => format!("Couldn't save the {} to {}: {}", what, path.display(), e),
+            },
+        };
+        chat.set_status(Some(status));
+    }
+
+    /// Uses the parameters of the dialog for the chat. The default ones are stored with the
+    /// conversation.
+    async fn apply_parameters(&mut self, action: ParametersAction) {
+        let Some(chat) = self.chat.as_mut() else {
+            return;
+        };
+
+        let status = match action {
+            ParametersAction::Cancel => return,
+            ParametersAction::Apply(ParametersTarget::Default, parameters) => {
+                chat.set_default_parameters(parameters);
+                match self.workspace.save_conversation(chat.conversation()).await {
+                    Ok(()) => String::from("Saved the parameters of the conversation"),
+                    Err(e) => format!("Couldn't save the parameters: {}", e),
+                }
+            }
+
+            // Choosing the default ones again drops the override
+            ParametersAction::Apply(ParametersTarget::Next, parameters)
+                if &parameters == chat.conversation().default_parameters() => {
+                chat.set_next_parameters(None);
+                String::from("The next completion uses the parameters of the conversation")
+            }
+            ParametersAction::Apply(ParametersTarget::Next, parameters) => {
+                chat.set_next_parameters(Some(parameters));
+                String::from("The parameters apply to the next completion only")
+            }
+        };
+        chat.set_status(Some(status));
+    }
+
+    /// Shows another sibling of the selected message in the chat
+    fn switch_sibling(chat: &mut ChatScreen, offset: isize) {
+        if let Err(e) = chat.switch_sibling(offset) {
+            chat.set_status(Some(e.to_string()));
+        }
+    }
+
+    /// Shows a message in the status bar of the chat, or in the placeholder without a chat
+    fn show_status(&mut self, status: String) {
+        match self.chat.as_mut() {
+            Some(chat) => chat.set_status(Some(status)),
+            None => self.status = Some(status),
+        }
+    }
+
+    /// Shows or hides the sidebar, moving the input to the chat when hidden
+    fn toggle_sidebar(&mut self) -> bool {
+        if self.chat.is_none() {
+            return true;
+        }
+
+        self.show_sidebar = !self.show_sidebar;
+        if !self.show_sidebar && self.focus == Focus::Sidebar {
+            self.focus = Focus::Input;
+        }
+        true
+    }
+
+    /// Shows or hides the tree, moving the input to the chat when hidden
+    fn toggle_tree(&mut self) -> bool {
+        self.show_tree = !self.show_tree;
+        if !self.show_tree && self.focus == Focus::Tree {
+            self.focus = Focus::Input;
+        }
+        true
+    }
 
-            n: 0,
+    /// Moves the input to the next visible part of the screen. The tree starts at the last
+    /// message of the chat.
+    fn switch_focus(&mut self) -> bool {
+        let Some(chat) = &self.chat else {
+            self.focus = Focus::Sidebar;
+            return true;
+        };
+
+        let order = [
+            (Focus::Sidebar, self.show_sidebar),
+            (Focus::Input, true),
+            (Focus::Tree, self.show_tree),
+        ];
+        let current = order.iter().position(|(focus, _)| *focus == self.focus).unwrap_or(0);
+        self.focus = (1..=order.len())
+            .map(|offset| order[(current + offset) % order.len()])
+            .find(|(_, visible)| *visible)
+            .map(|(focus, _)| focus)
+            .unwrap_or(Focus::Input);
+
+        if self.focus == Focus::Tree {
+            self.tree.select(chat.anchor());
         }
+        true
+    }
+
+    /// Sends the input of the chat, completing it in a background task whose progress is sent
+    /// to the application.
+    async fn send(&mut self) {
+        let Some(chat) = self.chat.as_mut() else {
+            return;
+        };
+        if self.completion.is_some() {
+            chat.set_status(Some(String::from("Wait for the current response, or press Esc to cancel it")));
+            return;
+        }
+
+        let mut completion = match chat.start_send(None) {
+            Ok(completion) => completion,
+            Err(RustGPTError::NoQueryGiven) => {
+                chat.set_status(Some(String::from("Write a message first")));
+                return;
+            }
+            Err(e) => {
+                chat.set_status(Some(e.to_string()));
+                return;
+            }
+        };
+        chat.set_status(None);
+
+        // The query is stored before the response arrives
+        self.sidebar.update_entry(ConversationEntry::from(chat.conversation()));
+        if let Err(e) = self.workspace.save_conversation(chat.conversation()).await {
+            chat.set_status(Some(e.to_string()));
+        }
+
+        let path = chat.conversation().path().to_path_buf();
+        let client = self.client.clone();
+        let sender = self.sender.clone();
+        self.completion = Some(tokio::spawn(async move {
+            let result = completion.stream(client, |index, delta| {
+                let _ = sender.send(ApplicationMessage::CompletionDelta {
+                    path: path.clone(),
+                    index,
+                    delta: delta.to_string(),
+                });
+            }).await;
+
+            let completion = Box::new(completion);
+            let _ = sender.send(ApplicationMessage::CompletionFinished { path, completion, result });
+        }));
+    }
+
+    /// Stops the running completion, the query can be completed again later
+    fn cancel_completion(&mut self) {
+        if let Some(completion) = self.completion.take() {
+            completion.abort();
+        }
+        if let Some(chat) = self.chat.as_mut() {
+            chat.cancel_pending();
+            chat.set_status(Some(String::from("Response cancelled. Press Enter to try again")));
+        }
+    }
+
+    /// Adds the responses of a finished completion to its conversation and stores it. The
+    /// conversation is loaded again if it's no longer open.
+    async fn finish_completion(&mut self, path: &Path, completion: &PendingCompletion,
+                               result: crate::Result<Vec<String>>) -> crate::Result<()> {
+        let responses = result?;
+
+        let entry = match self.chat.as_mut().filter(|chat| chat.conversation().path() == path) {
+            Some(chat) => {
+                chat.finish_send(completion, responses)?;
+                self.workspace.save_conversation(chat.conversation()).await?;
+                ConversationEntry::from(chat.conversation())
+            }
+            None => {
+                let mut conversation = self.workspace.load_conversation(path).await?;
+                conversation.finish_completion(completion, responses)?;
+                self.workspace.save_conversation(&conversation).await?;
+                ConversationEntry::from(&conversation)
+            }
+        };
+        self.sidebar.update_entry(entry);
+
+        Ok(())
+    }
+
+    /// Asks the application to quit once the current message is applied
+    fn quit(&self) -> Result<bool> {
+        self.sender.send(ApplicationMessage::Quit)?;
+        Ok(true)
+    }
+}
+
+/// Returns the area of a popup centered in the given one, as large as possible up to the given
+/// size while leaving a margin around it
+pub(crate) fn centered(area: Rect, max_width: u16, max_height: u16) -> Rect {
+    let width = area.width.saturating_sub(4).min(max_width);
+    let height = area.height.saturating_sub(2).min(max_height);
+    Rect::new(area.x + (area.width - width) / 2, area.y + (area.height - height) / 2, width, height)
+}
+
+/// Runs the editor of the user over the file, waiting for it to exit
+async fn run_editor(path: &Path) -> io::Result<ExitStatus> {
+    let editor = std::env::var("VISUAL")
+        .or_else(|_| std::env::var("EDITOR"))
+        .unwrap_or_else(|_| String::from("vi"));
+
+    // The editor may be given with arguments, e.g. "code --wait"
+    let mut words = editor.split_whitespace();
+    let program = words.next().unwrap_or("vi");
+    tokio::process::Command::new(program)
+        .args(words)
+        .arg(path)
+        .status()
+        .await
+}
+
+/// Sends the events of the terminal to the application, until it stops. Errors reading them are
+/// sent as well, as the application can't be used without input.
+async fn read_input(sender: UnboundedSender<ApplicationMessage>) -> Result<()> {
+    let mut events = EventStream::new();
+    while let Some(event) = events.next().await {
+        let message = match event {
+            Ok(Event::Key(key)) if key.kind != KeyEventKind::Release => ApplicationMessage::Key(key),
+            Ok(Event::Paste(text)) => ApplicationMessage::Paste(text),
+            Ok(Event::Resize(_, _)) => ApplicationMessage::Resize,
+            Ok(_) => continue,
+            Err(e) => {
+                sender.send(ApplicationMessage::InputFailed(e))?;
+                return Ok(());
+            }
+        };
+
+        sender.send(message)?;
+    }
+
+    // Without input the application can't be used
+    sender.send(ApplicationMessage::Quit)?;
+    Ok(())
+}
+
+/// Sends ticks to the application, until it stops
+async fn send_ticks(sender: UnboundedSender<ApplicationMessage>) -> Result<()> {
+    let mut interval = tokio::time::interval(TICK_INTERVAL);
+    loop {
+        interval.tick().await;
+        sender.send(ApplicationMessage::Tick)?;
     }
 }
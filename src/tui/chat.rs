@@ -0,0 +1,653 @@
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::path::PathBuf;
+
+use async_openai::types::Role;
+use crossterm::event::{KeyCode, KeyEvent};
+use ratatui::backend::Backend;
+use ratatui::layout::{Constraint, Direction, Layout, Rect};
+use ratatui::style::Modifier;
+use ratatui::text::{Line, Span, Text};
+use ratatui::widgets::{Block, Borders, Paragraph};
+use ratatui::Frame;
+use uuid::Uuid;
+
+use crate::conversations::{CompletionParameters, Conversation, Message, PendingCompletion};
+use crate::tui::editor::InputEditor;
+use crate::tui::markdown;
+use crate::tui::theme::Theme;
+use crate::{Result, RustGPTError};
+
+/// Module with tests related to the chat screen
+#[cfg(test)]
+mod tests;
+
+/// Maximum number of lines of the input box, including its borders
+const MAX_INPUT_HEIGHT: u16 = 10;
+
+/// Frames of the animation shown while waiting for a response
+const SPINNER: [&str; 4] = ["|", "/", "-", "\\"];
+
+/// Response being received for a query
+#[derive(Debug, Clone, PartialEq)]
+struct PendingResponse {
+    /// Query being completed
+    message_id: Uuid,
+
+    /// Content of the first response received so far
+    content: String,
+}
+
+/// Action requested from the chat, performed by the application
+#[derive(Debug, Clone, PartialEq)]
+pub enum ChatAction {
+    /// Copies the selected message, or its code block with the given number
+    Copy(Option<usize>),
+
+    /// Writes the code block with the given number of the selected message to a file
+    Save(usize, PathBuf),
+}
+
+/// Command being typed in the status bar
+#[derive(Debug, Clone, PartialEq)]
+enum Prompt {
+    Copy(String),
+    Save(String),
+}
+
+/// Chat over the active branch of a conversation: the messages of the branch, the input for the
+/// next query and a status bar.
+pub struct ChatScreen {
+    conversation: Conversation,
+
+    /// Last message of the active branch
+    anchor: Uuid,
+
+    /// Query being written
+    editor: InputEditor,
+
+    /// Lines scrolled up from the bottom of the messages
+    scroll: u16,
+
+    /// Message shown in the status bar, e.g. errors of the last completion
+    status: Option<String>,
+
+    /// Position within the branch of the message whose siblings are switched, the last message
+    /// if None
+    selected: Option<usize>,
+
+    /// Response being received, if a completion is running
+    pending: Option<PendingResponse>,
+
+    /// Ticks received while waiting for the response, for animating it
+    ticks: usize,
+
+    /// Shows the source of the messages instead of rendering their Markdown
+    raw: bool,
+
+    /// Lines of the messages already rendered, with the width they were rendered for
+    rendered: RefCell<HashMap<Uuid, (usize, Vec<Line<'static>>)>>,
+
+    /// Command being typed, if any
+    prompt: Option<Prompt>,
+
+    /// Keys of the input shown in its title, e.g. "Enter to send"
+    hint: String,
+
+    /// Parameters of the next completion only, instead of the default ones of the conversation
+    next_parameters: Option<CompletionParameters>,
+}
+
+impl ChatScreen {
+    /// Creates the screen for the conversation, showing its first branch
+    pub fn build(conversation: Conversation) -> Self {
+        let anchor = conversation.get_latest_messages()
+            .first()
+            .map(|msg| msg.id())
+            .unwrap_or_else(|| conversation.get_root_message().id());
+
+        // Queries already sent are the history of the input
+        let mut queries: Vec<&Message> = conversation.iter()
+            .filter(|msg| *msg.role() == Role::User)
+            .collect();
+        queries.sort_by_key(|msg| msg.created());
+        let mut editor = InputEditor::default();
+        editor.set_history(queries.into_iter().map(|msg| msg.content().clone()).collect());
+
+        ChatScreen {
+            conversation,
+            anchor,
+            editor,
+            scroll: 0,
+            status: None,
+            selected: None,
+            pending: None,
+            ticks: 0,
+            raw: false,
+            rendered: RefCell::new(HashMap::new()),
+            prompt: None,
+            hint: String::new(),
+            next_parameters: None,
+        }
+    }
+
+    /// Sets the keys of the input shown in its title
+    pub fn with_hint(mut self, hint: String) -> Self {
+        self.hint = hint;
+        self
+    }
+
+    pub fn conversation(&self) -> &Conversation { &self.conversation }
+    pub fn anchor(&self) -> Uuid { self.anchor }
+    pub fn input(&self) -> &str { self.editor.text() }
+    pub fn editor(&self) -> &InputEditor { &self.editor }
+    pub fn editor_mut(&mut self) -> &mut InputEditor { &mut self.editor }
+    pub fn status(&self) -> Option<&str> { self.status.as_deref() }
+    pub fn selected(&self) -> Option<usize> { self.selected }
+    pub fn is_raw(&self) -> bool { self.raw }
+    pub fn next_parameters(&self) -> Option<&CompletionParameters> { self.next_parameters.as_ref() }
+
+    /// Changes the parameters used for completions when none are specified
+    pub fn set_default_parameters(&mut self, parameters: CompletionParameters) {
+        self.conversation.set_default_parameters(parameters);
+    }
+
+    /// Uses the parameters for the next completion only, or the default ones again if None
+    pub fn set_next_parameters(&mut self, parameters: Option<CompletionParameters>) {
+        self.next_parameters = parameters;
+    }
+
+    /// Shows the branch containing the given message
+    pub fn set_anchor(&mut self, message_id: Uuid) -> Result<()> {
+        let last = *self.conversation.get_message_list(Some(message_id))?
+            .last()
+            .expect("message list contains the anchor");
+
+        self.anchor = last.id();
+        self.scroll = 0;
+        self.selected = None;
+        Ok(())
+    }
+
+    /// Returns the selected message, or the last one of the branch if none is selected
+    pub fn selected_message(&self) -> Option<&Message> {
+        let messages = self.messages();
+        match self.selected {
+            Some(index) => messages.get(index).copied(),
+            None => messages.last().copied(),
+        }
+    }
+
+    /// Selects the previous message of the branch, starting from the last one
+    pub fn select_previous(&mut self) {
+        let last = self.messages().len().saturating_sub(1);
+        self.selected = Some(self.selected.unwrap_or(last).saturating_sub(1).min(last));
+    }
+
+    /// Selects the next message of the branch, removing the selection after the last one
+    pub fn select_next(&mut self) {
+        let last = self.messages().len().saturating_sub(1);
+        self.selected = self.selected
+            .map(|index| index + 1)
+            .filter(|&index| index < last);
+    }
+
+    pub fn clear_selection(&mut self) {
+        self.selected = None;
+    }
+
+    /// Returns the position of the message among its siblings (starting at 1) and the number of
+    /// siblings
+    pub fn sibling_position(&self, message_id: Uuid) -> Option<(usize, usize)> {
+        let siblings = self.conversation.get_message_siblings(message_id).ok()?;
+        let position = siblings.iter().position(|msg| msg.id() == message_id)?;
+        Some((position + 1, siblings.len()))
+    }
+
+    /// Replaces the selected message with one of its siblings, showing the branch that follows
+    /// it. The offset moves forward if positive and backwards if negative, wrapping around.
+    ///
+    /// # Arguments
+    ///
+    /// * `offset`: Number of siblings to move
+    ///
+    /// returns: Result<(), RustGPTError>
+    pub fn switch_sibling(&mut self, offset: isize) -> Result<()> {
+        let Some(message) = self.selected_message() else {
+            return Ok(());
+        };
+
+        let siblings = self.conversation.get_message_siblings(message.id())?;
+        let position = siblings.iter()
+            .position(|msg| msg.id() == message.id())
+            .expect("message is one of its siblings") as isize;
+        let next = siblings[(position + offset).rem_euclid(siblings.len() as isize) as usize].id();
+
+        let selected = self.selected;
+        self.set_anchor(next)?;
+        self.selected = selected;
+        Ok(())
+    }
+
+    /// Renames the conversation, without saving it
+    pub fn set_name(&mut self, name: String) {
+        self.conversation.set_name(name);
+    }
+
+    /// Switches between the rendered Markdown of the messages and their source
+    pub fn toggle_raw(&mut self) {
+        self.raw = !self.raw;
+    }
+
+    /// Returns the text of the selected message, or of its code block with the given number
+    /// (starting at 1) if any.
+    pub fn selected_text(&self, code_block: Option<usize>) -> Option<String> {
+        let content = self.selected_message()?.content();
+        match code_block {
+            None => Some(content.clone()),
+            Some(number) => markdown::code_blocks(content)
+                .get(number.checked_sub(1)?)
+                .map(|block| block.code().to_string()),
+        }
+    }
+
+    /// Asks for what to copy from the selected message
+    pub fn open_copy_prompt(&mut self) {
+        self.prompt = Some(Prompt::Copy(String::new()));
+    }
+
+    /// Asks for the file where a code block of the selected message is written
+    pub fn open_save_prompt(&mut self) {
+        self.prompt = Some(Prompt::Save(String::new()));
+    }
+
+    /// Checks if a command is being typed, so every key is used for it
+    pub fn is_prompting(&self) -> bool {
+        self.prompt.is_some()
+    }
+
+    /// Handles a key while a command is being typed, returning the action it requests
+    pub fn handle_prompt_key(&mut self, key: KeyEvent) -> Option<ChatAction> {
+        let text = match self.prompt.as_mut()? {
+            Prompt::Copy(text) | Prompt::Save(text) => text,
+        };
+
+        match key.code {
+            KeyCode::Esc => self.prompt = None,
+            KeyCode::Backspace => {
+                text.pop();
+            }
+            KeyCode::Char(c) => text.push(c),
+            KeyCode::Enter => {
+                let action = match self.prompt.take()? {
+                    Prompt::Copy(text) => parse_copy(&text),
+                    Prompt::Save(text) => parse_save(&text),
+                };
+                if action.is_none() {
+                    self.status = Some(String::from("Invalid command"));
+                }
+                return action;
+            }
+            _ => {}
+        }
+
+        None
+    }
+
+    pub fn set_status(&mut self, status: Option<String>) {
+        self.status = status;
+    }
+
+    /// Returns the messages of the active branch, from the root
+    pub fn messages(&self) -> Vec<&Message> {
+        self.conversation.get_message_list(Some(self.anchor))
+            .unwrap_or_default()
+    }
+
+    pub fn scroll_up(&mut self, lines: u16) {
+        self.scroll = self.scroll.saturating_add(lines);
+    }
+
+    pub fn scroll_down(&mut self, lines: u16) {
+        self.scroll = self.scroll.saturating_sub(lines);
+    }
+
+    /// Checks if the last message of the branch is a query without response, e.g. after a failed
+    /// completion.
+    pub fn is_waiting_response(&self) -> bool {
+        self.conversation.get_message_list(Some(self.anchor))
+            .ok()
+            .and_then(|messages| messages.last().map(|msg| *msg.role() == Role::User))
+            .unwrap_or(false)
+    }
+
+    /// Checks if a completion is running
+    pub fn is_pending(&self) -> bool {
+        self.pending.is_some()
+    }
+
+    /// Sends the input as a query of the active branch, moving the branch to it, and prepares its
+    /// completion. An empty input completes the last query again if it has no response.
+    ///
+    /// # Arguments
+    ///
+    /// * `n_completions`: Number of completions, or the ones of the parameters if None
+    ///
+    /// returns: Result<PendingCompletion, RustGPTError> Completion to perform
+    pub fn start_send(&mut self, n_completions: Option<u8>) -> Result<PendingCompletion> {
+        let query = self.editor.text().trim().to_string();
+        if query.is_empty() && !self.is_waiting_response() {
+            return Err(RustGPTError::NoQueryGiven);
+        }
+
+        // A new query replaces an unanswered one, which is kept as another branch
+        if !query.is_empty() {
+            let parent = self.conversation.query_parent(self.anchor)?;
+            let &message = self.conversation.add_queries(parent, vec![query.clone()])?
+                .first()
+                .expect("first message created");
+
+            self.anchor = message.id();
+            self.editor.clear();
+            self.editor.push_history(query);
+        }
+
+        self.scroll = 0;
+        self.selected = None;
+
+        // The parameters of the next completion are used once
+        let completion = match &self.next_parameters {
+            Some(parameters) => {
+                let parameters = n_completions.map(|n| parameters.with_n(n)).unwrap_or_else(|| parameters.clone());
+                self.conversation.prepare_completion_with(self.anchor, parameters)?
+            }
+            None => self.conversation.prepare_completion(self.anchor, n_completions)?,
+        };
+        self.next_parameters = None;
+        self.pending = Some(PendingResponse {
+            message_id: completion.message_id(),
+            content: String::new(),
+        });
+        self.ticks = 0;
+
+        Ok(completion)
+    }
+
+    /// Adds a fragment of a response being received. Only the first response is shown.
+    pub fn push_delta(&mut self, index: usize, delta: &str) {
+        if let Some(pending) = self.pending.as_mut().filter(|_| index == 0) {
+            pending.content.push_str(delta);
+        }
+    }
+
+    /// Adds the responses of a finished completion, moving the branch to the first one if it
+    /// still shows the query.
+    pub fn finish_send(&mut self, completion: &PendingCompletion, responses: Vec<String>) -> Result<()> {
+        self.pending = None;
+
+        let first = self.conversation.finish_completion(completion, responses)?
+            .first()
+            .map(|msg| msg.id());
+
+        if let Some(first) = first.filter(|_| self.anchor == completion.message_id()) {
+            self.anchor = first;
+        }
+        Ok(())
+    }
+
+    /// Stops showing the response being received, e.g. after it failed or was cancelled
+    pub fn cancel_pending(&mut self) {
+        self.pending = None;
+    }
+
+    /// Advances the animation of the response being received, returns if it changed
+    pub fn tick(&mut self) -> bool {
+        if self.pending.is_none() {
+            return false;
+        }
+
+        self.ticks = self.ticks.wrapping_add(1);
+        true
+    }
+
+    /// Returns the estimated number of tokens of the active branch, counting ~4 characters each
+    pub fn estimated_tokens(&self) -> usize {
+        self.messages().iter()
+            .map(|msg| msg.content().chars().count().div_ceil(4))
+            .sum()
+    }
+
+    /// Renders the screen in the given area of the frame
+    ///
+    /// # Arguments
+    /// * `frame`: Frame being drawn
+    /// * `area`: Area of the frame where the screen goes
+    /// * `theme`: Colors of the messages and the bars
+    pub fn render<B: Backend>(&self, frame: &mut Frame<B>, area: Rect, theme: &Theme) {
+        let input_width = area.width.saturating_sub(2).max(1) as usize;
+        let (input_lines, cursor) = self.editor.layout(input_width);
+        let input_height = (input_lines.len() as u16 + 2).min(MAX_INPUT_HEIGHT);
+
+        let chunks = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([Constraint::Min(1), Constraint::Length(input_height), Constraint::Length(1)])
+            .split(area);
+
+        self.render_messages(frame, chunks[0], theme);
+        self.render_input(frame, chunks[1], &input_lines, cursor);
+        match &self.prompt {
+            Some(prompt) => render_prompt(frame, chunks[2], prompt, theme),
+            None => self.render_status(frame, chunks[2], theme),
+        }
+    }
+
+    fn render_messages<B: Backend>(&self, frame: &mut Frame<B>, area: Rect, theme: &Theme) {
+        let block = Block::default()
+            .title(format!(" {} ", self.conversation.name()))
+            .borders(Borders::ALL);
+        let width = block.inner(area).width.max(1) as usize;
+        let height = block.inner(area).height as usize;
+
+        let mut lines = Vec::new();
+        let mut selected_line = None;
+        for (index, msg) in self.messages().into_iter().enumerate() {
+            let mut style = theme.role(msg.role()).add_modifier(Modifier::BOLD);
+            if self.selected == Some(index) {
+                style = style.patch(theme.highlight());
+                selected_line = Some(lines.len());
+            }
+
+            // Messages with siblings show their position, e.g. < 2/5 >
+            let mut header = vec![Span::styled(role_title(msg.role()), style)];
+            if let Some((position, count)) = self.sibling_position(msg.id()).filter(|&(_, count)| count > 1) {
+                header.push(Span::styled(format!("  < {}/{} >", position, count), theme.muted()));
+            }
+
+            lines.push(Line::from(header));
+            lines.extend(self.content_lines(msg, width));
+            lines.push(Line::default());
+        }
+
+        // The response being received follows its query
+        if let Some(pending) = self.pending.as_ref().filter(|pending| pending.message_id == self.anchor) {
+            let style = theme.role(&Role::Assistant).add_modifier(Modifier::BOLD);
+            lines.push(Line::from(vec![
+                Span::styled(role_title(&Role::Assistant), style),
+                Span::styled(format!(" {}", self.spinner()), theme.muted()),
+            ]));
+            lines.extend(self.format(&pending.content, width));
+        }
+
+        // Scrolling is counted from the bottom, so the last message is visible by default
+        let max_scroll = lines.len().saturating_sub(height);
+        let mut top = max_scroll.saturating_sub(self.scroll as usize);
+
+        // .. and the selected message is always visible
+        if let Some(line) = selected_line {
+            top = top.min(line).max((line + 1).saturating_sub(height));
+        }
+
+        let paragraph = Paragraph::new(Text::from(lines))
+            .block(block)
+            .scroll((top as u16, 0));
+        frame.render_widget(paragraph, area);
+    }
+
+    /// Returns the lines of the content of the message, rendering them only once for each width
+    fn content_lines(&self, msg: &Message, width: usize) -> Vec<Line<'static>> {
+        if self.raw {
+            return self.format(msg.content(), width);
+        }
+
+        let mut rendered = self.rendered.borrow_mut();
+        match rendered.get(&msg.id()) {
+            Some((rendered_width, lines)) if *rendered_width == width => lines.clone(),
+            _ => {
+                let lines = self.format(msg.content(), width);
+                rendered.insert(msg.id(), (width, lines.clone()));
+                lines
+            }
+        }
+    }
+
+    /// Formats the text as Markdown, or as is in raw mode
+    fn format(&self, text: &str, width: usize) -> Vec<Line<'static>> {
+        if self.raw {
+            wrap(text, width).into_iter().map(Line::from).collect()
+        } else {
+            markdown::render(text, width)
+        }
+    }
+
+    fn render_input<B: Backend>(&self, frame: &mut Frame<B>, area: Rect, lines: &[String], cursor: (usize, usize)) {
+        let title = if self.hint.is_empty() { " Message ".to_string() } else { format!(" Message ({}) ", self.hint) };
+        let block = Block::default()
+            .title(title)
+            .borders(Borders::ALL);
+        let height = block.inner(area).height.max(1) as usize;
+
+        // Keep the line of the cursor visible
+        let top = (cursor.0 + 1).saturating_sub(height);
+        let paragraph = Paragraph::new(Text::from(lines.iter().map(|l| Line::from(l.as_str())).collect::<Vec<_>>()))
+            .block(block)
+            .scroll((top as u16, 0));
+        frame.render_widget(paragraph, area);
+
+        let (row, column) = ((cursor.0 - top) as u16, cursor.1 as u16);
+        frame.set_cursor(area.x + 1 + column, area.y + 1 + row);
+    }
+
+    fn spinner(&self) -> &'static str {
+        SPINNER[self.ticks % SPINNER.len()]
+    }
+
+    fn render_status<B: Backend>(&self, frame: &mut Frame<B>, area: Rect, theme: &Theme) {
+        // The parameters of the next completion replace the default ones until it's sent
+        let (parameters, next) = match &self.next_parameters {
+            Some(parameters) => (parameters, "next: "),
+            None => (self.conversation.default_parameters(), ""),
+        };
+        let mut status = format!(" {}{} | temperature {} | max tokens {} | ~{} tokens in branch",
+                                 next, parameters.model(), parameters.temperature(), parameters.max_tokens(),
+                                 self.estimated_tokens());
+        if self.raw {
+            status.push_str(" | raw");
+        }
+        if self.pending.is_some() {
+            status.push_str(&format!(" | {} waiting for response (Esc to cancel)", self.spinner()));
+        }
+        if let Some(message) = &self.status {
+            status.push_str(" | ");
+            status.push_str(message);
+        }
+
+        let paragraph = Paragraph::new(status)
+            .style(theme.status());
+        frame.render_widget(paragraph, area);
+    }
+}
+
+/// Renders the command being typed in place of the status bar
+fn render_prompt<B: Backend>(frame: &mut Frame<B>, area: Rect, prompt: &Prompt, theme: &Theme) {
+    let (label, text) = match prompt {
+        Prompt::Copy(text) => (" Copy (Enter: whole message, N: code block N): ", text),
+        Prompt::Save(text) => (" Save code block ([N] PATH, Esc to cancel): ", text),
+    };
+
+    let line = format!("{}{}", label, text);
+    let cursor = line.chars().count() as u16;
+    frame.render_widget(Paragraph::new(line).style(theme.status().patch(theme.highlight())), area);
+    frame.set_cursor(area.x + cursor.min(area.width.saturating_sub(1)), area.y);
+}
+
+/// Parses the command of the copy prompt: empty for the whole message, or the number of a code
+/// block.
+fn parse_copy(text: &str) -> Option<ChatAction> {
+    let text = text.trim();
+    if text.is_empty() {
+        return Some(ChatAction::Copy(None));
+    }
+
+    text.parse().ok().map(|number| ChatAction::Copy(Some(number)))
+}
+
+/// Parses the command of the save prompt: the path, optionally after the number of the code
+/// block, which is the first one by default.
+fn parse_save(text: &str) -> Option<ChatAction> {
+    let text = text.trim();
+    let (number, path) = match text.split_once(' ') {
+        Some((number, path)) if number.parse::<usize>().is_ok() => (number.parse().ok()?, path.trim()),
+        _ => (1, text),
+    };
+
+    if path.is_empty() {
+        return None;
+    }
+    Some(ChatAction::Save(number, PathBuf::from(path)))
+}
+
+fn role_title(role: &Role) -> &'static str {
+    match role {
+        Role::System => "System",
+        Role::User => "You",
+        Role::Assistant => "Assistant",
+        Role::Function => "Function",
+    }
+}
+
+/// Splits the text into lines no longer than the width, breaking at spaces when possible
+fn wrap(text: &str, width: usize) -> Vec<String> {
+    let width = width.max(1);
+
+    let mut lines = Vec::new();
+    for source_line in text.split('\n') {
+        let mut line = String::new();
+        let mut line_width = 0;
+
+        for word in source_line.split_inclusive(' ') {
+            let word_width = word.chars().count();
+            if line_width + word_width.min(width) > width && line_width > 0 {
+                lines.push(line.trim_end().to_string());
+                line.clear();
+                line_width = 0;
+            }
+
+            // Words longer than the width are broken
+            for c in word.chars() {
+                if line_width == width {
+                    lines.push(std::mem::take(&mut line));
+                    line_width = 0;
+                    if c == ' ' {
+                        continue;
+                    }
+                }
+                line.push(c);
+                line_width += 1;
+            }
+        }
+
+        lines.push(line.trim_end().to_string());
+    }
+
+    lines
+}
@@ -0,0 +1,257 @@
+use std::path::PathBuf;
+
+use ratatui::backend::TestBackend;
+use ratatui::Terminal;
+
+use crate::conversations::{CompletionModel, CompletionParametersBuilder};
+use crate::tui::theme::Theme;
+
+use super::*;
+
+fn build_screen() -> ChatScreen {
+    let parameters = CompletionParametersBuilder::default().build().expect("parameters");
+    let mut conversation = Conversation::build(parameters, PathBuf::from("chat.yaml"), "You are helpful")
+        .expect("build conversation");
+    conversation.set_name(String::from("Chat"));
+
+    let root = conversation.get_root_message().id();
+    conversation.add_queries(root, vec![String::from("First query"), String::from("Second query")])
+        .expect("add queries");
+
+    ChatScreen::build(conversation)
+}
+
+#[test]
+fn wrap_lines() {
+    assert_eq!(wrap("one two three", 8), vec!["one two", "three"]);
+    assert_eq!(wrap("first\n\nsecond", 20), vec!["first", "", "second"]);
+    assert_eq!(wrap("abcdefghij", 4), vec!["abcd", "efgh", "ij"]);
+    assert_eq!(wrap("", 10), vec![""]);
+}
+
+#[test]
+fn active_branch() {
+    let mut screen = build_screen();
+    let messages: Vec<&str> = screen.messages().iter().map(|msg| msg.content().as_str()).collect();
+    assert_eq!(messages, vec!["You are helpful", "First query"]);
+    assert!(screen.is_waiting_response());
+
+    // Anchoring at the root keeps the first branch, anchoring at a query shows its branch
+    let second = screen.conversation().get_latest_messages()[1].id();
+    screen.set_anchor(second).expect("set anchor");
+    assert_eq!(screen.messages().last().map(|msg| msg.content().as_str()), Some("Second query"));
+    assert!(screen.set_anchor(Uuid::new_v4()).is_err());
+
+    // Queries sent are the history of the input
+    assert_eq!(screen.editor().history(), &[String::from("First query"), String::from("Second query")]);
+}
+
+#[test]
+fn render_screen() {
+    let mut screen = build_screen();
+    screen.set_status(Some(String::from("Ready")));
+
+    let mut terminal = Terminal::new(TestBackend::new(80, 20)).expect("terminal");
+    terminal.draw(|frame| screen.render(frame, frame.size(), &Theme::default())).expect("draw");
+
+    let content: String = terminal.backend().buffer().content().iter()
+        .map(|cell| cell.symbol.as_str())
+        .collect();
+    assert!(content.contains("Chat"));
+    assert!(content.contains("First query"));
+    assert!(content.contains("gpt-3.5-turbo"));
+    assert!(content.contains("Ready"));
+}
+
+#[test]
+fn switch_siblings() {
+    let mut screen = build_screen();
+    let first = screen.messages()[1].id();
+    assert_eq!(screen.sibling_position(first), Some((1, 2)));
+    assert_eq!(screen.sibling_position(screen.conversation().get_root_message().id()), Some((1, 1)));
+
+    // The last message is switched when none is selected, wrapping around
+    screen.switch_sibling(1).expect("next sibling");
+    assert_eq!(screen.selected_message().map(|msg| msg.content().as_str()), Some("Second query"));
+    screen.switch_sibling(1).expect("wrap around");
+    assert_eq!(screen.anchor(), first);
+    screen.switch_sibling(-1).expect("previous sibling");
+    assert_eq!(screen.selected_message().map(|msg| msg.content().as_str()), Some("Second query"));
+
+    // The selection moves within the branch, and is kept while switching
+    screen.select_previous();
+    assert_eq!(screen.selected(), Some(0));
+    screen.switch_sibling(1).expect("root has no siblings");
+    assert_eq!(screen.selected(), Some(0));
+    screen.select_next();
+    assert_eq!(screen.selected(), None);
+}
+
+#[test]
+fn send_in_background() {
+    let mut screen = build_screen();
+    let query = screen.anchor();
+    assert!(!screen.tick());
+
+    // The first query has no response yet, so an empty input completes it
+    let completion = screen.start_send(None).expect("start send");
+    assert_eq!(completion.message_id(), screen.messages()[1].id());
+    assert!(screen.is_pending());
+    assert!(screen.tick());
+
+    // Only the first response is shown while it's received
+    screen.push_delta(0, "Hel");
+    screen.push_delta(1, "Other");
+    screen.push_delta(0, "lo");
+    assert_eq!(screen.pending.as_ref().map(|pending| pending.content.as_str()), Some("Hello"));
+
+    screen.finish_send(&completion, vec![String::from("Hello"), String::from("Other")])
+        .expect("finish send");
+    assert!(!screen.is_pending());
+    assert_eq!(screen.messages().last().map(|msg| msg.content().as_str()), Some("Hello"));
+    assert_eq!(screen.conversation().get_children(query).len(), 2);
+
+    // A new input is sent as a query of the response
+    assert!(matches!(screen.start_send(None), Err(RustGPTError::NoQueryGiven)));
+    screen.editor_mut().insert_str("Thanks");
+    screen.start_send(None).expect("start send");
+    assert_eq!(screen.input(), "");
+    assert_eq!(screen.editor().history().last().map(String::as_str), Some("Thanks"));
+    assert_eq!(screen.messages().last().map(|msg| msg.content().as_str()), Some("Thanks"));
+
+    screen.cancel_pending();
+    assert!(!screen.is_pending());
+    assert!(screen.is_waiting_response());
+}
+
+#[test]
+fn send_after_unanswered_query() {
+    // The first branch ends in a query without a response
+    let mut screen = build_screen();
+    let unanswered = screen.anchor();
+    assert!(screen.is_waiting_response());
+
+    // A new query becomes a sibling of it
+    screen.editor_mut().insert_str("Replacement");
+    let completion = screen.start_send(None).expect("start send");
+    let root = screen.conversation().get_root_message().id();
+    assert_ne!(completion.message_id(), unanswered);
+    assert_eq!(screen.conversation().get_children(root).len(), 3);
+    assert_eq!(screen.messages().last().map(|msg| msg.content().as_str()), Some("Replacement"));
+
+    // .. as it does after a cancelled completion
+    screen.cancel_pending();
+    screen.editor_mut().insert_str("Again");
+    screen.start_send(None).expect("start send after cancel");
+    assert_eq!(screen.conversation().get_children(root).len(), 4);
+}
+
+#[test]
+fn next_parameters() {
+    let mut screen = build_screen();
+    let defaults = screen.conversation().default_parameters().clone();
+    let next = CompletionParametersBuilder::default()
+        .model(CompletionModel::GPT4)
+        .n(2)
+        .build().expect("next parameters");
+
+    // The status shows the parameters of the next completion
+    screen.set_next_parameters(Some(next.clone()));
+    let mut terminal = Terminal::new(TestBackend::new(100, 20)).expect("terminal");
+    terminal.draw(|frame| screen.render(frame, frame.size(), &Theme::default())).expect("draw");
+    let content: String = terminal.backend().buffer().content().iter()
+        .map(|cell| cell.symbol.as_str())
+        .collect();
+    assert!(content.contains("next: gpt-4"));
+
+    // .. which are used once
+    let completion = screen.start_send(Some(3)).expect("start send");
+    assert_eq!(completion.parameters(), &next.with_n(3));
+    assert_eq!(screen.next_parameters(), None);
+    screen.cancel_pending();
+
+    let completion = screen.start_send(None).expect("start send");
+    assert_eq!(completion.parameters(), &defaults);
+    screen.cancel_pending();
+
+    // Changing the default parameters changes the conversation
+    screen.set_default_parameters(next.clone());
+    assert_eq!(screen.conversation().default_parameters(), &next);
+}
+
+#[test]
+fn render_markdown() {
+    let mut screen = build_screen();
+    let query = screen.anchor();
+    let response = screen.conversation.add_children_to_message(query, vec![String::from("# Answer\n\nUse **this**")],
+                                                                Role::Assistant)
+        .expect("add response")[0];
+    screen.set_anchor(response).expect("set anchor");
+
+    let draw = |screen: &ChatScreen| {
+        let mut terminal = Terminal::new(TestBackend::new(100, 20)).expect("terminal");
+        terminal.draw(|frame| screen.render(frame, frame.size(), &Theme::default())).expect("draw");
+        terminal.backend().buffer().content().iter()
+            .map(|cell| cell.symbol.clone())
+            .collect::<String>()
+    };
+
+    let content = draw(&screen);
+    assert!(content.contains("Use this"));
+    assert!(!content.contains("# Answer"));
+
+    // The source is shown in raw mode
+    screen.toggle_raw();
+    let content = draw(&screen);
+    assert!(content.contains("# Answer"));
+    assert!(content.contains("Use **this**"));
+    assert!(content.contains("raw"));
+}
+
+#[test]
+fn copy_and_save_prompts() {
+    let mut screen = build_screen();
+    let query = screen.anchor();
+    let text = "Run:\n\n```sh\nls -l\n```\n\nThen:\n\n```rust\nfn main() {}\n```";
+    let response = screen.conversation.add_children_to_message(query, vec![String::from(text)], Role::Assistant)
+        .expect("add response")[0];
+    screen.set_anchor(response).expect("set anchor");
+
+    // The last message is used when none is selected
+    assert_eq!(screen.selected_text(None).as_deref(), Some(text));
+    assert_eq!(screen.selected_text(Some(2)).as_deref(), Some("fn main() {}\n"));
+    assert_eq!(screen.selected_text(Some(0)), None);
+    assert_eq!(screen.selected_text(Some(3)), None);
+
+    let type_command = |screen: &mut ChatScreen, command: &str| {
+        command.chars().for_each(|c| {
+            screen.handle_prompt_key(KeyEvent::from(KeyCode::Char(c)));
+        });
+        screen.handle_prompt_key(KeyEvent::from(KeyCode::Enter))
+    };
+
+    screen.open_copy_prompt();
+    assert!(screen.is_prompting());
+    assert_eq!(type_command(&mut screen, ""), Some(ChatAction::Copy(None)));
+    assert!(!screen.is_prompting());
+
+    screen.open_copy_prompt();
+    assert_eq!(type_command(&mut screen, "2"), Some(ChatAction::Copy(Some(2))));
+
+    screen.open_copy_prompt();
+    assert_eq!(type_command(&mut screen, "two"), None);
+    assert_eq!(screen.status(), Some("Invalid command"));
+
+    // The save prompt takes an optional number before the path
+    screen.open_save_prompt();
+    assert_eq!(type_command(&mut screen, "2 src/main.rs"), Some(ChatAction::Save(2, PathBuf::from("src/main.rs"))));
+    screen.open_save_prompt();
+    assert_eq!(type_command(&mut screen, "run.sh"), Some(ChatAction::Save(1, PathBuf::from("run.sh"))));
+    screen.open_save_prompt();
+    assert_eq!(type_command(&mut screen, " "), None);
+
+    // Esc closes the prompt without an action
+    screen.open_save_prompt();
+    assert_eq!(screen.handle_prompt_key(KeyEvent::from(KeyCode::Esc)), None);
+    assert!(!screen.is_prompting());
+}
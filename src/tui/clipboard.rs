@@ -0,0 +1,69 @@
+use std::env;
+use std::io::{self, Write};
+
+use base64::Engine;
+use base64::engine::general_purpose::STANDARD;
+
+use crate::Result;
+
+/// Module with tests related to the clipboard
+#[cfg(test)]
+mod tests;
+
+/// Clipboard where text was copied
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ClipboardTarget {
+    /// Clipboard of the system running the application
+    System,
+
+    /// Clipboard of the terminal, through an OSC 52 sequence. The text reaches the local machine
+    /// even over SSH, if the terminal supports it.
+    Terminal,
+}
+
+/// Copies text to the clipboard of the system, or to the one of the terminal when the system
+/// clipboard is not available, e.g. over SSH.
+pub struct Clipboard {
+    /// Kept while the application runs, as some systems lose the text copied once it's dropped
+    system: Option<arboard::Clipboard>,
+}
+
+impl Clipboard {
+    pub fn build() -> Self {
+        // Over SSH the system clipboard is the one of the remote machine
+        let system = if is_remote() { None } else { arboard::Clipboard::new().ok() };
+        Clipboard { system }
+    }
+
+    /// Copies the text, returning the clipboard used
+    pub fn copy(&mut self, text: &str) -> Result<ClipboardTarget> {
+        if let Some(system) = self.system.as_mut() {
+            if system.set_text(text).is_ok() {
+                return Ok(ClipboardTarget::System);
+            }
+        }
+
+        let mut stdout = io::stdout();
+        stdout.write_all(osc52(text, env::var_os("TMUX").is_some()).as_bytes())?;
+        stdout.flush()?;
+
+        Ok(ClipboardTarget::Terminal)
+    }
+}
+
+/// Checks if the application runs over SSH
+fn is_remote() -> bool {
+    ["SSH_TTY", "SSH_CONNECTION", "SSH_CLIENT"].iter()
+        .any(|variable| env::var_os(variable).is_some())
+}
+
+/// Returns the sequence that asks the terminal to copy the text. Within tmux, the sequence is
+/// passed through to the outer terminal.
+fn osc52(text: &str, tmux: bool) -> String {
+    let sequence = format!("\x1b]52;c;{}\x07", STANDARD.encode(text));
+    if tmux {
+        format!("\x1bPtmux;\x1b{}\x1b\\", sequence)
+    } else {
+        sequence
+    }
+}
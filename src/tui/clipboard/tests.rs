@@ -0,0 +1,10 @@
+use super::*;
+
+#[test]
+fn terminal_sequence() {
+    assert_eq!(osc52("hello", false), "\x1b]52;c;aGVsbG8=\x07");
+    assert_eq!(osc52("", false), "\x1b]52;c;\x07");
+
+    // Within tmux the sequence is wrapped for passing it through
+    assert_eq!(osc52("hello", true), "\x1bPtmux;\x1b\x1b]52;c;aGVsbG8=\x07\x1b\\");
+}
@@ -0,0 +1,321 @@
+/// Module with tests related to the input editor
+#[cfg(test)]
+mod tests;
+
+/// Kind of the last edit, consecutive edits of the same kind are undone together
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Edit {
+    Insert,
+    Delete,
+}
+
+/// Multi-line editor of the query being written, with undo and a history of the queries sent
+#[derive(Debug, Default)]
+pub struct InputEditor {
+    text: String,
+
+    /// Position of the cursor, in bytes, always at the boundary of a character
+    cursor: usize,
+
+    /// Text and cursor before each edit
+    undo: Vec<(String, usize)>,
+    last_edit: Option<Edit>,
+
+    /// Queries sent, oldest first
+    history: Vec<String>,
+
+    /// Entry of the history being shown, if browsing it
+    history_index: Option<usize>,
+
+    /// Text being written before browsing the history
+    draft: String,
+}
+
+impl InputEditor {
+    pub fn text(&self) -> &str { &self.text }
+    pub fn cursor(&self) -> usize { self.cursor }
+    pub fn history(&self) -> &[String] { &self.history }
+
+    pub fn is_empty(&self) -> bool {
+        self.text.is_empty()
+    }
+
+    /// Replaces the text, moving the cursor to its end. It can be undone.
+    pub fn set_text(&mut self, text: &str) {
+        self.save_undo(None);
+        self.text = normalize(text);
+        self.cursor = self.text.len();
+    }
+
+    /// Removes the text, e.g. after sending it. It can be undone.
+    pub fn clear(&mut self) {
+        self.set_text("");
+        self.history_index = None;
+    }
+
+    /// Stores the state before an edit, unless it continues the previous one
+    fn save_undo(&mut self, edit: Option<Edit>) {
+        if edit.is_none() || edit != self.last_edit {
+            self.undo.push((self.text.clone(), self.cursor));
+        }
+        self.last_edit = edit;
+    }
+
+    /// Restores the text before the last edit, returns if there was any
+    pub fn undo(&mut self) -> bool {
+        let Some((text, cursor)) = self.undo.pop() else {
+            return false;
+        };
+
+        self.text = text;
+        self.cursor = cursor;
+        self.last_edit = None;
+        true
+    }
+
+    /// Inserts a character at the cursor. Words typed are undone together.
+    pub fn insert_char(&mut self, c: char) {
+        let edit = if c.is_whitespace() { None } else { Some(Edit::Insert) };
+        self.save_undo(edit);
+        self.text.insert(self.cursor, c);
+        self.cursor += c.len_utf8();
+    }
+
+    /// Inserts a new line at the cursor
+    pub fn insert_newline(&mut self) {
+        self.insert_char('\n');
+    }
+
+    /// Inserts text at the cursor, e.g. when it's pasted. It's undone at once.
+    pub fn insert_str(&mut self, text: &str) {
+        let text = normalize(text);
+        self.save_undo(None);
+        self.text.insert_str(self.cursor, &text);
+        self.cursor += text.len();
+    }
+
+    /// Deletes the character before the cursor
+    pub fn delete_backward(&mut self) {
+        if let Some(previous) = self.previous_boundary(self.cursor) {
+            self.save_undo(Some(Edit::Delete));
+            self.text.replace_range(previous..self.cursor, "");
+            self.cursor = previous;
+        }
+    }
+
+    /// Deletes the character after the cursor
+    pub fn delete_forward(&mut self) {
+        if let Some(next) = self.next_boundary(self.cursor) {
+            self.save_undo(Some(Edit::Delete));
+            self.text.replace_range(self.cursor..next, "");
+        }
+    }
+
+    /// Deletes from the start of the word before the cursor to the cursor
+    pub fn delete_word_backward(&mut self) {
+        let start = self.word_start(self.cursor);
+        if start < self.cursor {
+            self.save_undo(None);
+            self.text.replace_range(start..self.cursor, "");
+            self.cursor = start;
+        }
+    }
+
+    pub fn move_left(&mut self) {
+        self.move_to(self.previous_boundary(self.cursor).unwrap_or(self.cursor));
+    }
+
+    pub fn move_right(&mut self) {
+        self.move_to(self.next_boundary(self.cursor).unwrap_or(self.cursor));
+    }
+
+    /// Moves the cursor to the start of the word before it
+    pub fn move_word_left(&mut self) {
+        self.move_to(self.word_start(self.cursor));
+    }
+
+    /// Moves the cursor to the end of the word after it
+    pub fn move_word_right(&mut self) {
+        let rest = &self.text[self.cursor..];
+        let offset = rest.char_indices()
+            .skip_while(|(_, c)| !is_word(*c))
+            .find(|(_, c)| !is_word(*c))
+            .map(|(offset, _)| offset)
+            .unwrap_or(rest.len());
+        self.move_to(self.cursor + offset);
+    }
+
+    /// Moves the cursor to the start of its line
+    pub fn move_line_start(&mut self) {
+        self.move_to(self.line_start(self.cursor));
+    }
+
+    /// Moves the cursor to the end of its line
+    pub fn move_line_end(&mut self) {
+        self.move_to(self.line_end(self.cursor));
+    }
+
+    /// Moves the cursor to the previous line, keeping its column when possible. Returns false if
+    /// it's already in the first line.
+    pub fn move_up(&mut self) -> bool {
+        let start = self.line_start(self.cursor);
+        if start == 0 {
+            return false;
+        }
+
+        let column = self.text[start..self.cursor].chars().count();
+        let previous_start = self.line_start(start - 1);
+        self.move_to(self.column_position(previous_start, column));
+        true
+    }
+
+    /// Moves the cursor to the next line, keeping its column when possible. Returns false if
+    /// it's already in the last line.
+    pub fn move_down(&mut self) -> bool {
+        let end = self.line_end(self.cursor);
+        if end == self.text.len() {
+            return false;
+        }
+
+        let column = self.text[self.line_start(self.cursor)..self.cursor].chars().count();
+        self.move_to(self.column_position(end + 1, column));
+        true
+    }
+
+    fn move_to(&mut self, position: usize) {
+        self.cursor = position;
+        self.last_edit = None;
+    }
+
+    /// Replaces the queries of the history
+    pub fn set_history(&mut self, history: Vec<String>) {
+        self.history = history;
+        self.history_index = None;
+    }
+
+    /// Adds a query sent to the history, unless it repeats the last one
+    pub fn push_history(&mut self, query: String) {
+        if self.history.last() != Some(&query) {
+            self.history.push(query);
+        }
+        self.history_index = None;
+    }
+
+    /// Shows the previous query of the history, returns if there was any
+    pub fn history_previous(&mut self) -> bool {
+        let index = match self.history_index {
+            Some(0) => return false,
+            Some(index) => index - 1,
+            None if self.history.is_empty() => return false,
+            None => {
+                self.draft = self.text.clone();
+                self.history.len() - 1
+            }
+        };
+
+        self.show_history(Some(index));
+        true
+    }
+
+    /// Shows the next query of the history, or the text written before browsing it. Returns
+    /// false if the history isn't being browsed.
+    pub fn history_next(&mut self) -> bool {
+        let Some(index) = self.history_index else {
+            return false;
+        };
+
+        let next = Some(index + 1).filter(|next| *next < self.history.len());
+        self.show_history(next);
+        true
+    }
+
+    fn show_history(&mut self, index: Option<usize>) {
+        let text = match index {
+            Some(index) => self.history[index].clone(),
+            None => std::mem::take(&mut self.draft),
+        };
+
+        self.set_text(&text);
+        self.history_index = index;
+    }
+
+    /// Splits the text into lines no longer than the width, returning them with the line and
+    /// column of the cursor within them.
+    pub fn layout(&self, width: usize) -> (Vec<String>, (usize, usize)) {
+        let width = width.max(1);
+
+        let mut lines = Vec::new();
+        let mut cursor = (0, 0);
+        let mut start = 0;
+        for source_line in self.text.split('\n') {
+            let chars: Vec<char> = source_line.chars().collect();
+            let first = lines.len();
+            if chars.is_empty() {
+                lines.push(String::new());
+            }
+            lines.extend(chars.chunks(width).map(|chunk| chunk.iter().collect::<String>()));
+
+            let end = start + source_line.len();
+            if (start..=end).contains(&self.cursor) {
+                let column = self.text[start..self.cursor].chars().count();
+                cursor = (first + column / width, column % width);
+
+                // The cursor after a full line goes to a new one
+                if cursor.0 == lines.len() {
+                    lines.push(String::new());
+                }
+            }
+            start = end + 1;
+        }
+
+        (lines, cursor)
+    }
+
+    fn previous_boundary(&self, position: usize) -> Option<usize> {
+        self.text[..position].char_indices().last().map(|(index, _)| index)
+    }
+
+    fn next_boundary(&self, position: usize) -> Option<usize> {
+        self.text[position..].chars().next().map(|c| position + c.len_utf8())
+    }
+
+    fn word_start(&self, position: usize) -> usize {
+        let before: Vec<(usize, char)> = self.text[..position].char_indices().collect();
+        let mut index = before.len();
+        while index > 0 && !is_word(before[index - 1].1) {
+            index -= 1;
+        }
+        while index > 0 && is_word(before[index - 1].1) {
+            index -= 1;
+        }
+
+        before.get(index).map(|(offset, _)| *offset).unwrap_or(position)
+    }
+
+    fn line_start(&self, position: usize) -> usize {
+        self.text[..position].rfind('\n').map(|index| index + 1).unwrap_or(0)
+    }
+
+    fn line_end(&self, position: usize) -> usize {
+        self.text[position..].find('\n').map(|index| position + index).unwrap_or(self.text.len())
+    }
+
+    /// Returns the position of the column within the line starting at the given position, or
+    /// the end of the line if it's shorter.
+    fn column_position(&self, line_start: usize, column: usize) -> usize {
+        let end = self.line_end(line_start);
+        self.text[line_start..end].char_indices()
+            .nth(column)
+            .map(|(offset, _)| line_start + offset)
+            .unwrap_or(end)
+    }
+}
+
+fn is_word(c: char) -> bool {
+    c.is_alphanumeric() || c == '_'
+}
+
+/// Uses only \n for ending lines, e.g. for pasted text
+fn normalize(text: &str) -> String {
+    text.replace("\r\n", "\n").replace('\r', "\n")
+}
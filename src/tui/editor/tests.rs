@@ -0,0 +1,117 @@
+use super::*;
+
+fn build_editor(text: &str) -> InputEditor {
+    let mut editor = InputEditor::default();
+    editor.insert_str(text);
+    editor
+}
+
+#[test]
+fn edit_at_cursor() {
+    let mut editor = build_editor("hello world");
+    editor.move_word_left();
+    assert_eq!(editor.cursor(), 6);
+    editor.move_left();
+    editor.insert_char(',');
+    assert_eq!(editor.text(), "hello, world");
+
+    editor.move_line_start();
+    editor.delete_forward();
+    editor.insert_char('H');
+    editor.move_line_end();
+    editor.delete_backward();
+    assert_eq!(editor.text(), "Hello, worl");
+
+    // Words are jumped over, skipping punctuation
+    editor.move_line_start();
+    editor.move_word_right();
+    assert_eq!(editor.cursor(), 5);
+    editor.move_word_right();
+    assert_eq!(editor.cursor(), 11);
+    editor.delete_word_backward();
+    assert_eq!(editor.text(), "Hello, ");
+
+    // Multi-byte characters are handled
+    let mut editor = build_editor("añb");
+    editor.move_left();
+    editor.delete_backward();
+    assert_eq!(editor.text(), "ab");
+}
+
+#[test]
+fn move_between_lines() {
+    let mut editor = build_editor("first line\nab\nthird line");
+    assert!(editor.move_up());
+    assert_eq!(editor.cursor(), 13);
+    assert!(editor.move_up());
+    assert_eq!(editor.cursor(), 2);
+    assert!(!editor.move_up());
+
+    assert!(editor.move_down());
+    assert!(editor.move_down());
+    assert_eq!(editor.cursor(), 16);
+    assert!(!editor.move_down());
+}
+
+#[test]
+fn undo_edits() {
+    let mut editor = InputEditor::default();
+    "one two".chars().for_each(|c| editor.insert_char(c));
+    editor.insert_str("\r\npasted");
+    assert_eq!(editor.text(), "one two\npasted");
+
+    // The paste, then each word, is undone at once
+    assert!(editor.undo());
+    assert_eq!(editor.text(), "one two");
+    assert!(editor.undo());
+    assert_eq!(editor.text(), "one ");
+    assert!(editor.undo());
+    assert!(editor.undo());
+    assert_eq!(editor.text(), "");
+    assert!(!editor.undo());
+
+    // Clearing can be undone too
+    editor.set_text("draft");
+    editor.clear();
+    assert!(editor.undo());
+    assert_eq!(editor.text(), "draft");
+}
+
+#[test]
+fn browse_history() {
+    let mut editor = build_editor("draft");
+    assert!(!editor.history_previous());
+
+    editor.set_history(vec![String::from("first"), String::from("second")]);
+    editor.push_history(String::from("second"));
+    assert_eq!(editor.history().len(), 2);
+
+    assert!(editor.history_previous());
+    assert_eq!(editor.text(), "second");
+    assert!(editor.history_previous());
+    assert_eq!(editor.text(), "first");
+    assert!(!editor.history_previous());
+
+    // Going past the newest entry restores the draft
+    assert!(editor.history_next());
+    assert!(editor.history_next());
+    assert_eq!(editor.text(), "draft");
+    assert!(!editor.history_next());
+}
+
+#[test]
+fn layout_lines() {
+    let mut editor = build_editor("abcdef\n\nxy");
+    assert_eq!(editor.layout(4), (vec![String::from("abcd"), String::from("ef"), String::new(), String::from("xy")], (3, 2)));
+
+    editor.move_up();
+    assert_eq!(editor.layout(4).1, (2, 0));
+    editor.move_up();
+    editor.move_line_end();
+    assert_eq!(editor.layout(4).1, (1, 2));
+
+    // The cursor after a full line is shown in a new one
+    let editor = build_editor("abcd");
+    assert_eq!(editor.layout(4), (vec![String::from("abcd"), String::new()], (1, 0)));
+    assert_eq!(InputEditor::default().layout(4), (vec![String::new()], (0, 0)));
+}
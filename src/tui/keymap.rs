@@ -0,0 +1,547 @@
+use std::collections::{BTreeMap, HashMap};
+use std::fmt::{Display, Formatter};
+use std::str::FromStr;
+
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use serde::{Deserialize, Serialize};
+
+use crate::RustGPTError;
+
+/// Module with tests related to the keymap
+#[cfg(test)]
+mod tests;
+
+/// Named action of the application, bound to key chords by the keymap
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Action {
+    // Every part of the screen
+    Quit,
+    ToggleSidebar,
+    ToggleTree,
+    SwitchFocus,
+    ShowHelp,
+
+    // Chat and lists
+    Send,
+    NewLine,
+    Cancel,
+    SelectPrevious,
+    SelectNext,
+    PreviousSibling,
+    NextSibling,
+    ScrollUp,
+    ScrollDown,
+    PageUp,
+    PageDown,
+    ToggleRaw,
+    Copy,
+    SaveCode,
+    OpenEditor,
+    EditParameters,
+    Undo,
+    Left,
+    Right,
+    WordLeft,
+    WordRight,
+    LineStart,
+    LineEnd,
+    Up,
+    Down,
+    DeleteBackward,
+    DeleteForward,
+    DeleteWord,
+    First,
+    Last,
+    Collapse,
+    Expand,
+    Toggle,
+    Open,
+    Filter,
+    New,
+    Rename,
+    Delete,
+}
+
+impl Action {
+    /// Actions in the order they are listed in the help
+    pub const ALL: [Action; 43] = [
+        Action::Quit, Action::ToggleSidebar, Action::ToggleTree, Action::SwitchFocus, Action::ShowHelp,
+        Action::Send, Action::NewLine, Action::Cancel, Action::SelectPrevious, Action::SelectNext,
+        Action::PreviousSibling, Action::NextSibling, Action::ScrollUp, Action::ScrollDown, Action::PageUp,
+        Action::PageDown, Action::ToggleRaw, Action::Copy, Action::SaveCode, Action::OpenEditor,
+        Action::EditParameters, Action::Undo,
+        Action::Left, Action::Right, Action::WordLeft, Action::WordRight, Action::LineStart, Action::LineEnd,
+        Action::Up, Action::Down, Action::DeleteBackward, Action::DeleteForward, Action::DeleteWord,
+        Action::First, Action::Last, Action::Collapse, Action::Expand, Action::Toggle, Action::Open,
+        Action::Filter, Action::New, Action::Rename, Action::Delete,
+    ];
+
+    /// Returns what the action does within the given part of the screen
+    pub fn description(&self, context: KeyContext) -> &'static str {
+        match (self, context) {
+            (Action::Quit, _) => "Quit",
+            (Action::ToggleSidebar, _) => "Show or hide the conversations",
+            (Action::ToggleTree, _) => "Show or hide the tree",
+            (Action::SwitchFocus, _) => "Move to the next panel",
+            (Action::ShowHelp, _) => "Show this help",
+            (Action::Send, _) => "Send the message",
+            (Action::NewLine, _) => "Insert a new line",
+            (Action::Cancel, KeyContext::Chat) => "Clear the selection, cancel the response, or quit",
+            (Action::Cancel, _) => "Go back to the chat",
+            (Action::SelectPrevious, _) => "Select the previous message",
+            (Action::SelectNext, _) => "Select the next message",
+            (Action::PreviousSibling, _) => "Show the previous version of the message",
+            (Action::NextSibling, _) => "Show the next version of the message",
+            (Action::ScrollUp, _) => "Scroll up a line",
+            (Action::ScrollDown, _) => "Scroll down a line",
+            (Action::PageUp, _) => "Scroll up a page",
+            (Action::PageDown, _) => "Scroll down a page",
+            (Action::ToggleRaw, _) => "Show the source of the messages or their Markdown",
+            (Action::Copy, _) => "Copy the message or one of its code blocks",
+            (Action::SaveCode, _) => "Save a code block of the message to a file",
+            (Action::OpenEditor, _) => "Edit the message in $EDITOR",
+            (Action::EditParameters, _) => "Change the parameters of the completions",
+            (Action::Undo, _) => "Undo the last edit",
+            (Action::Left, _) => "Move the cursor left",
+            (Action::Right, _) => "Move the cursor right",
+            (Action::WordLeft, _) => "Move the cursor to the previous word",
+            (Action::WordRight, _) => "Move the cursor to the next word",
+            (Action::LineStart, _) => "Move the cursor to the start of the line",
+            (Action::LineEnd, _) => "Move the cursor to the end of the line",
+            (Action::Up, KeyContext::Chat) => "Move the cursor up, or show the previous message sent",
+            (Action::Down, KeyContext::Chat) => "Move the cursor down, or show the next message sent",
+            (Action::Up, _) => "Move up",
+            (Action::Down, _) => "Move down",
+            (Action::DeleteBackward, _) => "Delete the character before the cursor",
+            (Action::DeleteForward, _) => "Delete the character after the cursor",
+            (Action::DeleteWord, _) => "Delete the word before the cursor",
+            (Action::First, _) => "Move to the first entry",
+            (Action::Last, _) => "Move to the last entry",
+            (Action::Collapse, _) => "Collapse the node, or move to its parent",
+            (Action::Expand, _) => "Expand the node, or move to its first child",
+            (Action::Toggle, _) => "Collapse or expand the node",
+            (Action::Open, _) => "Open the selected entry",
+            (Action::Filter, _) => "Filter the conversations by name",
+            (Action::New, _) => "Create a conversation",
+            (Action::Rename, _) => "Rename the selected conversation",
+            (Action::Delete, _) => "Delete the selected conversation",
+        }
+    }
+}
+
+/// Part of the screen where a binding applies
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum KeyContext {
+    /// Every part of the screen, before the other contexts
+    Global,
+
+    /// Chat and its input
+    Chat,
+
+    /// Conversation list and tree
+    List,
+}
+
+impl KeyContext {
+    pub const ALL: [KeyContext; 3] = [KeyContext::Global, KeyContext::Chat, KeyContext::List];
+
+    pub fn title(&self) -> &'static str {
+        match self {
+            KeyContext::Global => "Everywhere",
+            KeyContext::Chat => "Chat",
+            KeyContext::List => "Conversations and tree",
+        }
+    }
+}
+
+/// Key pressed with its modifiers, e.g. `ctrl+c`, `alt+enter` or `G`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct KeyChord {
+    code: KeyCode,
+    modifiers: KeyModifiers,
+}
+
+impl KeyChord {
+    pub fn new(code: KeyCode, modifiers: KeyModifiers) -> Self {
+        // Shift is part of the character, e.g. G instead of shift+g
+        let modifiers = match code {
+            KeyCode::Char(_) | KeyCode::BackTab => modifiers - KeyModifiers::SHIFT,
+            _ => modifiers,
+        };
+
+        KeyChord { code, modifiers }
+    }
+}
+
+impl From<&KeyEvent> for KeyChord {
+    fn from(key: &KeyEvent) -> Self {
+        KeyChord::new(key.code, key.modifiers)
+    }
+}
+
+impl FromStr for KeyChord {
+    type Err = RustGPTError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || RustGPTError::InvalidSetting(format!("key {}", s));
+
+        // The key is the last part, so "ctrl++" is the plus key
+        let (modifier_names, key) = match s.strip_suffix("++") {
+            Some(rest) => (rest, "+"),
+            None => s.rsplit_once('+').unwrap_or(("", s)),
+        };
+
+        let mut modifiers = KeyModifiers::NONE;
+        for name in modifier_names.split('+').filter(|name| !name.is_empty()) {
+            modifiers |= match name.to_lowercase().as_str() {
+                "ctrl" | "control" => KeyModifiers::CONTROL,
+                "alt" | "meta" => KeyModifiers::ALT,
+                "shift" => KeyModifiers::SHIFT,
+                _ => return Err(invalid()),
+            };
+        }
+
+        let mut chars = key.chars();
+        let code = match (chars.next(), chars.next()) {
+            (Some(c), None) if modifiers.contains(KeyModifiers::SHIFT) => KeyCode::Char(c.to_ascii_uppercase()),
+            (Some(c), None) => KeyCode::Char(c),
+            _ => match key.to_lowercase().as_str() {
+                "enter" | "return" => KeyCode::Enter,
+                "esc" | "escape" => KeyCode::Esc,
+                "tab" => KeyCode::Tab,
+                "backtab" => KeyCode::BackTab,
+                "backspace" => KeyCode::Backspace,
+                "delete" | "del" => KeyCode::Delete,
+                "insert" => KeyCode::Insert,
+                "left" => KeyCode::Left,
+                "right" => KeyCode::Right,
+                "up" => KeyCode::Up,
+                "down" => KeyCode::Down,
+                "home" => KeyCode::Home,
+                "end" => KeyCode::End,
+                "pageup" => KeyCode::PageUp,
+                "pagedown" => KeyCode::PageDown,
+                "space" => KeyCode::Char(' '),
+                "plus" => KeyCode::Char('+'),
+                name => match name.strip_prefix('f').and_then(|number| number.parse().ok()) {
+                    Some(number) if (1..=12).contains(&number) => KeyCode::F(number),
+                    _ => return Err(invalid()),
+                },
+            },
+        };
+
+        Ok(KeyChord::new(code, modifiers))
+    }
+}
+
+impl Display for KeyChord {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        if self.modifiers.contains(KeyModifiers::CONTROL) {
+            write!(f, "Ctrl+")?;
+        }
+        if self.modifiers.contains(KeyModifiers::ALT) {
+            write!(f, "Alt+")?;
+        }
+        if self.modifiers.contains(KeyModifiers::SHIFT) {
+            write!(f, "Shift+")?;
+        }
+
+        match self.code {
+            KeyCode::Char(' ') => write!(f, "Space"),
+            KeyCode::Char(c) => write!(f, "{}", c),
+            KeyCode::F(number) => write!(f, "F{}", number),
+            KeyCode::Enter => write!(f, "Enter"),
+            KeyCode::Esc => write!(f, "Esc"),
+            KeyCode::PageUp => write!(f, "PageUp"),
+            KeyCode::PageDown => write!(f, "PageDown"),
+            code => write!(f, "{:?}", code),
+        }
+    }
+}
+
+/// Set of bindings the keymap starts from
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Preset {
+    #[default]
+    Default,
+
+    /// Vim-like navigation, e.g. ctrl+u and ctrl+d for scrolling
+    Vim,
+
+    /// Readline-like editing, e.g. ctrl+a, ctrl+e and ctrl+f
+    Emacs,
+}
+
+/// Keymap settings of the configuration file: a preset, and bindings that replace its own
+/// ones. Binding a key to null removes it.
+///
+/// # Examples
+///
+/// ```yaml
+/// preset: emacs
+/// chat:
+///   ctrl+s: send
+///   enter: new_line
+/// global:
+///   ctrl+b: null
+/// ```
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+#[serde(default)]
+pub struct KeymapOptions {
+    preset: Preset,
+    global: BTreeMap<String, Option<Action>>,
+    chat: BTreeMap<String, Option<Action>>,
+    list: BTreeMap<String, Option<Action>>,
+}
+
+impl KeymapOptions {
+    pub fn preset(&self) -> Preset { self.preset }
+
+    pub fn with_preset(mut self, preset: Preset) -> Self {
+        self.preset = preset;
+        self
+    }
+
+    /// Binds a key within the given context, or removes its binding if None
+    pub fn with_binding(mut self, context: KeyContext, key: &str, action: Option<Action>) -> Self {
+        let bindings = match context {
+            KeyContext::Global => &mut self.global,
+            KeyContext::Chat => &mut self.chat,
+            KeyContext::List => &mut self.list,
+        };
+        bindings.insert(key.to_string(), action);
+        self
+    }
+}
+
+/// Bindings of every preset
+const DEFAULT_BINDINGS: &[(KeyContext, &str, Action)] = &[
+    (KeyContext::Global, "ctrl+c", Action::Quit),
+    (KeyContext::Global, "ctrl+b", Action::ToggleSidebar),
+    (KeyContext::Global, "ctrl+t", Action::ToggleTree),
+    (KeyContext::Global, "tab", Action::SwitchFocus),
+    (KeyContext::Global, "f1", Action::ShowHelp),
+    (KeyContext::Chat, "enter", Action::Send),
+    (KeyContext::Chat, "alt+enter", Action::NewLine),
+    (KeyContext::Chat, "ctrl+j", Action::NewLine),
+    (KeyContext::Chat, "esc", Action::Cancel),
+    (KeyContext::Chat, "alt+up", Action::SelectPrevious),
+    (KeyContext::Chat, "alt+down", Action::SelectNext),
+    (KeyContext::Chat, "alt+left", Action::PreviousSibling),
+    (KeyContext::Chat, "alt+right", Action::NextSibling),
+    (KeyContext::Chat, "ctrl+up", Action::ScrollUp),
+    (KeyContext::Chat, "ctrl+down", Action::ScrollDown),
+    (KeyContext::Chat, "pageup", Action::PageUp),
+    (KeyContext::Chat, "pagedown", Action::PageDown),
+    (KeyContext::Chat, "ctrl+r", Action::ToggleRaw),
+    (KeyContext::Chat, "ctrl+y", Action::Copy),
+    (KeyContext::Chat, "ctrl+s", Action::SaveCode),
+    (KeyContext::Chat, "ctrl+o", Action::OpenEditor),
+    (KeyContext::Chat, "f2", Action::EditParameters),
+    (KeyContext::Chat, "ctrl+z", Action::Undo),
+    (KeyContext::Chat, "left", Action::Left),
+    (KeyContext::Chat, "right", Action::Right),
+    (KeyContext::Chat, "ctrl+left", Action::WordLeft),
+    (KeyContext::Chat, "ctrl+right", Action::WordRight),
+    (KeyContext::Chat, "alt+b", Action::WordLeft),
+    (KeyContext::Chat, "alt+f", Action::WordRight),
+    (KeyContext::Chat, "home", Action::LineStart),
+    (KeyContext::Chat, "ctrl+a", Action::LineStart),
+    (KeyContext::Chat, "end", Action::LineEnd),
+    (KeyContext::Chat, "ctrl+e", Action::LineEnd),
+    (KeyContext::Chat, "up", Action::Up),
+    (KeyContext::Chat, "down", Action::Down),
+    (KeyContext::Chat, "backspace", Action::DeleteBackward),
+    (KeyContext::Chat, "delete", Action::DeleteForward),
+    (KeyContext::Chat, "ctrl+w", Action::DeleteWord),
+    (KeyContext::Chat, "ctrl+backspace", Action::DeleteWord),
+    (KeyContext::Chat, "alt+backspace", Action::DeleteWord),
+    (KeyContext::List, "up", Action::Up),
+    (KeyContext::List, "k", Action::Up),
+    (KeyContext::List, "down", Action::Down),
+    (KeyContext::List, "j", Action::Down),
+    (KeyContext::List, "pageup", Action::PageUp),
+    (KeyContext::List, "pagedown", Action::PageDown),
+    (KeyContext::List, "home", Action::First),
+    (KeyContext::List, "g", Action::First),
+    (KeyContext::List, "end", Action::Last),
+    (KeyContext::List, "G", Action::Last),
+    (KeyContext::List, "left", Action::Collapse),
+    (KeyContext::List, "h", Action::Collapse),
+    (KeyContext::List, "right", Action::Expand),
+    (KeyContext::List, "l", Action::Expand),
+    (KeyContext::List, "space", Action::Toggle),
+    (KeyContext::List, "enter", Action::Open),
+    (KeyContext::List, "esc", Action::Cancel),
+    (KeyContext::List, "/", Action::Filter),
+    (KeyContext::List, "n", Action::New),
+    (KeyContext::List, "r", Action::Rename),
+    (KeyContext::List, "d", Action::Delete),
+];
+
+/// Changes of the vim preset to the default bindings
+const VIM_BINDINGS: &[(KeyContext, &str, Option<Action>)] = &[
+    (KeyContext::Chat, "ctrl+j", Some(Action::SelectNext)),
+    (KeyContext::Chat, "ctrl+k", Some(Action::SelectPrevious)),
+    (KeyContext::Chat, "alt+h", Some(Action::PreviousSibling)),
+    (KeyContext::Chat, "alt+l", Some(Action::NextSibling)),
+    (KeyContext::Chat, "ctrl+u", Some(Action::PageUp)),
+    (KeyContext::Chat, "ctrl+d", Some(Action::PageDown)),
+    (KeyContext::Chat, "ctrl+y", Some(Action::ScrollUp)),
+    (KeyContext::Chat, "ctrl+e", Some(Action::ScrollDown)),
+    (KeyContext::Chat, "alt+y", Some(Action::Copy)),
+    (KeyContext::Chat, "alt+w", Some(Action::SaveCode)),
+    (KeyContext::Chat, "ctrl+a", None),
+    (KeyContext::List, "ctrl+u", Some(Action::PageUp)),
+    (KeyContext::List, "ctrl+d", Some(Action::PageDown)),
+    (KeyContext::List, "q", Some(Action::Cancel)),
+    (KeyContext::List, "o", Some(Action::New)),
+];
+
+/// Changes of the emacs preset to the default bindings
+const EMACS_BINDINGS: &[(KeyContext, &str, Option<Action>)] = &[
+    (KeyContext::Global, "ctrl+b", None),
+    (KeyContext::Global, "ctrl+t", None),
+    (KeyContext::Global, "f2", Some(Action::ToggleSidebar)),
+    (KeyContext::Global, "f3", Some(Action::ToggleTree)),
+    (KeyContext::Chat, "f2", None),
+    (KeyContext::Chat, "f4", Some(Action::EditParameters)),
+    (KeyContext::Chat, "ctrl+b", Some(Action::Left)),
+    (KeyContext::Chat, "ctrl+f", Some(Action::Right)),
+    (KeyContext::Chat, "ctrl+p", Some(Action::Up)),
+    (KeyContext::Chat, "ctrl+n", Some(Action::Down)),
+    (KeyContext::Chat, "ctrl+d", Some(Action::DeleteForward)),
+    (KeyContext::Chat, "ctrl+_", Some(Action::Undo)),
+    (KeyContext::Chat, "ctrl+g", Some(Action::Cancel)),
+    (KeyContext::Chat, "alt+p", Some(Action::SelectPrevious)),
+    (KeyContext::Chat, "alt+n", Some(Action::SelectNext)),
+    (KeyContext::Chat, "alt+v", Some(Action::PageUp)),
+    (KeyContext::Chat, "ctrl+v", Some(Action::PageDown)),
+    (KeyContext::Chat, "alt+w", Some(Action::Copy)),
+    (KeyContext::List, "ctrl+p", Some(Action::Up)),
+    (KeyContext::List, "ctrl+n", Some(Action::Down)),
+    (KeyContext::List, "ctrl+b", Some(Action::Collapse)),
+    (KeyContext::List, "ctrl+f", Some(Action::Expand)),
+    (KeyContext::List, "alt+<", Some(Action::First)),
+    (KeyContext::List, "alt+>", Some(Action::Last)),
+    (KeyContext::List, "ctrl+g", Some(Action::Cancel)),
+    (KeyContext::List, "ctrl+s", Some(Action::Filter)),
+];
+
+/// Actions of a context listed in the help, with the keys bound to them
+pub type HelpSection = (KeyContext, Vec<(Action, Vec<KeyChord>)>);
+
+/// Bindings of key chords to actions, for each part of the screen
+#[derive(Debug, Clone, PartialEq)]
+pub struct Keymap {
+    bindings: HashMap<KeyContext, HashMap<KeyChord, Action>>,
+}
+
+impl Default for Keymap {
+    fn default() -> Self {
+        Self::preset(Preset::Default)
+    }
+}
+
+impl Keymap {
+    /// Creates the keymap with the bindings of the preset
+    pub fn preset(preset: Preset) -> Self {
+        let mut keymap = Keymap { bindings: HashMap::new() };
+        for &(context, key, action) in DEFAULT_BINDINGS {
+            keymap.bind(context, key.parse().expect("valid default key"), Some(action));
+        }
+
+        let changes = match preset {
+            Preset::Default => &[][..],
+            Preset::Vim => VIM_BINDINGS,
+            Preset::Emacs => EMACS_BINDINGS,
+        };
+        for &(context, key, action) in changes {
+            keymap.bind(context, key.parse().expect("valid preset key"), action);
+        }
+
+        keymap
+    }
+
+    /// Creates the keymap of the settings, failing on keys that can't be parsed
+    pub fn build(options: &KeymapOptions) -> crate::Result<Self> {
+        let mut keymap = Self::preset(options.preset);
+        let contexts = [
+            (KeyContext::Global, &options.global),
+            (KeyContext::Chat, &options.chat),
+            (KeyContext::List, &options.list),
+        ];
+        for (context, bindings) in contexts {
+            for (key, action) in bindings {
+                keymap.bind(context, key.parse()?, *action);
+            }
+        }
+
+        Ok(keymap)
+    }
+
+    /// Binds the chord to the action within the context, or removes its binding if None
+    pub fn bind(&mut self, context: KeyContext, chord: KeyChord, action: Option<Action>) {
+        let bindings = self.bindings.entry(context).or_default();
+        match action {
+            Some(action) => bindings.insert(chord, action),
+            None => bindings.remove(&chord),
+        };
+    }
+
+    /// Returns the action bound to the key within the context, if any
+    pub fn action(&self, context: KeyContext, key: &KeyEvent) -> Option<Action> {
+        self.bindings.get(&context)?.get(&KeyChord::from(key)).copied()
+    }
+
+    /// Returns the chords bound to the action within the context, sorted by how they're shown
+    pub fn keys(&self, context: KeyContext, action: Action) -> Vec<KeyChord> {
+        let mut chords: Vec<KeyChord> = self.bindings.get(&context)
+            .map(|bindings| bindings.iter()
+                .filter(|(_, bound)| **bound == action)
+                .map(|(chord, _)| *chord)
+                .collect())
+            .unwrap_or_default();
+        chords.sort_by_key(|chord| (chord.modifiers.bits(), chord.to_string()));
+        chords
+    }
+
+    /// Returns the keys of the main actions of the input, e.g. "Enter to send, F1 for help"
+    pub fn hint(&self) -> String {
+        self.describe(&[(KeyContext::Chat, Action::Send, "to send"), (KeyContext::Chat, Action::NewLine, "for a new line"),
+                        (KeyContext::Chat, Action::OpenEditor, "to open in $EDITOR"),
+                        (KeyContext::Global, Action::ShowHelp, "for help")], ", ")
+    }
+
+    /// Returns the keys of the commands of the conversation list, e.g. "/ filter  n new"
+    pub fn sidebar_hint(&self) -> String {
+        self.describe(&[(KeyContext::List, Action::Filter, "filter"), (KeyContext::List, Action::New, "new"),
+                        (KeyContext::List, Action::Rename, "rename"), (KeyContext::List, Action::Delete, "delete")],
+                      "  ")
+    }
+
+    /// Joins the first key of each bound action followed by its text, leaving out unbound actions
+    fn describe(&self, actions: &[(KeyContext, Action, &str)], separator: &str) -> String {
+        actions.iter()
+            .filter_map(|&(context, action, text)| {
+                self.keys(context, action).first().map(|key| format!("{} {}", key, text))
+            })
+            .collect::<Vec<_>>()
+            .join(separator)
+    }
+
+    /// Returns the bound actions of each context, with their keys, in the order of the help
+    pub fn help(&self) -> Vec<HelpSection> {
+        KeyContext::ALL.iter()
+            .map(|&context| {
+                let actions = Action::ALL.iter()
+                    .map(|&action| (action, self.keys(context, action)))
+                    .filter(|(_, keys)| !keys.is_empty())
+                    .collect();
+                (context, actions)
+            })
+            .collect()
+    }
+}
@@ -0,0 +1,120 @@
+use super::*;
+
+fn key(code: KeyCode, modifiers: KeyModifiers) -> KeyEvent {
+    KeyEvent::new(code, modifiers)
+}
+
+#[test]
+fn parse_chords() {
+    let chord = |s: &str| s.parse::<KeyChord>().expect(s);
+
+    assert_eq!(chord("ctrl+c"), KeyChord::new(KeyCode::Char('c'), KeyModifiers::CONTROL));
+    assert_eq!(chord("Alt+Enter"), KeyChord::new(KeyCode::Enter, KeyModifiers::ALT));
+    assert_eq!(chord("f1"), KeyChord::new(KeyCode::F(1), KeyModifiers::NONE));
+    assert_eq!(chord("space"), KeyChord::new(KeyCode::Char(' '), KeyModifiers::NONE));
+    assert_eq!(chord("ctrl++"), KeyChord::new(KeyCode::Char('+'), KeyModifiers::CONTROL));
+
+    // Shift is part of the character, as terminals report it
+    assert_eq!(chord("G"), chord("shift+g"));
+    assert_eq!(KeyChord::from(&key(KeyCode::Char('G'), KeyModifiers::SHIFT)), chord("G"));
+
+    assert!("hyper+x".parse::<KeyChord>().is_err());
+    assert!("f13".parse::<KeyChord>().is_err());
+    assert!("nokey".parse::<KeyChord>().is_err());
+
+    assert_eq!(chord("ctrl+alt+pageup").to_string(), "Ctrl+Alt+PageUp");
+    assert_eq!(chord("G").to_string(), "G");
+    assert_eq!(chord("space").to_string(), "Space");
+}
+
+#[test]
+fn presets() {
+    let ctrl = |c| key(KeyCode::Char(c), KeyModifiers::CONTROL);
+
+    let keymap = Keymap::default();
+    assert_eq!(keymap.action(KeyContext::Global, &ctrl('c')), Some(Action::Quit));
+    assert_eq!(keymap.action(KeyContext::Global, &ctrl('b')), Some(Action::ToggleSidebar));
+    assert_eq!(keymap.action(KeyContext::Chat, &key(KeyCode::Enter, KeyModifiers::NONE)), Some(Action::Send));
+    assert_eq!(keymap.action(KeyContext::Chat, &ctrl('u')), None);
+
+    let vim = Keymap::preset(Preset::Vim);
+    assert_eq!(vim.action(KeyContext::Chat, &ctrl('u')), Some(Action::PageUp));
+    assert_eq!(vim.action(KeyContext::List, &key(KeyCode::Char('q'), KeyModifiers::NONE)), Some(Action::Cancel));
+
+    // Emacs moves the sidebar toggle out of the way of the cursor movements
+    let emacs = Keymap::preset(Preset::Emacs);
+    assert_eq!(emacs.action(KeyContext::Global, &ctrl('b')), None);
+    assert_eq!(emacs.action(KeyContext::Chat, &ctrl('b')), Some(Action::Left));
+    assert_eq!(emacs.action(KeyContext::Chat, &key(KeyCode::F(4), KeyModifiers::NONE)), Some(Action::EditParameters));
+
+    // Every action can be reached in every preset, without being shadowed by a global binding
+    for preset in [Preset::Default, Preset::Vim, Preset::Emacs] {
+        let keymap = Keymap::preset(preset);
+        for action in Action::ALL {
+            let reachable = KeyContext::ALL.iter().any(|&context| {
+                keymap.keys(context, action).iter().any(|chord| {
+                    let key = key(chord.code, chord.modifiers);
+                    context == KeyContext::Global || keymap.action(KeyContext::Global, &key).is_none()
+                })
+            });
+            assert!(reachable, "{:?} is not bound in the {:?} preset", action, preset);
+        }
+    }
+}
+
+#[test]
+fn user_bindings() {
+    let options = KeymapOptions::default()
+        .with_preset(Preset::Vim)
+        .with_binding(KeyContext::Chat, "ctrl+s", Some(Action::Send))
+        .with_binding(KeyContext::Chat, "enter", Some(Action::NewLine))
+        .with_binding(KeyContext::Global, "f1", None);
+    let keymap = Keymap::build(&options).expect("keymap");
+
+    let ctrl_s = key(KeyCode::Char('s'), KeyModifiers::CONTROL);
+    assert_eq!(keymap.action(KeyContext::Chat, &ctrl_s), Some(Action::Send));
+    assert_eq!(keymap.action(KeyContext::Chat, &key(KeyCode::Enter, KeyModifiers::NONE)), Some(Action::NewLine));
+    assert_eq!(keymap.action(KeyContext::Global, &key(KeyCode::F(1), KeyModifiers::NONE)), None);
+    assert_eq!(keymap.action(KeyContext::Chat, &key(KeyCode::Char('u'), KeyModifiers::CONTROL)), Some(Action::PageUp));
+
+    let invalid = KeymapOptions::default().with_binding(KeyContext::Chat, "ctrl+nokey", Some(Action::Send));
+    assert!(Keymap::build(&invalid).is_err());
+
+    // Options are read from the configuration file
+    let options: KeymapOptions = serde_yaml::from_str("preset: emacs\nchat:\n  ctrl+s: send\n  enter: null\n")
+        .expect("options");
+    assert_eq!(options.preset(), Preset::Emacs);
+    let keymap = Keymap::build(&options).expect("keymap");
+    assert_eq!(keymap.action(KeyContext::Chat, &ctrl_s), Some(Action::Send));
+    assert_eq!(keymap.action(KeyContext::Chat, &key(KeyCode::Enter, KeyModifiers::NONE)), None);
+}
+
+#[test]
+fn help() {
+    let options = KeymapOptions::default().with_binding(KeyContext::Global, "f2", Some(Action::ShowHelp));
+    let keymap = Keymap::build(&options).expect("keymap");
+    let help = keymap.help();
+
+    assert_eq!(help.iter().map(|(context, _)| *context).collect::<Vec<_>>(), KeyContext::ALL.to_vec());
+
+    let (_, global) = &help[0];
+    let (_, keys) = global.iter().find(|(action, _)| *action == Action::ShowHelp).expect("help");
+    assert_eq!(keys.iter().map(ToString::to_string).collect::<Vec<_>>(), vec!["F1", "F2"]);
+
+    // Unbound actions are not listed
+    assert!(global.iter().all(|(_, keys)| !keys.is_empty()));
+
+    assert_eq!(Keymap::default().hint(),
+               "Enter to send, Ctrl+j for a new line, Ctrl+o to open in $EDITOR, F1 for help");
+    let unbound = KeymapOptions::default().with_binding(KeyContext::Chat, "ctrl+o", None);
+    assert!(!Keymap::build(&unbound).expect("keymap").hint().contains("$EDITOR"));
+
+    // The commands of the list are listed with their keys
+    let (_, list) = &help[2];
+    assert!(list.iter().any(|(action, _)| *action == Action::Delete));
+    assert_eq!(Keymap::default().sidebar_hint(), "/ filter  n new  r rename  d delete");
+    let rebound = KeymapOptions::default()
+        .with_binding(KeyContext::List, "d", None)
+        .with_binding(KeyContext::List, "x", Some(Action::Delete));
+    assert_eq!(Keymap::build(&rebound).expect("keymap").sidebar_hint(), "/ filter  n new  r rename  x delete");
+}
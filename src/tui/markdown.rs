@@ -0,0 +1,525 @@
+use std::sync::OnceLock;
+
+use pulldown_cmark::{Alignment, CodeBlockKind, Event, HeadingLevel, Options, Parser, Tag};
+use ratatui::style::{Color, Modifier, Style};
+use ratatui::text::{Line, Span};
+use syntect::easy::HighlightLines;
+use syntect::highlighting::{FontStyle, Theme, ThemeSet};
+use syntect::parsing::SyntaxSet;
+use syntect::util::LinesWithEndings;
+
+/// Module with tests related to the rendering of Markdown
+#[cfg(test)]
+mod tests;
+
+/// Theme used for highlighting code
+const CODE_THEME: &str = "base16-ocean.dark";
+
+/// Style of code without a known language
+const CODE_STYLE: Style = Style::new().fg(Color::Yellow);
+
+/// Style of the decorations, e.g. quote bars and table borders
+const DECORATION_STYLE: Style = Style::new().fg(Color::DarkGray);
+
+/// Renders the Markdown text into styled lines no longer than the width. Code blocks aren't
+/// wrapped, so their lines may be longer.
+pub fn render(text: &str, width: usize) -> Vec<Line<'static>> {
+    let mut renderer = Renderer::new(width.max(1));
+    for event in Parser::new_ext(text, parser_options()) {
+        renderer.handle(event);
+    }
+
+    renderer.finish()
+}
+
+/// Block containing the text being rendered, which indents it
+#[derive(Debug)]
+enum Container {
+    Quote,
+
+    /// Item of a list, indented by the width of its marker
+    Item { marker: String, marked: bool },
+}
+
+/// Fenced or indented block of code within a message
+#[derive(Debug, Clone, PartialEq)]
+pub struct CodeBlock {
+    /// Language tag of the fence, empty if none
+    language: String,
+    code: String,
+}
+
+impl CodeBlock {
+    pub fn language(&self) -> &str { &self.language }
+    pub fn code(&self) -> &str { &self.code }
+}
+
+/// Returns the code blocks of the Markdown text, in the order they are numbered when rendered
+pub fn code_blocks(text: &str) -> Vec<CodeBlock> {
+    let mut blocks = Vec::new();
+    let mut current: Option<CodeBlock> = None;
+    for event in Parser::new_ext(text, parser_options()) {
+        match event {
+            Event::Start(Tag::CodeBlock(kind)) => current = Some(CodeBlock { language: language(&kind), code: String::new() }),
+            Event::Text(text) => if let Some(block) = current.as_mut() {
+                block.code.push_str(&text);
+            },
+            Event::End(Tag::CodeBlock(_)) => blocks.extend(current.take()),
+            _ => {}
+        }
+    }
+
+    // The fence may not be closed yet
+    blocks.extend(current);
+    blocks
+}
+
+fn parser_options() -> Options {
+    Options::ENABLE_TABLES | Options::ENABLE_STRIKETHROUGH | Options::ENABLE_TASKLISTS
+}
+
+fn language(kind: &CodeBlockKind) -> String {
+    match kind {
+        CodeBlockKind::Fenced(info) => info.split_whitespace().next().unwrap_or_default().to_string(),
+        CodeBlockKind::Indented => String::new(),
+    }
+}
+
+/// Table being read, whose columns are aligned once it ends
+#[derive(Debug, Default)]
+struct Table {
+    alignments: Vec<Alignment>,
+    rows: Vec<Vec<String>>,
+
+    /// Number of rows in the head
+    head: usize,
+}
+
+/// Turns the events of the Markdown parser into lines
+struct Renderer {
+    width: usize,
+    lines: Vec<Line<'static>>,
+
+    /// Text of the current block, wrapped once it ends
+    spans: Vec<Span<'static>>,
+    styles: Vec<Style>,
+    containers: Vec<Container>,
+
+    /// Next number of each list being rendered, None for bullet lists
+    lists: Vec<Option<u64>>,
+    code: Option<CodeBlock>,
+    table: Option<Table>,
+
+    /// Code blocks rendered so far, for numbering them
+    code_blocks: usize,
+}
+
+impl Renderer {
+    fn new(width: usize) -> Self {
+        Renderer {
+            width,
+            lines: Vec::new(),
+            spans: Vec::new(),
+            styles: vec![Style::default()],
+            containers: Vec::new(),
+            lists: Vec::new(),
+            code: None,
+            table: None,
+            code_blocks: 0,
+        }
+    }
+
+    fn style(&self) -> Style {
+        self.styles.last().copied().unwrap_or_default()
+    }
+
+    fn push_style(&mut self, style: Style) {
+        self.styles.push(self.style().patch(style));
+    }
+
+    fn pop_style(&mut self) {
+        if self.styles.len() > 1 {
+            self.styles.pop();
+        }
+    }
+
+    fn handle(&mut self, event: Event) {
+        match event {
+            Event::Start(tag) => self.start(tag),
+            Event::End(tag) => self.end(tag),
+            Event::Text(text) => self.push_text(&text, self.style()),
+            Event::Code(code) => self.push_text(&code, self.style().patch(CODE_STYLE)),
+            Event::Html(html) => self.push_text(&html, DECORATION_STYLE),
+            Event::FootnoteReference(name) => self.push_text(&format!("[^{}]", name), DECORATION_STYLE),
+            Event::SoftBreak => self.push_text(" ", self.style()),
+            Event::HardBreak => self.push_text("\n", self.style()),
+            Event::Rule => {
+                self.flush();
+                let width = self.width.saturating_sub(self.prefix_width());
+                self.spans.push(Span::styled("─".repeat(width), DECORATION_STYLE));
+                self.flush();
+                self.blank_line();
+            }
+            Event::TaskListMarker(checked) => {
+                let marker = if checked { "[x] " } else { "[ ] " };
+                self.push_text(marker, DECORATION_STYLE);
+            }
+        }
+    }
+
+    fn start(&mut self, tag: Tag) {
+        match tag {
+            Tag::Paragraph => {}
+            Tag::Heading(level, _, _) => {
+                self.flush();
+                let style = match level {
+                    HeadingLevel::H1 => Style::default().fg(Color::Magenta)
+                        .add_modifier(Modifier::BOLD | Modifier::UNDERLINED),
+                    HeadingLevel::H2 => Style::default().fg(Color::Magenta).add_modifier(Modifier::BOLD),
+                    _ => Style::default().add_modifier(Modifier::BOLD),
+                };
+                self.push_style(style);
+            }
+            Tag::BlockQuote => {
+                self.flush();
+                self.containers.push(Container::Quote);
+                self.push_style(Style::default().add_modifier(Modifier::ITALIC));
+            }
+            Tag::CodeBlock(kind) => {
+                self.flush();
+                self.code = Some(CodeBlock { language: language(&kind), code: String::new() });
+            }
+            Tag::List(start) => {
+                // The text of the item containing the list goes before it
+                self.flush();
+                self.lists.push(start);
+            }
+            Tag::Item => {
+                self.flush();
+                let marker = match self.lists.last_mut() {
+                    Some(Some(number)) => {
+                        *number += 1;
+                        format!("{}. ", *number - 1)
+                    }
+                    _ => String::from("• "),
+                };
+                self.containers.push(Container::Item { marker, marked: false });
+            }
+            Tag::FootnoteDefinition(name) => {
+                self.flush();
+                self.push_text(&format!("[^{}]: ", name), DECORATION_STYLE);
+            }
+            Tag::Table(alignments) => {
+                self.flush();
+                self.table = Some(Table { alignments, ..Table::default() });
+            }
+            Tag::TableHead | Tag::TableRow => if let Some(table) = self.table.as_mut() {
+                table.rows.push(Vec::new());
+            },
+            Tag::TableCell => if let Some(row) = self.table.as_mut().and_then(|table| table.rows.last_mut()) {
+                row.push(String::new());
+            },
+            Tag::Emphasis => self.push_style(Style::default().add_modifier(Modifier::ITALIC)),
+            Tag::Strong => self.push_style(Style::default().add_modifier(Modifier::BOLD)),
+            Tag::Strikethrough => self.push_style(Style::default().add_modifier(Modifier::CROSSED_OUT)),
+            Tag::Link(..) | Tag::Image(..) => {
+                self.push_style(Style::default().fg(Color::Blue).add_modifier(Modifier::UNDERLINED));
+            }
+        }
+    }
+
+    fn end(&mut self, tag: Tag) {
+        match tag {
+            Tag::Paragraph => {
+                self.flush();
+                self.blank_line();
+            }
+            Tag::Heading(..) => {
+                self.flush();
+                self.pop_style();
+                self.blank_line();
+            }
+            Tag::BlockQuote => {
+                self.flush();
+                self.containers.pop();
+                self.pop_style();
+                self.blank_line();
+            }
+            Tag::CodeBlock(_) => {
+                if let Some(block) = self.code.take() {
+                    self.push_code(&block);
+                }
+                self.blank_line();
+            }
+            Tag::List(_) => {
+                self.flush();
+                self.lists.pop();
+                if self.lists.is_empty() {
+                    self.blank_line();
+                }
+            }
+            Tag::Item => {
+                self.flush();
+                self.containers.pop();
+            }
+            Tag::FootnoteDefinition(_) => {
+                self.flush();
+                self.blank_line();
+            }
+            Tag::Table(_) => {
+                if let Some(table) = self.table.take() {
+                    self.push_table(&table);
+                }
+                self.blank_line();
+            }
+            Tag::TableHead => if let Some(table) = self.table.as_mut() {
+                table.head = table.rows.len();
+            },
+            Tag::TableRow | Tag::TableCell => {}
+            Tag::Emphasis | Tag::Strong | Tag::Strikethrough | Tag::Link(..) | Tag::Image(..) => self.pop_style(),
+        }
+    }
+
+    /// Adds text to the current block, or to the code block or table being read
+    fn push_text(&mut self, text: &str, style: Style) {
+        if let Some(block) = self.code.as_mut() {
+            block.code.push_str(text);
+        } else if let Some(cell) = self.table.as_mut()
+            .and_then(|table| table.rows.last_mut())
+            .and_then(|row| row.last_mut()) {
+            cell.push_str(text);
+        } else {
+            self.spans.push(Span::styled(text.to_string(), style));
+        }
+    }
+
+    /// Returns the indentation of the lines of the current block. The marker of a list item is
+    /// only shown on its first line.
+    fn prefix(&mut self) -> Vec<Span<'static>> {
+        let innermost = self.containers.len().saturating_sub(1);
+        self.containers.iter_mut()
+            .enumerate()
+            .map(|(index, container)| match container {
+                Container::Quote => Span::styled("│ ", DECORATION_STYLE),
+                Container::Item { marker, marked } if !*marked && index == innermost => {
+                    *marked = true;
+                    Span::styled(marker.clone(), DECORATION_STYLE)
+                }
+                Container::Item { marker, .. } => Span::raw(" ".repeat(marker.chars().count())),
+            })
+            .collect()
+    }
+
+    fn prefix_width(&self) -> usize {
+        self.containers.iter()
+            .map(|container| match container {
+                Container::Quote => 2,
+                Container::Item { marker, .. } => marker.chars().count(),
+            })
+            .sum()
+    }
+
+    /// Wraps the text of the current block into lines
+    fn flush(&mut self) {
+        if self.spans.is_empty() {
+            return;
+        }
+
+        let spans = std::mem::take(&mut self.spans);
+        let width = self.width.saturating_sub(self.prefix_width()).max(1);
+        for line in wrap_spans(&spans, width) {
+            let mut prefixed = self.prefix();
+            prefixed.extend(line);
+            self.lines.push(Line::from(prefixed));
+        }
+    }
+
+    /// Separates blocks with an empty line, without repeating it
+    fn blank_line(&mut self) {
+        if self.lines.last().map(|line| line.width() > 0).unwrap_or(false) {
+            self.lines.push(Line::default());
+        }
+    }
+
+    /// Adds the highlighted code after a header with its language and number, which is used to
+    /// copy it.
+    fn push_code(&mut self, block: &CodeBlock) {
+        self.code_blocks += 1;
+        let mut header = self.prefix();
+        header.push(Span::styled(format!("```{} [{}]", block.language, self.code_blocks), DECORATION_STYLE));
+        self.lines.push(Line::from(header));
+
+        for line in highlight(&block.code, &block.language) {
+            let mut prefixed = self.prefix();
+            prefixed.push(Span::raw("  "));
+            prefixed.extend(line.spans);
+            self.lines.push(Line::from(prefixed));
+        }
+    }
+
+    fn push_table(&mut self, table: &Table) {
+        let columns = table.rows.iter().map(|row| row.len()).max().unwrap_or(0);
+        let widths: Vec<usize> = (0..columns)
+            .map(|column| table.rows.iter()
+                .filter_map(|row| row.get(column))
+                .map(|cell| cell.trim().chars().count())
+                .max()
+                .unwrap_or(0))
+            .collect();
+
+        for (index, row) in table.rows.iter().enumerate() {
+            let style = if index < table.head { Style::default().add_modifier(Modifier::BOLD) } else { Style::default() };
+
+            let mut spans = self.prefix();
+            for (column, width) in widths.iter().enumerate() {
+                if column > 0 {
+                    spans.push(Span::styled(" │ ", DECORATION_STYLE));
+                }
+                let cell = row.get(column).map(|cell| cell.trim()).unwrap_or_default();
+                let alignment = table.alignments.get(column).copied().unwrap_or(Alignment::None);
+                spans.push(Span::styled(align(cell, *width, alignment), style));
+            }
+            self.lines.push(Line::from(spans));
+
+            // The head is separated from the body
+            if index + 1 == table.head {
+                let mut separator = self.prefix();
+                let columns: Vec<String> = widths.iter().map(|width| "─".repeat(*width)).collect();
+                separator.push(Span::styled(columns.join("─┼─"), DECORATION_STYLE));
+                self.lines.push(Line::from(separator));
+            }
+        }
+    }
+
+    fn finish(mut self) -> Vec<Line<'static>> {
+        self.flush();
+        if let Some(block) = self.code.take() {
+            // The fence isn't closed yet, e.g. while the response is received
+            self.push_code(&block);
+        }
+        if let Some(table) = self.table.take() {
+            self.push_table(&table);
+        }
+
+        while self.lines.last().map(|line| line.width() == 0).unwrap_or(false) {
+            self.lines.pop();
+        }
+        self.lines
+    }
+}
+
+/// Pads the text to the width of its column
+fn align(text: &str, width: usize, alignment: Alignment) -> String {
+    let padding = width.saturating_sub(text.chars().count());
+    match alignment {
+        Alignment::Right => format!("{}{}", " ".repeat(padding), text),
+        Alignment::Center => format!("{}{}{}", " ".repeat(padding / 2), text, " ".repeat(padding - padding / 2)),
+        Alignment::None | Alignment::Left => format!("{}{}", text, " ".repeat(padding)),
+    }
+}
+
+/// Splits styled text into lines no longer than the width, breaking at spaces when possible
+fn wrap_spans(spans: &[Span<'static>], width: usize) -> Vec<Vec<Span<'static>>> {
+    let chars: Vec<(char, Style)> = spans.iter()
+        .flat_map(|span| span.content.chars().map(|c| (c, span.style)))
+        .collect();
+
+    let mut lines = Vec::new();
+    for source_line in chars.split(|(c, _)| *c == '\n') {
+        let mut line: Vec<(char, Style)> = Vec::new();
+
+        for word in source_line.split_inclusive(|(c, _)| *c == ' ') {
+            // The space ending the word may be left out of the line
+            let word_width = word.len() - word.last().map(|(c, _)| *c == ' ').unwrap_or(false) as usize;
+            if line.len() + word_width.min(width) > width && !line.is_empty() {
+                lines.push(join_chars(&line));
+                line.clear();
+            }
+
+            // Words longer than the width are broken
+            for &(c, style) in word {
+                if line.len() == width {
+                    lines.push(join_chars(&line));
+                    line.clear();
+                    if c == ' ' {
+                        continue;
+                    }
+                }
+                line.push((c, style));
+            }
+        }
+
+        lines.push(join_chars(&line));
+    }
+
+    lines
+}
+
+/// Joins styled characters into spans, without trailing spaces
+fn join_chars(chars: &[(char, Style)]) -> Vec<Span<'static>> {
+    let end = chars.iter().rposition(|(c, _)| *c != ' ').map(|end| end + 1).unwrap_or(0);
+
+    let mut spans: Vec<Span<'static>> = Vec::new();
+    for &(c, style) in &chars[..end] {
+        match spans.last_mut() {
+            Some(span) if span.style == style => span.content.to_mut().push(c),
+            _ => spans.push(Span::styled(c.to_string(), style)),
+        }
+    }
+    spans
+}
+
+fn syntax_set() -> &'static SyntaxSet {
+    static SYNTAXES: OnceLock<SyntaxSet> = OnceLock::new();
+    SYNTAXES.get_or_init(SyntaxSet::load_defaults_newlines)
+}
+
+fn code_theme() -> &'static Theme {
+    static THEME: OnceLock<Theme> = OnceLock::new();
+    THEME.get_or_init(|| {
+        ThemeSet::load_defaults().themes.remove(CODE_THEME).unwrap_or_default()
+    })
+}
+
+/// Highlights the code according to its language, e.g. `rust` or `py`. Code of unknown
+/// languages is shown with a single style.
+pub fn highlight(code: &str, language: &str) -> Vec<Line<'static>> {
+    let syntaxes = syntax_set();
+    let Some(syntax) = syntaxes.find_syntax_by_token(language).filter(|_| !language.is_empty()) else {
+        return code.lines()
+            .map(|line| Line::from(Span::styled(line.to_string(), CODE_STYLE)))
+            .collect();
+    };
+
+    let mut highlighter = HighlightLines::new(syntax, code_theme());
+    LinesWithEndings::from(code)
+        .map(|line| {
+            let Ok(regions) = highlighter.highlight_line(line, syntaxes) else {
+                return Line::from(Span::styled(line.trim_end_matches('\n').to_string(), CODE_STYLE));
+            };
+
+            let spans: Vec<Span<'static>> = regions.into_iter()
+                .map(|(style, text)| (style, text.trim_end_matches('\n')))
+                .filter(|(_, text)| !text.is_empty())
+                .map(|(style, text)| Span::styled(text.to_string(), convert_style(style)))
+                .collect();
+            Line::from(spans)
+        })
+        .collect()
+}
+
+/// Converts a style of the highlighter, ignoring the background of the theme
+fn convert_style(style: syntect::highlighting::Style) -> Style {
+    let mut converted = Style::default().fg(Color::Rgb(style.foreground.r, style.foreground.g, style.foreground.b));
+    if style.font_style.contains(FontStyle::BOLD) {
+        converted = converted.add_modifier(Modifier::BOLD);
+    }
+    if style.font_style.contains(FontStyle::ITALIC) {
+        converted = converted.add_modifier(Modifier::ITALIC);
+    }
+    if style.font_style.contains(FontStyle::UNDERLINE) {
+        converted = converted.add_modifier(Modifier::UNDERLINED);
+    }
+
+    converted
+}
@@ -0,0 +1,118 @@
+use super::*;
+
+/// Returns the text of the lines, without their styles
+fn plain(lines: &[Line]) -> Vec<String> {
+    lines.iter()
+        .map(|line| line.spans.iter().map(|span| span.content.as_ref()).collect())
+        .collect()
+}
+
+/// Returns the style of the first span containing the text
+fn style_of(lines: &[Line], text: &str) -> Option<Style> {
+    lines.iter()
+        .flat_map(|line| line.spans.iter())
+        .find(|span| span.content.contains(text))
+        .map(|span| span.style)
+}
+
+#[test]
+fn render_blocks() {
+    let text = "# Title\n\nSome *emphasis* and **strong** text with `code`.\n\n\
+                > Quoted words\n\n\
+                - first\n- second\n  1. nested\n  2. again\n\n---";
+    let lines = render(text, 40);
+
+    assert_eq!(plain(&lines), vec![
+        "Title",
+        "",
+        "Some emphasis and strong text with code.",
+        "",
+        "│ Quoted words",
+        "",
+        "• first",
+        "• second",
+        "  1. nested",
+        "  2. again",
+        "",
+        "─".repeat(40).as_str(),
+    ]);
+
+    assert!(style_of(&lines, "Title").expect("title").add_modifier.contains(Modifier::BOLD));
+    assert!(style_of(&lines, "emphasis").expect("emphasis").add_modifier.contains(Modifier::ITALIC));
+    assert!(style_of(&lines, "strong").expect("strong").add_modifier.contains(Modifier::BOLD));
+    assert_eq!(style_of(&lines, "code").expect("code").fg, Some(Color::Yellow));
+    assert!(style_of(&lines, "Quoted").expect("quote").add_modifier.contains(Modifier::ITALIC));
+}
+
+#[test]
+fn wrap_within_containers() {
+    let lines = render("- one two three four\n\n> five six seven", 12);
+    assert_eq!(plain(&lines), vec![
+        "• one two",
+        "  three four",
+        "",
+        "│ five six",
+        "│ seven",
+    ]);
+
+    // Styles are kept across the lines
+    let lines = render("**bold words here**", 10);
+    assert_eq!(plain(&lines), vec!["bold words", "here"]);
+    assert!(style_of(&lines, "here").expect("here").add_modifier.contains(Modifier::BOLD));
+}
+
+#[test]
+fn render_tables() {
+    let text = "| Name | Size |\n|:-----|-----:|\n| a | 1 |\n| long name | 100 |";
+    let lines = render(text, 40);
+
+    assert_eq!(plain(&lines), vec![
+        "Name      │ Size",
+        "──────────┼─────",
+        "a         │    1",
+        "long name │  100",
+    ]);
+    assert!(style_of(&lines, "Name").expect("head").add_modifier.contains(Modifier::BOLD));
+}
+
+#[test]
+fn highlight_code() {
+    let text = "Code:\n\n```rust\nfn main() {\n    let x = 1;\n}\n```\n\n```\nplain text\n```";
+    let lines = render(text, 10);
+
+    // Code isn't wrapped, and the language is shown before it
+    assert_eq!(plain(&lines), vec![
+        "Code:",
+        "",
+        "```rust [1]",
+        "  fn main() {",
+        "      let x = 1;",
+        "  }",
+        "",
+        "``` [2]",
+        "  plain text",
+    ]);
+
+    // Known languages get several colors, unknown ones a single style
+    let mut colors: Vec<Color> = lines[3].spans.iter().filter_map(|span| span.style.fg).collect();
+    colors.dedup();
+    assert!(colors.len() > 1);
+    assert_eq!(style_of(&lines, "plain text"), Some(CODE_STYLE));
+    assert_eq!(highlight("a\nb", "no-such-language").len(), 2);
+
+    // Blocks whose fence isn't closed yet are still shown
+    assert_eq!(plain(&render("```py\nprint(1)", 20)), vec!["```py [1]", "  print(1)"]);
+}
+
+#[test]
+fn list_code_blocks() {
+    let text = "Intro\n\n```rust\nfn main() {}\n```\n\n    indented\n\n```sh\nls -l";
+    let blocks = code_blocks(text);
+
+    assert_eq!(blocks.len(), 3);
+    assert_eq!((blocks[0].language(), blocks[0].code()), ("rust", "fn main() {}\n"));
+    assert_eq!((blocks[1].language(), blocks[1].code()), ("", "indented\n"));
+    assert_eq!((blocks[2].language(), blocks[2].code()), ("sh", "ls -l"));
+    assert!(code_blocks("No code").is_empty());
+}
+
@@ -0,0 +1,274 @@
+use crossterm::event::{KeyCode, KeyEvent};
+use ratatui::backend::Backend;
+use ratatui::layout::Rect;
+use ratatui::style::{Modifier, Style};
+use ratatui::text::{Line, Span};
+use ratatui::widgets::{Block, Borders, Clear, Paragraph};
+use ratatui::Frame;
+
+use crate::conversations::{CompletionModel, CompletionParameters, CompletionParametersBuilder};
+use crate::tui::theme::Theme;
+
+/// Module with tests related to the parameters dialog
+#[cfg(test)]
+mod tests;
+
+/// Width of the labels of the fields
+const LABEL_WIDTH: usize = 14;
+
+/// Parameter edited in a field of the dialog
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Field {
+    Model,
+    Temperature,
+    MaxTokens,
+    N,
+}
+
+impl Field {
+    /// Fields in the order they are shown
+    pub const ALL: [Field; 4] = [Field::Model, Field::Temperature, Field::MaxTokens, Field::N];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            Field::Model => "Model",
+            Field::Temperature => "Temperature",
+            Field::MaxTokens => "Max tokens",
+            Field::N => "Completions",
+        }
+    }
+
+    /// Returns the value of the field within the parameters, as it's typed
+    fn value(&self, parameters: &CompletionParameters) -> String {
+        match self {
+            Field::Model => parameters.model().to_string(),
+            Field::Temperature => parameters.temperature().to_string(),
+            Field::MaxTokens => parameters.max_tokens().to_string(),
+            Field::N => parameters.n().to_string(),
+        }
+    }
+
+    /// Sets the typed value in the builder, or returns why it's not valid, either because it can't
+    /// be parsed or because the parameters don't allow it
+    fn apply(&self, value: &str, builder: &mut CompletionParametersBuilder) -> Result<(), String> {
+        let value = value.trim();
+        match self {
+            Field::Model => value.parse::<CompletionModel>()
+                .map(|model| { builder.model(model); })
+                .map_err(|_| String::from("Unknown model, press Left or Right to choose one")),
+            Field::Temperature => {
+                let temperature = value.parse().map_err(|_| String::from("Must be a number, e.g. 0.7"))?;
+                CompletionParametersBuilder::validate_temperature(temperature)?;
+                builder.temperature(temperature);
+                Ok(())
+            }
+            Field::MaxTokens => value.parse()
+                .map(|max_tokens| { builder.max_tokens(max_tokens); })
+                .map_err(|_| format!("Must be a whole number up to {}", u16::MAX)),
+            Field::N => {
+                let n = value.parse().map_err(|_| format!("Must be a whole number up to {}", u8::MAX))?;
+                CompletionParametersBuilder::validate_n(n)?;
+                builder.n(n);
+                Ok(())
+            }
+        }
+    }
+}
+
+/// Parameters changed by the dialog
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ParametersTarget {
+    /// Default parameters of the conversation, stored with it
+    Default,
+
+    /// Parameters of the next completion only
+    Next,
+}
+
+impl ParametersTarget {
+    pub fn title(&self) -> &'static str {
+        match self {
+            ParametersTarget::Default => "conversation defaults",
+            ParametersTarget::Next => "next completion only",
+        }
+    }
+}
+
+/// What the dialog asks for once it's closed
+#[derive(Debug, Clone, PartialEq)]
+pub enum ParametersAction {
+    Apply(ParametersTarget, CompletionParameters),
+    Cancel,
+}
+
+/// Dialog for viewing and changing the parameters of the completions of a conversation, either
+/// its default ones or the ones of the next completion only. The values are validated when
+/// applied, showing what's wrong next to them.
+#[derive(Debug)]
+pub struct ParametersDialog {
+    /// Typed value of each field, in the order of [Field::ALL]
+    values: Vec<String>,
+    errors: Vec<Option<String>>,
+
+    /// Error of the parameters as a whole, when they can't be built from valid fields
+    error: Option<String>,
+
+    /// Row with the cursor: one of the fields, or the target after them
+    selected: usize,
+    target: ParametersTarget,
+}
+
+impl ParametersDialog {
+    /// Creates the dialog showing the given parameters
+    ///
+    /// # Arguments
+    ///
+    /// * `parameters`: Parameters shown at first
+    /// * `target`: Parameters changed, unless the user chooses the others
+    ///
+    /// returns: ParametersDialog
+    pub fn build(parameters: &CompletionParameters, target: ParametersTarget) -> Self {
+        ParametersDialog {
+            values: Field::ALL.iter().map(|field| field.value(parameters)).collect(),
+            errors: vec![None; Field::ALL.len()],
+            error: None,
+            selected: 0,
+            target,
+        }
+    }
+
+    pub fn target(&self) -> ParametersTarget { self.target }
+    pub fn error(&self) -> Option<&str> { self.error.as_deref() }
+
+    /// Returns the field with the cursor, None if it's on the target
+    pub fn selected_field(&self) -> Option<Field> {
+        Field::ALL.get(self.selected).copied()
+    }
+
+    /// Returns the typed value of the field
+    pub fn value(&self, field: Field) -> &str {
+        &self.values[field as usize]
+    }
+
+    /// Returns why the value of the field is not valid, if it was applied
+    pub fn field_error(&self, field: Field) -> Option<&str> {
+        self.errors[field as usize].as_deref()
+    }
+
+    /// Handles the key, returning what to do once the dialog is closed
+    pub fn handle_key(&mut self, key: KeyEvent) -> Option<ParametersAction> {
+        let rows = Field::ALL.len() + 1;
+        match (key.code, self.selected_field()) {
+            (KeyCode::Esc, _) => return Some(ParametersAction::Cancel),
+            (KeyCode::Enter, _) => {
+                return self.parameters().map(|parameters| ParametersAction::Apply(self.target, parameters));
+            }
+            (KeyCode::Down | KeyCode::Tab, _) => self.selected = (self.selected + 1) % rows,
+            (KeyCode::Up | KeyCode::BackTab, _) => self.selected = (self.selected + rows - 1) % rows,
+            (KeyCode::Left, Some(Field::Model)) => self.switch_model(-1),
+            (KeyCode::Right, Some(Field::Model)) => self.switch_model(1),
+            (KeyCode::Left | KeyCode::Right | KeyCode::Char(' '), None) => {
+                self.target = match self.target {
+                    ParametersTarget::Default => ParametersTarget::Next,
+                    ParametersTarget::Next => ParametersTarget::Default,
+                };
+            }
+            (KeyCode::Backspace, Some(field)) => {
+                self.values[field as usize].pop();
+                self.errors[field as usize] = None;
+            }
+            (KeyCode::Char(c), Some(field)) => {
+                self.values[field as usize].push(c);
+                self.errors[field as usize] = None;
+            }
+            _ => {}
+        }
+
+        None
+    }
+
+    /// Replaces the model by the previous or next known one
+    fn switch_model(&mut self, offset: isize) {
+        let models = CompletionModel::ALL;
+        let value = &mut self.values[Field::Model as usize];
+        let index = match models.iter().position(|model| model.to_string() == value.trim()) {
+            Some(index) => (index as isize + offset).rem_euclid(models.len() as isize) as usize,
+            None => 0,
+        };
+
+        *value = models[index].to_string();
+        self.errors[Field::Model as usize] = None;
+    }
+
+    /// Builds the parameters from the typed values, keeping the errors if they are not valid
+    pub fn parameters(&mut self) -> Option<CompletionParameters> {
+        let mut builder = CompletionParametersBuilder::default();
+        for field in Field::ALL {
+            self.errors[field as usize] = field.apply(&self.values[field as usize], &mut builder).err();
+        }
+
+        self.error = None;
+        if self.errors.iter().any(Option::is_some) {
+            return None;
+        }
+
+        match builder.build() {
+            Ok(parameters) => Some(parameters),
+            Err(e) => {
+                self.error = Some(e.to_string());
+                None
+            }
+        }
+    }
+
+    /// Renders the dialog centered over the given area of the frame
+    pub fn render<B: Backend>(&self, frame: &mut Frame<B>, area: Rect, theme: &Theme) {
+        let mut lines = Vec::new();
+        let mut cursor = None;
+        for field in Field::ALL {
+            let selected = self.selected_field() == Some(field);
+            let label = format!(" {:<width$}", field.label(), width = LABEL_WIDTH);
+            let value = self.value(field);
+            if selected {
+                cursor = Some((lines.len(), label.chars().count() + value.chars().count()));
+            }
+
+            let label_style = if selected { theme.highlight() } else { Style::default() };
+            lines.push(Line::from(vec![Span::styled(label, label_style), Span::raw(value.to_string())]));
+            if let Some(error) = self.field_error(field) {
+                lines.push(Line::from(Span::styled(format!(" {:<width$}{}", "", error, width = LABEL_WIDTH),
+                                                   theme.error())));
+            }
+        }
+
+        // The target, with the chosen one marked
+        let selected = self.selected_field().is_none();
+        let mut target = vec![Span::styled(format!(" {:<width$}", "Apply to", width = LABEL_WIDTH),
+                                           if selected { theme.highlight() } else { Style::default() })];
+        for option in [ParametersTarget::Default, ParametersTarget::Next] {
+            let style = if option == self.target { theme.accent().add_modifier(Modifier::BOLD) } else { theme.muted() };
+            let mark = if option == self.target { "(•)" } else { "( )" };
+            target.push(Span::styled(format!("{} {}  ", mark, option.title()), style));
+        }
+        lines.push(Line::from(target));
+
+        lines.push(Line::default());
+        if let Some(error) = &self.error {
+            lines.push(Line::from(Span::styled(format!(" {}", error), theme.error())));
+        }
+        lines.push(Line::from(Span::styled(" Enter to apply, Esc to cancel, Up and Down to move", theme.muted())));
+
+        let popup = crate::tui::centered(area, 70, lines.len() as u16 + 2);
+        let block = Block::default()
+            .title(" Parameters ")
+            .borders(Borders::ALL)
+            .border_style(theme.border(true));
+        frame.render_widget(Clear, popup);
+        frame.render_widget(Paragraph::new(lines).block(block), popup);
+
+        if let Some((row, column)) = cursor {
+            let x = (popup.x + 1 + column as u16).min(popup.right().saturating_sub(2));
+            frame.set_cursor(x, popup.y + 1 + row as u16);
+        }
+    }
+}
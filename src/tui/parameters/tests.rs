@@ -0,0 +1,130 @@
+use crossterm::event::KeyModifiers;
+use ratatui::backend::TestBackend;
+use ratatui::Terminal;
+
+use super::*;
+
+fn press(dialog: &mut ParametersDialog, code: KeyCode) -> Option<ParametersAction> {
+    dialog.handle_key(KeyEvent::new(code, KeyModifiers::NONE))
+}
+
+fn type_text(dialog: &mut ParametersDialog, text: &str) {
+    for c in text.chars() {
+        press(dialog, KeyCode::Char(c));
+    }
+}
+
+/// Replaces the value of the selected field
+fn replace(dialog: &mut ParametersDialog, text: &str) {
+    while !dialog.value(dialog.selected_field().expect("field")).is_empty() {
+        press(dialog, KeyCode::Backspace);
+    }
+    type_text(dialog, text);
+}
+
+fn build_dialog() -> (CompletionParameters, ParametersDialog) {
+    let parameters = CompletionParametersBuilder::default()
+        .temperature(0.5)
+        .max_tokens(100)
+        .build().expect("parameters");
+    let dialog = ParametersDialog::build(&parameters, ParametersTarget::Default);
+    (parameters, dialog)
+}
+
+#[test]
+fn edit_parameters() {
+    let (parameters, mut dialog) = build_dialog();
+    assert_eq!(dialog.value(Field::Model), "gpt-3.5-turbo");
+    assert_eq!(dialog.value(Field::Temperature), "0.5");
+    assert_eq!(dialog.value(Field::MaxTokens), "100");
+    assert_eq!(dialog.value(Field::N), "1");
+
+    // Unchanged values are applied as they were
+    assert_eq!(press(&mut dialog, KeyCode::Enter),
+               Some(ParametersAction::Apply(ParametersTarget::Default, parameters)));
+
+    // Models are chosen among the known ones
+    assert_eq!(dialog.selected_field(), Some(Field::Model));
+    press(&mut dialog, KeyCode::Left);
+    assert_eq!(dialog.value(Field::Model), "gpt-4-32k");
+    press(&mut dialog, KeyCode::Right);
+    press(&mut dialog, KeyCode::Right);
+    assert_eq!(dialog.value(Field::Model), "gpt-3.5-turbo-16k");
+
+    press(&mut dialog, KeyCode::Down);
+    replace(&mut dialog, "0.2");
+    press(&mut dialog, KeyCode::Tab);
+    press(&mut dialog, KeyCode::Tab);
+    replace(&mut dialog, "2");
+
+    // The target is switched from its row
+    press(&mut dialog, KeyCode::Down);
+    assert_eq!(dialog.selected_field(), None);
+    press(&mut dialog, KeyCode::Char(' '));
+    assert_eq!(dialog.target(), ParametersTarget::Next);
+
+    let Some(ParametersAction::Apply(target, changed)) = press(&mut dialog, KeyCode::Enter) else {
+        panic!("parameters not applied");
+    };
+    assert_eq!(target, ParametersTarget::Next);
+    assert_eq!(changed.model(), CompletionModel::GPT35_16K);
+    assert_eq!(changed.temperature(), 0.2);
+    assert_eq!(changed.max_tokens(), 100);
+    assert_eq!(changed.n(), 2);
+
+    assert_eq!(press(&mut dialog, KeyCode::Esc), Some(ParametersAction::Cancel));
+}
+
+#[test]
+fn invalid_parameters() {
+    let (_, mut dialog) = build_dialog();
+
+    // Values that can't be parsed are shown next to their field
+    type_text(&mut dialog, "x");
+    press(&mut dialog, KeyCode::Down);
+    replace(&mut dialog, "warm");
+    press(&mut dialog, KeyCode::Down);
+    replace(&mut dialog, "70000");
+    assert_eq!(press(&mut dialog, KeyCode::Enter), None);
+    assert!(dialog.field_error(Field::Model).is_some());
+    assert!(dialog.field_error(Field::Temperature).is_some());
+    assert!(dialog.field_error(Field::MaxTokens).is_some());
+    assert_eq!(dialog.field_error(Field::N), None);
+
+    // .. until they are edited
+    press(&mut dialog, KeyCode::Up);
+    press(&mut dialog, KeyCode::Up);
+    press(&mut dialog, KeyCode::Right);
+    assert_eq!(dialog.field_error(Field::Model), None);
+    press(&mut dialog, KeyCode::Down);
+    replace(&mut dialog, "3");
+    assert_eq!(dialog.field_error(Field::Temperature), None);
+    press(&mut dialog, KeyCode::Down);
+    replace(&mut dialog, "256");
+
+    press(&mut dialog, KeyCode::Down);
+    replace(&mut dialog, "0");
+
+    // Parsed values are validated as well, showing why next to their field
+    assert_eq!(press(&mut dialog, KeyCode::Enter), None);
+    assert_eq!(dialog.field_error(Field::Temperature), Some("Temperature must be <2.0"));
+    assert_eq!(dialog.field_error(Field::MaxTokens), None);
+    assert_eq!(dialog.field_error(Field::N), Some("At least one completion must be requested"));
+    assert_eq!(dialog.error(), None);
+
+    let mut terminal = Terminal::new(TestBackend::new(80, 20)).expect("terminal");
+    terminal.draw(|frame| dialog.render(frame, frame.size(), &Theme::default())).expect("draw");
+    let content: String = terminal.backend().buffer().content().iter()
+        .map(|cell| cell.symbol.as_str())
+        .collect();
+    assert!(content.contains("Parameters"));
+    assert!(content.contains("Temperature must be <2.0"));
+    assert!(content.contains("conversation defaults"));
+
+    // Temperatures that aren't numbers are not valid either
+    press(&mut dialog, KeyCode::Up);
+    press(&mut dialog, KeyCode::Up);
+    replace(&mut dialog, "NaN");
+    assert_eq!(press(&mut dialog, KeyCode::Enter), None);
+    assert_eq!(dialog.field_error(Field::Temperature), Some("Temperature must be a number"));
+}
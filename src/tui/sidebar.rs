@@ -0,0 +1,386 @@
+use std::path::{Path, PathBuf};
+
+use chrono::{DateTime, Local, Utc};
+use crossterm::event::{KeyCode, KeyEvent};
+use ratatui::backend::Backend;
+use ratatui::layout::{Constraint, Direction, Layout, Rect};
+use ratatui::style::{Modifier, Style};
+use ratatui::text::{Line, Span};
+use ratatui::widgets::{Block, Borders, List, ListItem, ListState, Paragraph};
+use ratatui::Frame;
+
+use crate::conversations::Conversation;
+use crate::tui::theme::Theme;
+use crate::Result;
+use crate::workspace::Workspace;
+
+/// Module with tests related to the conversation list
+#[cfg(test)]
+mod tests;
+
+/// Conversation listed in the sidebar
+#[derive(Debug, Clone, PartialEq)]
+pub struct ConversationEntry {
+    path: PathBuf,
+    name: String,
+
+    /// Moment of the latest message, if known
+    updated: Option<DateTime<Utc>>,
+    messages: usize,
+}
+
+impl ConversationEntry {
+    pub fn path(&self) -> &Path { &self.path }
+    pub fn name(&self) -> &str { &self.name }
+    pub fn updated(&self) -> Option<DateTime<Utc>> { self.updated }
+    pub fn messages(&self) -> usize { self.messages }
+}
+
+impl From<&Conversation> for ConversationEntry {
+    fn from(conversation: &Conversation) -> Self {
+        ConversationEntry {
+            path: conversation.path().to_path_buf(),
+            name: conversation.name().to_string(),
+            updated: conversation.iter().filter_map(|msg| msg.created()).max(),
+            messages: conversation.iter().count(),
+        }
+    }
+}
+
+/// Loads the entries of the conversations of the workspace, most recently updated first.
+/// Conversations that can't be loaded (e.g. encrypted without a secret) are listed by their
+/// file name.
+pub async fn load_entries(workspace: &Workspace) -> Result<Vec<ConversationEntry>> {
+    let mut entries = Vec::new();
+    for path in workspace.conversation_paths().await? {
+        let entry = match workspace.load_conversation(&path).await {
+            Ok(conversation) => ConversationEntry::from(&conversation),
+            Err(_) => ConversationEntry {
+                name: path.file_stem().map(|stem| stem.to_string_lossy().to_string()).unwrap_or_default(),
+                path,
+                updated: None,
+                messages: 0,
+            },
+        };
+        entries.push(entry);
+    }
+
+    sort_entries(&mut entries);
+    Ok(entries)
+}
+
+fn sort_entries(entries: &mut [ConversationEntry]) {
+    entries.sort_by(|a, b| b.updated.cmp(&a.updated).then_with(|| a.name.cmp(&b.name)));
+}
+
+/// Action requested from the sidebar, performed by the application
+#[derive(Debug, Clone, PartialEq)]
+pub enum SidebarAction {
+    Open(PathBuf),
+    Create(String),
+    Rename(PathBuf, String),
+    Delete(PathBuf),
+}
+
+/// What the keys of the sidebar do
+#[derive(Debug, Clone, PartialEq)]
+enum Mode {
+    Browse,
+
+    /// Typing the filter
+    Filter,
+
+    /// Typing the name of a new conversation
+    Create(String),
+
+    /// Typing the new name of a conversation
+    Rename(PathBuf, String),
+
+    /// Waiting for the confirmation of a deletion
+    Delete(PathBuf),
+}
+
+/// List of the conversations of the workspace, with a fuzzy filter
+#[derive(Debug)]
+pub struct Sidebar {
+    entries: Vec<ConversationEntry>,
+    filter: String,
+
+    /// Position of the cursor within the filtered entries
+    selected: usize,
+    mode: Mode,
+
+    /// Keys of the commands of the list, shown below it
+    hint: String,
+}
+
+impl Sidebar {
+    pub fn build(entries: Vec<ConversationEntry>) -> Self {
+        Sidebar {
+            entries,
+            filter: String::new(),
+            selected: 0,
+            mode: Mode::Browse,
+            hint: String::new(),
+        }
+    }
+
+    /// Sets the keys of the commands shown below the list
+    pub fn with_hint(mut self, hint: String) -> Self {
+        self.hint = hint;
+        self
+    }
+
+    pub fn set_hint(&mut self, hint: String) {
+        self.hint = hint;
+    }
+
+    pub fn filter(&self) -> &str { &self.filter }
+
+    /// Replaces the entries, keeping the cursor on the same conversation if it still exists
+    pub fn set_entries(&mut self, entries: Vec<ConversationEntry>) {
+        let selected = self.selected_entry().map(|entry| entry.path.clone());
+        self.entries = entries;
+        self.selected = 0;
+        if let Some(path) = selected {
+            self.select_path(&path);
+        }
+    }
+
+    /// Replaces the entry of the conversation, e.g. after new messages
+    pub fn update_entry(&mut self, entry: ConversationEntry) {
+        let selected = self.selected_entry().map(|entry| entry.path.clone());
+        self.entries.retain(|e| e.path != entry.path);
+        self.entries.push(entry);
+        sort_entries(&mut self.entries);
+
+        if let Some(path) = selected {
+            self.select_path(&path);
+        }
+    }
+
+    /// Moves the cursor to the conversation stored at the given path, if it's listed
+    pub fn select_path(&mut self, path: &Path) {
+        if let Some(index) = self.filtered().iter().position(|entry| entry.path == path) {
+            self.selected = index;
+        }
+    }
+
+    /// Returns the entries matching the filter, best matches first
+    pub fn filtered(&self) -> Vec<&ConversationEntry> {
+        if self.filter.is_empty() {
+            return self.entries.iter().collect();
+        }
+
+        let mut scored: Vec<(i64, &ConversationEntry)> = self.entries.iter()
+            .filter_map(|entry| fuzzy_score(&self.filter, &entry.name).map(|score| (score, entry)))
+            .collect();
+        scored.sort_by_key(|(score, _)| std::cmp::Reverse(*score));
+        scored.into_iter().map(|(_, entry)| entry).collect()
+    }
+
+    pub fn selected_entry(&self) -> Option<&ConversationEntry> {
+        self.filtered().get(self.selected).copied()
+    }
+
+    /// Checks if the sidebar is waiting for text, so every key is used for it
+    pub fn is_editing(&self) -> bool {
+        self.mode != Mode::Browse
+    }
+
+    /// Handles a key while text is typed or a deletion is confirmed, returning the action it
+    /// requests. The commands of the list are started by the application, with the keys of its
+    /// keymap.
+    pub fn handle_key(&mut self, key: KeyEvent) -> Option<SidebarAction> {
+        match &mut self.mode {
+            Mode::Browse => {}
+            Mode::Filter => match key.code {
+                KeyCode::Esc | KeyCode::Enter => self.mode = Mode::Browse,
+                KeyCode::Backspace => {
+                    self.filter.pop();
+                    self.selected = 0;
+                }
+                KeyCode::Char(c) => {
+                    self.filter.push(c);
+                    self.selected = 0;
+                }
+                _ => {}
+            },
+            Mode::Create(name) | Mode::Rename(_, name) => match key.code {
+                KeyCode::Esc => self.mode = Mode::Browse,
+                KeyCode::Backspace => {
+                    name.pop();
+                }
+                KeyCode::Char(c) => name.push(c),
+                KeyCode::Enter => {
+                    let name = name.trim().to_string();
+                    let action = match std::mem::replace(&mut self.mode, Mode::Browse) {
+                        Mode::Create(_) if !name.is_empty() => Some(SidebarAction::Create(name)),
+                        Mode::Rename(path, _) if !name.is_empty() => Some(SidebarAction::Rename(path, name)),
+                        _ => None,
+                    };
+                    return action;
+                }
+                _ => {}
+            },
+            Mode::Delete(path) => {
+                let path = path.clone();
+                self.mode = Mode::Browse;
+                if matches!(key.code, KeyCode::Char('y') | KeyCode::Char('Y')) {
+                    return Some(SidebarAction::Delete(path));
+                }
+            }
+        }
+
+        None
+    }
+
+    /// Moves the cursor by the given number of entries, up if negative. Returns no action, so it
+    /// can be used like [Sidebar::handle_key].
+    pub fn move_selection(&mut self, offset: isize) -> Option<SidebarAction> {
+        let last = self.filtered().len().saturating_sub(1) as isize;
+        self.selected = (self.selected as isize + offset).clamp(0, last) as usize;
+        None
+    }
+
+    /// Moves the cursor to the first or last entry
+    pub fn select_edge(&mut self, last: bool) -> Option<SidebarAction> {
+        self.selected = if last { self.filtered().len().saturating_sub(1) } else { 0 };
+        None
+    }
+
+    /// Requests opening the entry under the cursor
+    pub fn open(&self) -> Option<SidebarAction> {
+        self.selected_entry().map(|entry| SidebarAction::Open(entry.path.clone()))
+    }
+
+    /// Removes the filter, returns if there was any
+    pub fn clear_filter(&mut self) -> bool {
+        if self.filter.is_empty() {
+            return false;
+        }
+
+        self.filter.clear();
+        self.selected = 0;
+        true
+    }
+
+    /// Starts typing the filter
+    pub fn start_filter(&mut self) -> Option<SidebarAction> {
+        self.mode = Mode::Filter;
+        None
+    }
+
+    /// Starts typing the name of a new conversation
+    pub fn start_create(&mut self) -> Option<SidebarAction> {
+        self.mode = Mode::Create(String::new());
+        None
+    }
+
+    /// Starts typing the new name of the entry under the cursor, from its current one
+    pub fn start_rename(&mut self) -> Option<SidebarAction> {
+        if let Some(entry) = self.selected_entry() {
+            self.mode = Mode::Rename(entry.path.clone(), entry.name.clone());
+        }
+        None
+    }
+
+    /// Asks for the confirmation of the deletion of the entry under the cursor
+    pub fn start_delete(&mut self) -> Option<SidebarAction> {
+        if let Some(entry) = self.selected_entry() {
+            self.mode = Mode::Delete(entry.path.clone());
+        }
+        None
+    }
+
+    /// Renders the list in the given area of the frame
+    ///
+    /// # Arguments
+    ///
+    /// * `frame`: Frame to draw on
+    /// * `area`: Area of the sidebar
+    /// * `opened`: Path of the conversation shown in the chat, which is highlighted
+    /// * `focused`: Highlights the cursor when the sidebar receives the input
+    /// * `theme`: Colors of the list
+    pub fn render<B: Backend>(&self, frame: &mut Frame<B>, area: Rect, opened: Option<&Path>, focused: bool,
+                              theme: &Theme) {
+        let block = Block::default()
+            .title(" Conversations ")
+            .borders(Borders::ALL)
+            .border_style(theme.border(focused));
+        let inner = block.inner(area);
+        frame.render_widget(block, area);
+
+        let chunks = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([Constraint::Min(1), Constraint::Length(1)])
+            .split(inner);
+
+        let items: Vec<ListItem> = self.filtered().into_iter()
+            .map(|entry| {
+                let mut name_style = Style::default().add_modifier(Modifier::BOLD);
+                if Some(entry.path.as_path()) == opened {
+                    name_style = name_style.patch(theme.accent());
+                }
+
+                let updated = entry.updated
+                    .map(|updated| updated.with_timezone(&Local).format("%Y-%m-%d %H:%M").to_string())
+                    .unwrap_or_else(|| String::from("-"));
+                let details = format!("{} · {} messages", updated, entry.messages);
+
+                ListItem::new(vec![
+                    Line::from(Span::styled(entry.name.clone(), name_style)),
+                    Line::from(Span::styled(details, theme.muted())),
+                ])
+            })
+            .collect();
+
+        // Each entry takes two lines
+        let visible = (chunks[0].height as usize / 2).max(1);
+        let mut state = ListState::default()
+            .with_selected(focused.then_some(self.selected))
+            .with_offset(self.selected.saturating_sub(visible - 1));
+        let list = List::new(items)
+            .highlight_style(theme.highlight());
+        frame.render_stateful_widget(list, chunks[0], &mut state);
+
+        let prompt = match &self.mode {
+            Mode::Browse if self.filter.is_empty() => self.hint.clone(),
+            Mode::Browse => format!("filter: {}", self.filter),
+            Mode::Filter => format!("/{}", self.filter),
+            Mode::Create(name) => format!("New: {}", name),
+            Mode::Rename(_, name) => format!("Rename: {}", name),
+            Mode::Delete(_) => String::from("Delete conversation? (y/n)"),
+        };
+        let prompt_style = if self.is_editing() { Style::default() } else { theme.muted() };
+        frame.render_widget(Paragraph::new(prompt).style(prompt_style), chunks[1]);
+    }
+}
+
+/// Returns how well the pattern matches the text, if every character of the pattern appears in
+/// order within it (ignoring case). Consecutive characters and matches at the start of words
+/// score higher.
+fn fuzzy_score(pattern: &str, text: &str) -> Option<i64> {
+    let text: Vec<char> = text.to_lowercase().chars().collect();
+
+    let mut score = 0;
+    let mut position = 0;
+    let mut previous: Option<usize> = None;
+    for c in pattern.to_lowercase().chars().filter(|c| !c.is_whitespace()) {
+        let found = position + text[position..].iter().position(|&t| t == c)?;
+
+        score += 1;
+        if previous.map(|p| p + 1 == found).unwrap_or(false) {
+            score += 5;
+        }
+        if found == 0 || !text[found - 1].is_alphanumeric() {
+            score += 3;
+        }
+
+        previous = Some(found);
+        position = found + 1;
+    }
+
+    // Shorter texts are better matches
+    Some(score * 100 - text.len() as i64)
+}
@@ -0,0 +1,112 @@
+use crossterm::event::KeyModifiers;
+
+use crate::conversations::CompletionParametersBuilder;
+use crate::test_util::TempDirectoryHandler;
+
+use super::*;
+
+fn key(code: KeyCode) -> KeyEvent {
+    KeyEvent::new(code, KeyModifiers::NONE)
+}
+
+fn type_text(sidebar: &mut Sidebar, text: &str) -> Option<SidebarAction> {
+    text.chars()
+        .map(|c| sidebar.handle_key(key(KeyCode::Char(c))))
+        .last()
+        .flatten()
+}
+
+fn entry(name: &str, messages: usize) -> ConversationEntry {
+    ConversationEntry {
+        path: PathBuf::from(format!("{}.yaml", name)),
+        name: name.to_string(),
+        updated: None,
+        messages,
+    }
+}
+
+#[test]
+fn fuzzy_matches() {
+    assert!(fuzzy_score("rst", "Rust lifetimes").is_some());
+    assert!(fuzzy_score("tsr", "Rust lifetimes").is_none());
+    assert!(fuzzy_score("", "anything").is_some());
+
+    // Consecutive characters and starts of words are better matches
+    assert!(fuzzy_score("rust", "Rust tips") > fuzzy_score("rust", "rare unsorted tests"));
+    assert!(fuzzy_score("rl", "Rust lifetimes") > fuzzy_score("rl", "ruler"));
+}
+
+#[test]
+fn sidebar_actions() {
+    let mut sidebar = Sidebar::build(vec![entry("Rust lifetimes", 3), entry("Cooking", 5), entry("Rust macros", 2)]);
+
+    // Filtering keeps the matching entries
+    sidebar.start_filter();
+    assert_eq!(type_text(&mut sidebar, "rust"), None);
+    sidebar.handle_key(key(KeyCode::Enter));
+    assert!(!sidebar.is_editing());
+    assert_eq!(sidebar.filtered().len(), 2);
+
+    sidebar.move_selection(5);
+    let selected = sidebar.selected_entry().expect("selected entry").path().to_path_buf();
+    assert_eq!(sidebar.open(), Some(SidebarAction::Open(selected.clone())));
+    sidebar.select_edge(false);
+    assert_ne!(sidebar.selected_entry().map(|entry| entry.path()), Some(selected.as_path()));
+    sidebar.select_edge(true);
+
+    // Renaming starts with the current name
+    sidebar.start_rename();
+    for _ in 0..selected.to_string_lossy().len() {
+        sidebar.handle_key(key(KeyCode::Backspace));
+    }
+    type_text(&mut sidebar, "Macros");
+    assert_eq!(sidebar.handle_key(key(KeyCode::Enter)),
+               Some(SidebarAction::Rename(selected.clone(), String::from("Macros"))));
+
+    // Deletions must be confirmed
+    sidebar.start_delete();
+    assert_eq!(sidebar.handle_key(key(KeyCode::Char('n'))), None);
+    sidebar.start_delete();
+    assert_eq!(sidebar.handle_key(key(KeyCode::Char('y'))), Some(SidebarAction::Delete(selected)));
+
+    // Keys of the list don't start commands by themselves, empty names are ignored
+    assert!(sidebar.clear_filter());
+    assert_eq!(sidebar.filtered().len(), 3);
+    assert!(!sidebar.clear_filter());
+    sidebar.handle_key(key(KeyCode::Char('n')));
+    assert!(!sidebar.is_editing());
+    sidebar.start_create();
+    assert_eq!(sidebar.handle_key(key(KeyCode::Enter)), None);
+    sidebar.start_create();
+    type_text(&mut sidebar, "New chat");
+    assert_eq!(sidebar.handle_key(key(KeyCode::Enter)), Some(SidebarAction::Create(String::from("New chat"))));
+}
+
+#[tokio::test]
+async fn workspace_entries() {
+    let temp_dir = TempDirectoryHandler::build().expect("temp dir");
+    let workspace = Workspace::open(temp_dir.path()).await.expect("workspace");
+
+    let parameters = CompletionParametersBuilder::default().build().expect("parameters");
+    let mut conversation = Conversation::build(parameters, workspace.path().join("chat.yaml"), "System")
+        .expect("build conversation");
+    conversation.set_name(String::from("Chat"));
+    let root = conversation.get_root_message().id();
+    conversation.add_queries(root, vec![String::from("Query")]).expect("add query");
+    conversation.save().await.expect("save conversation");
+
+    // Files that aren't conversations are listed by their name
+    fs_write(workspace.path().join("broken.yaml"), "not: [a conversation").await;
+
+    let entries = load_entries(&workspace).await.expect("load entries");
+    assert_eq!(entries.len(), 2);
+    assert_eq!(entries[0].name(), "Chat");
+    assert_eq!(entries[0].messages(), 2);
+    assert!(entries[0].updated().is_some());
+    assert_eq!(entries[1].name(), "broken");
+    assert_eq!(entries[1].messages(), 0);
+}
+
+async fn fs_write(path: PathBuf, content: &str) {
+    tokio::fs::write(path, content).await.expect("write file");
+}
@@ -0,0 +1,87 @@
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+use tokio::fs;
+
+use crate::Result;
+use crate::conversations::Conversation;
+use crate::workspace::Workspace;
+
+/// Module with tests related to the state of the TUI
+#[cfg(test)]
+mod tests;
+
+/// File within the workspace where the state of the TUI is kept between launches. It isn't a
+/// YAML file, so it isn't listed as a conversation.
+pub const STATE_FILE: &str = ".rgpt-tui.json";
+
+/// State of the TUI kept between launches, one for each workspace
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+#[serde(default)]
+pub struct SessionState {
+    /// Conversation opened last, relative to the workspace
+    last_opened: Option<PathBuf>,
+
+    /// Queries written but not sent, by the path of their conversation relative to the workspace
+    drafts: BTreeMap<PathBuf, String>,
+}
+
+impl SessionState {
+    /// Loads the state of the workspace, or returns an empty one if it doesn't have any
+    pub async fn load(workspace: &Workspace) -> Result<Self> {
+        let path = workspace.path().join(STATE_FILE);
+        if !fs::try_exists(&path).await? {
+            return Ok(Self::default());
+        }
+
+        Ok(serde_json::from_slice(&fs::read(path).await?)?)
+    }
+
+    /// Stores the state within the workspace
+    pub async fn save(&self, workspace: &Workspace) -> Result<()> {
+        let data = serde_json::to_vec_pretty(self)?;
+        fs::write(workspace.path().join(STATE_FILE), data).await?;
+
+        Ok(())
+    }
+
+    /// Returns the path of the conversation opened last, within the workspace
+    pub fn last_opened(&self, workspace: &Workspace) -> Option<PathBuf> {
+        self.last_opened.as_ref()
+            .map(|path| workspace.path().join(path))
+    }
+
+    pub fn set_last_opened(&mut self, workspace: &Workspace, path: Option<&Path>) {
+        self.last_opened = path.map(|path| relative_path(workspace, path));
+    }
+
+    /// Returns the query written but not sent in the conversation at the given path, if any
+    pub fn draft(&self, workspace: &Workspace, path: &Path) -> Option<&str> {
+        self.drafts.get(&relative_path(workspace, path)).map(String::as_str)
+    }
+
+    /// Keeps the query written in the conversation at the given path, or forgets it if empty
+    pub fn set_draft(&mut self, workspace: &Workspace, path: &Path, draft: &str) {
+        let path = relative_path(workspace, path);
+        if draft.trim().is_empty() {
+            self.drafts.remove(&path);
+        } else {
+            self.drafts.insert(path, draft.to_string());
+        }
+    }
+
+    /// Keeps the query written in the conversation, unless it's encrypted: the state is stored in
+    /// plain text, so the drafts of encrypted conversations are forgotten instead
+    pub fn set_conversation_draft(&mut self, workspace: &Workspace, conversation: &Conversation, draft: &str) {
+        let draft = if conversation.is_encrypted() { "" } else { draft };
+        self.set_draft(workspace, conversation.path(), draft);
+    }
+}
+
+/// Returns the path relative to the workspace, if it's within it
+fn relative_path(workspace: &Workspace, path: &Path) -> PathBuf {
+    path.strip_prefix(workspace.path())
+        .map(Path::to_path_buf)
+        .unwrap_or_else(|_| path.to_path_buf())
+}
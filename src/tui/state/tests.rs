@@ -0,0 +1,70 @@
+use crate::conversations::CompletionParametersBuilder;
+use crate::encryption::Secret;
+use crate::test_util::TempDirectoryHandler;
+
+use super::*;
+
+#[tokio::test]
+async fn session_state() {
+    let temp_dir = TempDirectoryHandler::build().expect("temp dir");
+    let workspace = Workspace::open(temp_dir.path()).await.expect("workspace");
+
+    // Workspaces without state start empty
+    let mut state = SessionState::load(&workspace).await.expect("load empty state");
+    assert_eq!(state.last_opened(&workspace), None);
+
+    // Paths are stored relative to the workspace
+    let path = workspace.path().join("chat.yaml");
+    state.set_last_opened(&workspace, Some(&path));
+    state.save(&workspace).await.expect("save state");
+
+    let loaded = SessionState::load(&workspace).await.expect("load state");
+    assert_eq!(loaded.last_opened(&workspace), Some(path));
+    assert_eq!(loaded.last_opened, Some(PathBuf::from("chat.yaml")));
+
+    // The state file isn't a conversation
+    assert!(workspace.conversation_paths().await.expect("list conversations").is_empty());
+}
+
+#[tokio::test]
+async fn drafts() {
+    let temp_dir = TempDirectoryHandler::build().expect("temp dir");
+    let workspace = Workspace::open(temp_dir.path()).await.expect("workspace");
+
+    let mut state = SessionState::default();
+    let path = workspace.path().join("chat.yaml");
+    state.set_draft(&workspace, &path, "Unsent\nquery");
+    state.save(&workspace).await.expect("save state");
+
+    let mut loaded = SessionState::load(&workspace).await.expect("load state");
+    assert_eq!(loaded.draft(&workspace, &path), Some("Unsent\nquery"));
+    assert_eq!(loaded.draft(&workspace, &workspace.path().join("other.yaml")), None);
+
+    // Empty drafts are forgotten
+    loaded.set_draft(&workspace, &path, "  ");
+    assert_eq!(loaded.draft(&workspace, &path), None);
+    assert!(loaded.drafts.is_empty());
+}
+
+#[tokio::test]
+async fn encrypted_drafts() {
+    let temp_dir = TempDirectoryHandler::build().expect("temp dir");
+    let workspace = Workspace::open(temp_dir.path()).await.expect("workspace");
+
+    let parameters = CompletionParametersBuilder::default().build().expect("parameters");
+    let mut conversation = Conversation::build(parameters, workspace.path().join("chat.yaml"), "System")
+        .expect("conversation");
+    let mut state = SessionState::default();
+    state.set_conversation_draft(&workspace, &conversation, "Plain query");
+    assert_eq!(state.draft(&workspace, conversation.path()), Some("Plain query"));
+
+    // Drafts of encrypted conversations are never stored, and older ones are forgotten
+    conversation.set_secret(Some(Secret::from_passphrase("secret").expect("secret")));
+    state.set_conversation_draft(&workspace, &conversation, "Secret query");
+    state.save(&workspace).await.expect("save state");
+
+    let loaded = SessionState::load(&workspace).await.expect("load state");
+    assert_eq!(loaded.draft(&workspace, conversation.path()), None);
+    let data = fs::read_to_string(workspace.path().join(STATE_FILE)).await.expect("read state");
+    assert!(!data.contains("Secret query"));
+}
@@ -0,0 +1,103 @@
+use async_openai::types::Role;
+use ratatui::style::{Color, Modifier, Style};
+use serde::{Deserialize, Serialize};
+
+/// Colors of the TUI, loaded from the configuration file. Colors are given by name (e.g.
+/// `LightBlue`), as `!Rgb [r, g, b]` or as `!Indexed n`.
+///
+/// # Examples
+///
+/// ```yaml
+/// user: LightBlue
+/// assistant: !Rgb [160, 224, 160]
+/// focused_border: Yellow
+/// highlight: DarkGray
+/// ```
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(default)]
+pub struct Theme {
+    system: Color,
+    user: Color,
+    assistant: Color,
+    function: Color,
+
+    /// Borders of the panels, and of the one receiving the input
+    border: Color,
+    focused_border: Color,
+
+    /// Background of the selected entries and messages, reversed colors if None
+    highlight: Option<Color>,
+
+    /// Details, e.g. dates and positions of siblings
+    muted: Color,
+
+    /// Status bar
+    status_foreground: Color,
+    status_background: Color,
+
+    /// Messages of invalid values, e.g. in the parameters dialog
+    error: Color,
+}
+
+impl Default for Theme {
+    fn default() -> Self {
+        Theme {
+            system: Color::Yellow,
+            user: Color::Cyan,
+            assistant: Color::Green,
+            function: Color::Magenta,
+            border: Color::Reset,
+            focused_border: Color::Cyan,
+            highlight: None,
+            muted: Color::DarkGray,
+            status_foreground: Color::White,
+            status_background: Color::DarkGray,
+            error: Color::Red,
+        }
+    }
+}
+
+impl Theme {
+    /// Returns the style of the messages of the role
+    pub fn role(&self, role: &Role) -> Style {
+        let color = match role {
+            Role::System => self.system,
+            Role::User => self.user,
+            Role::Assistant => self.assistant,
+            Role::Function => self.function,
+        };
+
+        Style::default().fg(color)
+    }
+
+    /// Returns the style of the borders of a panel
+    pub fn border(&self, focused: bool) -> Style {
+        Style::default().fg(if focused { self.focused_border } else { self.border })
+    }
+
+    /// Returns the style of the selected entries and messages
+    pub fn highlight(&self) -> Style {
+        match self.highlight {
+            Some(color) => Style::default().bg(color),
+            None => Style::default().add_modifier(Modifier::REVERSED),
+        }
+    }
+
+    pub fn muted(&self) -> Style {
+        Style::default().fg(self.muted)
+    }
+
+    /// Returns the style of what stands out, e.g. the conversation shown in the chat and the
+    /// keys in the help
+    pub fn accent(&self) -> Style {
+        Style::default().fg(self.focused_border)
+    }
+
+    pub fn status(&self) -> Style {
+        Style::default().bg(self.status_background).fg(self.status_foreground)
+    }
+
+    pub fn error(&self) -> Style {
+        Style::default().fg(self.error)
+    }
+}
@@ -0,0 +1,234 @@
+use std::collections::HashSet;
+
+use async_openai::types::Role;
+use ratatui::backend::Backend;
+use ratatui::layout::Rect;
+use ratatui::style::{Modifier, Style};
+use ratatui::text::{Line, Span};
+use ratatui::widgets::{Block, Borders, List, ListItem, ListState};
+use ratatui::Frame;
+use uuid::Uuid;
+
+use crate::conversations::Conversation;
+use crate::tui::theme::Theme;
+
+/// Module with tests related to the conversation tree
+#[cfg(test)]
+mod tests;
+
+/// Spaces of indentation for each level of the tree
+const INDENT: usize = 2;
+
+/// Visible node of the tree
+#[derive(Debug, Clone, PartialEq)]
+pub struct TreeRow {
+    id: Uuid,
+    parent_id: Option<Uuid>,
+    depth: usize,
+    role: Role,
+    has_children: bool,
+    collapsed: bool,
+
+    /// Content in a single line
+    preview: String,
+}
+
+impl TreeRow {
+    pub fn id(&self) -> Uuid { self.id }
+    pub fn parent_id(&self) -> Option<Uuid> { self.parent_id }
+    pub fn depth(&self) -> usize { self.depth }
+    pub fn role(&self) -> &Role { &self.role }
+    pub fn has_children(&self) -> bool { self.has_children }
+    pub fn is_collapsed(&self) -> bool { self.collapsed }
+    pub fn preview(&self) -> &str { &self.preview }
+}
+
+/// Panel with the whole tree of a conversation, where nodes can be collapsed and selected
+#[derive(Debug, Default)]
+pub struct TreePanel {
+    /// Nodes whose children are hidden
+    collapsed: HashSet<Uuid>,
+
+    /// Node under the cursor, the root if None
+    selected: Option<Uuid>,
+}
+
+impl TreePanel {
+    pub fn selected(&self) -> Option<Uuid> { self.selected }
+
+    /// Returns the visible nodes of the tree, in depth-first order
+    pub fn rows(&self, conversation: &Conversation) -> Vec<TreeRow> {
+        // The children of every node are found at once, the tree is rendered on every change
+        let children = conversation.get_children_map();
+
+        let mut rows = Vec::new();
+        let mut stack = vec![(conversation.get_root_message(), 0)];
+        while let Some((msg, depth)) = stack.pop() {
+            let msg_children = children.get(&msg.id()).map(Vec::as_slice).unwrap_or_default();
+            let collapsed = self.collapsed.contains(&msg.id());
+
+            rows.push(TreeRow {
+                id: msg.id(),
+                parent_id: msg.parent_id(),
+                depth,
+                role: msg.role().clone(),
+                has_children: !msg_children.is_empty(),
+                collapsed,
+                preview: msg.content().split_whitespace().collect::<Vec<_>>().join(" "),
+            });
+
+            // Descendants of collapsed nodes are hidden
+            if !collapsed {
+                stack.extend(msg_children.iter().rev().map(|&child| (child, depth + 1)));
+            }
+        }
+
+        rows
+    }
+
+    /// Moves the cursor to the given node
+    pub fn select(&mut self, message_id: Uuid) {
+        self.selected = Some(message_id);
+    }
+
+    /// Moves the cursor by the given number of visible rows, up if negative
+    pub fn move_selection(&mut self, conversation: &Conversation, offset: isize) {
+        let rows = self.rows(conversation);
+        if rows.is_empty() {
+            return;
+        }
+
+        let current = self.selected_index(&rows) as isize;
+        let next = (current + offset).clamp(0, rows.len() as isize - 1) as usize;
+        self.selected = Some(rows[next].id);
+    }
+
+    /// Moves the cursor to the first or last visible row
+    pub fn select_edge(&mut self, conversation: &Conversation, last: bool) {
+        let rows = self.rows(conversation);
+        let row = if last { rows.last() } else { rows.first() };
+        self.selected = row.map(|row| row.id);
+    }
+
+    /// Collapses the selected node, or moves to its parent if it is already collapsed or has no
+    /// children.
+    pub fn collapse(&mut self, conversation: &Conversation) {
+        let rows = self.rows(conversation);
+        let Some(row) = rows.get(self.selected_index(&rows)) else {
+            return;
+        };
+
+        if row.has_children && !row.collapsed {
+            self.collapsed.insert(row.id);
+        } else if let Some(parent) = row.parent_id {
+            self.selected = Some(parent);
+        }
+    }
+
+    /// Expands the selected node, or moves to its first child if it is already expanded
+    pub fn expand(&mut self, conversation: &Conversation) {
+        let rows = self.rows(conversation);
+        let index = self.selected_index(&rows);
+        let Some(row) = rows.get(index) else {
+            return;
+        };
+
+        if row.collapsed {
+            self.collapsed.remove(&row.id);
+        } else if row.has_children {
+            self.selected = rows.get(index + 1).map(|child| child.id);
+        }
+    }
+
+    /// Collapses or expands the selected node
+    pub fn toggle(&mut self, conversation: &Conversation) {
+        let rows = self.rows(conversation);
+        let Some(row) = rows.get(self.selected_index(&rows)) else {
+            return;
+        };
+
+        if !self.collapsed.remove(&row.id) && row.has_children {
+            self.collapsed.insert(row.id);
+        }
+    }
+
+    /// Returns the position of the cursor within the rows, the first row if the selected node
+    /// isn't visible.
+    fn selected_index(&self, rows: &[TreeRow]) -> usize {
+        self.selected
+            .and_then(|selected| rows.iter().position(|row| row.id == selected))
+            .unwrap_or(0)
+    }
+
+    /// Renders the tree in the given area of the frame
+    ///
+    /// # Arguments
+    ///
+    /// * `frame`: Frame to draw on
+    /// * `area`: Area of the panel
+    /// * `conversation`: Conversation whose tree is drawn
+    /// * `active_branch`: Messages shown in the chat, which are highlighted
+    /// * `focused`: Highlights the cursor when the panel receives the input
+    /// * `theme`: Colors of the panel
+    pub fn render<B: Backend>(&self, frame: &mut Frame<B>, area: Rect, conversation: &Conversation,
+                              active_branch: &HashSet<Uuid>, focused: bool, theme: &Theme) {
+        let block = Block::default()
+            .title(" Tree ")
+            .borders(Borders::ALL)
+            .border_style(theme.border(focused));
+        let width = block.inner(area).width as usize;
+        let height = block.inner(area).height as usize;
+
+        let rows = self.rows(conversation);
+        let items: Vec<ListItem> = rows.iter()
+            .map(|row| {
+                let marker = match (row.has_children, row.collapsed) {
+                    (true, true) => "▸ ",
+                    (true, false) => "▾ ",
+                    (false, _) => "  ",
+                };
+                let prefix = format!("{}{}{} ", " ".repeat(row.depth * INDENT), marker, role_icon(&row.role));
+                let preview = truncate(&row.preview, width.saturating_sub(prefix.chars().count()));
+
+                let style = if active_branch.contains(&row.id) {
+                    Style::default().add_modifier(Modifier::BOLD)
+                } else {
+                    theme.muted()
+                };
+
+                ListItem::new(Line::from(vec![Span::styled(prefix, theme.role(&row.role)), Span::styled(preview, style)]))
+            })
+            .collect();
+
+        // Keep the cursor within the visible rows
+        let selected = self.selected_index(&rows);
+        let mut state = ListState::default()
+            .with_selected(focused.then_some(selected))
+            .with_offset(selected.saturating_sub(height.saturating_sub(1)));
+
+        let list = List::new(items)
+            .block(block)
+            .highlight_style(theme.highlight());
+        frame.render_stateful_widget(list, area, &mut state);
+    }
+}
+
+fn role_icon(role: &Role) -> &'static str {
+    match role {
+        Role::System => "⚙",
+        Role::User => "●",
+        Role::Assistant => "◆",
+        Role::Function => "ƒ",
+    }
+}
+
+/// Cuts the text to the given number of characters, ending it with an ellipsis if cut
+fn truncate(text: &str, width: usize) -> String {
+    if text.chars().count() <= width {
+        return text.to_string();
+    }
+
+    let mut truncated: String = text.chars().take(width.saturating_sub(1)).collect();
+    truncated.push('…');
+    truncated
+}
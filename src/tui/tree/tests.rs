@@ -0,0 +1,67 @@
+use std::path::PathBuf;
+
+use crate::conversations::CompletionParametersBuilder;
+
+use super::*;
+
+/// Builds a conversation with two queries, the first one with two responses
+fn build_conversation() -> Conversation {
+    let parameters = CompletionParametersBuilder::default().build().expect("parameters");
+    let mut conversation = Conversation::build(parameters, PathBuf::from("tree.yaml"), "System")
+        .expect("build conversation");
+
+    let root = conversation.get_root_message().id();
+    let queries: Vec<Uuid> = conversation.add_queries(root, vec![String::from("Query 1"), String::from("Query 2")])
+        .expect("add queries")
+        .iter()
+        .map(|msg| msg.id())
+        .collect();
+    conversation.add_children_to_message(queries[0], vec![String::from("Answer 1a"), String::from("Answer\n1b")],
+                                         Role::Assistant)
+        .expect("add responses");
+
+    conversation
+}
+
+#[test]
+fn visible_rows() {
+    let conversation = build_conversation();
+    let mut panel = TreePanel::default();
+
+    let rows = panel.rows(&conversation);
+    let previews: Vec<(&str, usize)> = rows.iter().map(|row| (row.preview(), row.depth())).collect();
+    assert_eq!(previews, vec![("System", 0), ("Query 1", 1), ("Answer 1a", 2), ("Answer 1b", 2), ("Query 2", 1)]);
+    assert!(rows[1].has_children() && !rows[4].has_children());
+
+    // Collapsing hides the descendants, and moves to the parent afterwards
+    panel.select(rows[1].id());
+    panel.collapse(&conversation);
+    assert_eq!(panel.rows(&conversation).len(), 3);
+    assert!(panel.rows(&conversation)[1].is_collapsed());
+    panel.collapse(&conversation);
+    assert_eq!(panel.selected(), Some(rows[0].id()));
+
+    // Collapsing the root hides everything else
+    panel.toggle(&conversation);
+    assert_eq!(panel.rows(&conversation).len(), 1);
+    panel.toggle(&conversation);
+
+    // Expanding shows the children, and moves to the first one afterwards
+    panel.move_selection(&conversation, 1);
+    panel.expand(&conversation);
+    assert_eq!(panel.rows(&conversation).len(), 5);
+    panel.expand(&conversation);
+    assert_eq!(panel.selected(), Some(rows[2].id()));
+
+    // Movements are limited to the visible rows
+    panel.move_selection(&conversation, 100);
+    assert_eq!(panel.selected(), Some(rows[4].id()));
+    panel.select_edge(&conversation, false);
+    assert_eq!(panel.selected(), Some(rows[0].id()));
+}
+
+#[test]
+fn truncate_previews() {
+    assert_eq!(truncate("short", 10), "short");
+    assert_eq!(truncate("a longer text", 6), "a lon…");
+}
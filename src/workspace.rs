@@ -0,0 +1,259 @@
+use std::path::{Path, PathBuf};
+
+use tokio::fs;
+
+use crate::conversations::Conversation;
+use crate::encryption::{self, Secret};
+use crate::search::index::SearchIndex;
+use crate::{Result, RustGPTError};
+
+/// Module with tests related to workspaces
+#[cfg(test)]
+mod tests;
+
+/// Extensions of the files that are considered conversations within a workspace
+const CONVERSATION_EXTENSIONS: [&str; 2] = ["yaml", "yml"];
+
+/// Writes the data to the path through a temporary file, so a failure doesn't leave the file
+/// half written
+pub(crate) async fn write_atomically(path: &Path, data: &[u8]) -> Result<()> {
+    let mut temp_path = path.to_path_buf().into_os_string();
+    temp_path.push(".tmp");
+    fs::write(&temp_path, data).await?;
+    fs::rename(&temp_path, path).await?;
+
+    Ok(())
+}
+
+/// Represents a directory where conversations are stored, one file per conversation.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Workspace {
+    path: PathBuf,
+
+    /// Secret of the encrypted conversations, which new conversations are stored with too. The
+    /// one of the environment is used for loading them if not given.
+    secret: Option<Secret>,
+}
+
+impl Workspace {
+    /// Opens the workspace at the given directory, creating it if it doesn't exist yet.
+    ///
+    /// # Arguments
+    ///
+    /// * `path`: Directory of the workspace
+    ///
+    /// returns: Result<Workspace, RustGPTError>
+    pub async fn open<T>(path: T) -> Result<Self>
+    where
+        T: Into<PathBuf>
+    {
+        let path: PathBuf = path.into();
+        if !fs::try_exists(&path).await? {
+            fs::create_dir_all(&path).await
+                .map_err(|_| RustGPTError::Initialize(path.display().to_string()))?;
+        }
+
+        Ok(Workspace { path, secret: None })
+    }
+
+    /// Opens the workspace where the given conversation file is stored
+    pub async fn containing(conversation_path: &Path) -> Result<Self> {
+        match conversation_path.parent() {
+            Some(parent) if !parent.as_os_str().is_empty() => Self::open(parent).await,
+            _ => Self::open(".").await,
+        }
+    }
+
+    /// Returns the directory of the workspace
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    pub fn secret(&self) -> Option<&Secret> {
+        self.secret.as_ref()
+    }
+
+    /// Uses the secret for the encrypted conversations of the workspace
+    pub fn with_secret(mut self, secret: Option<Secret>) -> Self {
+        self.secret = secret;
+        self
+    }
+
+    /// Returns true if any of the conversations of the workspace is encrypted
+    pub async fn is_encrypted(&self) -> Result<bool> {
+        for path in self.conversation_paths().await? {
+            if encryption::is_encrypted_file(&path).await? {
+                return Ok(true);
+            }
+        }
+
+        Ok(false)
+    }
+
+    /// Checks that the secret decrypts the conversations of the workspace, by loading the first
+    /// encrypted one. Workspaces without encrypted conversations accept any secret.
+    pub async fn verify_secret(&self, secret: &Secret) -> Result<()> {
+        for path in self.conversation_paths().await? {
+            if encryption::is_encrypted_file(&path).await? {
+                Conversation::load_with_secret(path, Some(secret)).await?;
+                return Ok(());
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Loads the conversation stored at the given path, decrypting it with the secret of the
+    /// workspace if it has one, or with the one of the environment.
+    pub async fn load_conversation(&self, path: &Path) -> Result<Conversation> {
+        match &self.secret {
+            Some(secret) => Conversation::load_with_secret(path, Some(secret)).await,
+            None => Conversation::load(path).await,
+        }
+    }
+
+    /// Saves the conversation and updates the search index of the workspace, if it has one.
+    pub async fn save_conversation(&self, conversation: &Conversation) -> Result<()> {
+        conversation.save().await?;
+        self.index_conversations(&[conversation]).await
+    }
+
+    /// Deletes the conversation stored in the given path, removing it from the search index.
+    pub async fn delete_conversation(&self, path: &Path) -> Result<()> {
+        fs::remove_file(path).await?;
+
+        if let Some(mut index) = SearchIndex::load(&self.path).await? {
+            index.remove(path);
+            index.save().await?;
+        }
+
+        Ok(())
+    }
+
+    /// Updates the search index of the workspace with the given conversations, if the workspace
+    /// has an index. Used after saving several conversations at once.
+    pub async fn index_conversations(&self, conversations: &[&Conversation]) -> Result<()> {
+        if let Some(mut index) = SearchIndex::load(&self.path).await? {
+            for conversation in conversations {
+                index.update(conversation);
+            }
+            index.save().await?;
+        }
+
+        Ok(())
+    }
+
+    /// Creates the search index of the workspace from scratch, replacing the current one.
+    ///
+    /// returns: Result<SearchIndex, RustGPTError> New index
+    pub async fn rebuild_index(&self) -> Result<SearchIndex> {
+        let mut index = SearchIndex::build(&self.path);
+        for path in self.conversation_paths().await? {
+            // Encrypted conversations are never indexed, so they aren't decrypted either
+            let data = fs::read(&path).await?;
+            if encryption::is_encrypted(&data) {
+                continue;
+            }
+
+            index.update(&Conversation::from_data(path, &data, None)?);
+        }
+        index.save().await?;
+
+        Ok(index)
+    }
+
+    /// Returns the search index of the workspace, if it has one
+    pub async fn index(&self) -> Result<Option<SearchIndex>> {
+        SearchIndex::load(&self.path).await
+    }
+
+    /// Returns the paths of all the conversation files within the workspace, sorted by name.
+    pub async fn conversation_paths(&self) -> Result<Vec<PathBuf>> {
+        let mut paths = Vec::new();
+
+        let mut entries = fs::read_dir(&self.path).await?;
+        while let Some(entry) = entries.next_entry().await? {
+            let path = entry.path();
+            let is_conversation = path.extension()
+                .and_then(|ext| ext.to_str())
+                .map(|ext| CONVERSATION_EXTENSIONS.contains(&ext))
+                .unwrap_or(false);
+
+            if is_conversation && entry.file_type().await?.is_file() {
+                paths.push(path);
+            }
+        }
+
+        paths.sort();
+        Ok(paths)
+    }
+
+    /// Changes how every conversation in the workspace is encrypted. Use `None` as `current` when
+    /// the conversations are stored as plain text, and as `new` for decrypting them.
+    ///
+    /// # Arguments
+    ///
+    /// * `current`: Secret that is currently used by the encrypted conversations
+    /// * `new`: Secret used for storing the conversations from now on
+    ///
+    /// returns: Result<Vec<PathBuf, Global>, RustGPTError> Paths of the updated conversations
+    pub async fn reencrypt(&self, current: Option<&Secret>, new: Option<&Secret>) -> Result<Vec<PathBuf>> {
+        let paths = self.conversation_paths().await?;
+
+        // Load everything first, so a wrong secret doesn't leave the workspace half updated
+        let mut conversations = Vec::with_capacity(paths.len());
+        for path in paths {
+            conversations.push(Conversation::load_with_secret(path, current).await?);
+        }
+
+        for conversation in conversations.iter_mut() {
+            conversation.set_secret(new.cloned());
+            conversation.save().await?;
+        }
+        self.index_conversations(&conversations.iter().collect::<Vec<_>>()).await?;
+
+        Ok(conversations.iter()
+            .map(|c| c.path().to_path_buf())
+            .collect())
+    }
+
+    /// Returns a path within the workspace that is not used by any other file, based on the
+    /// given conversation name.
+    ///
+    /// # Arguments
+    ///
+    /// * `name`: Name of the conversation
+    ///
+    /// returns: Result<PathBuf, RustGPTError>
+    pub async fn new_conversation_path(&self, name: &str) -> Result<PathBuf> {
+        let stem = slugify(name);
+
+        let mut path = self.path.join(format!("{}.yaml", stem));
+        let mut n = 1;
+        while fs::try_exists(&path).await? {
+            n += 1;
+            path = self.path.join(format!("{}-{}.yaml", stem, n));
+        }
+
+        Ok(path)
+    }
+}
+
+/// Converts a name into something that can be safely used as a file name
+fn slugify(name: &str) -> String {
+    let mut slug = String::with_capacity(name.len());
+    for c in name.chars() {
+        if c.is_alphanumeric() {
+            slug.extend(c.to_lowercase());
+        } else if !slug.is_empty() && !slug.ends_with('-') {
+            slug.push('-');
+        }
+    }
+
+    let slug = slug.trim_end_matches('-');
+    if slug.is_empty() {
+        String::from("conversation")
+    } else {
+        slug.to_string()
+    }
+}
@@ -0,0 +1,93 @@
+use crate::conversations::CompletionParametersBuilder;
+use crate::encryption;
+use crate::test_util::TempDirectoryHandler;
+
+use super::*;
+
+#[test]
+fn slugify_names() {
+    assert_eq!(slugify("Hello World"), "hello-world");
+    assert_eq!(slugify("  Rust: lifetimes & borrows?  "), "rust-lifetimes-borrows");
+    assert_eq!(slugify("../etc/passwd"), "etc-passwd");
+    assert_eq!(slugify("???"), "conversation");
+}
+
+#[tokio::test]
+async fn workspace_paths() {
+    let temp_dir = TempDirectoryHandler::build().expect("temp dir");
+    let workspace = Workspace::open(temp_dir.path().join("workspace")).await
+        .expect("open workspace");
+    assert!(workspace.path().exists(), "Workspace directory should be created");
+
+    // New paths shouldn't collide with existing files
+    let first = workspace.new_conversation_path("My chat").await.expect("first path");
+    assert_eq!(first, workspace.path().join("my-chat.yaml"));
+    fs::write(&first, "").await.expect("write first");
+
+    let second = workspace.new_conversation_path("My chat").await.expect("second path");
+    assert_eq!(second, workspace.path().join("my-chat-2.yaml"));
+    fs::write(&second, "").await.expect("write second");
+
+    // Only conversation files are listed
+    fs::write(workspace.path().join("notes.txt"), "").await.expect("write notes");
+    let paths = workspace.conversation_paths().await.expect("list conversations");
+    assert_eq!(paths, vec![second, first]);
+}
+
+#[tokio::test]
+async fn workspace_encryption() {
+    let temp_dir = TempDirectoryHandler::build().expect("temp dir");
+    let workspace = Workspace::open(temp_dir.path()).await.expect("workspace");
+
+    let parameters = CompletionParametersBuilder::default().build()
+        .expect("parameters");
+    let path = workspace.new_conversation_path("Plain").await.expect("path");
+    Conversation::build(parameters, path.clone(), "System").expect("conversation")
+        .save().await.expect("save");
+
+    // Encrypt
+    let first = Secret::from_passphrase("first").expect("first secret");
+    let updated = workspace.reencrypt(None, Some(&first)).await.expect("encrypt");
+    assert_eq!(updated, vec![path.clone()]);
+    assert!(encryption::is_encrypted(&fs::read(&path).await.expect("read")));
+
+    // Rekey, a wrong secret doesn't change anything
+    let second = Secret::from_passphrase("second").expect("second secret");
+    assert!(workspace.reencrypt(Some(&second), None).await.is_err());
+    workspace.reencrypt(Some(&first), Some(&second)).await.expect("rekey");
+    assert!(Conversation::load_with_secret(&path, Some(&first)).await.is_err());
+
+    // Decrypt
+    workspace.reencrypt(Some(&second), None).await.expect("decrypt");
+    let conversation = Conversation::load_with_secret(&path, None).await.expect("load plain");
+    assert!(!conversation.is_encrypted());
+}
+
+#[tokio::test]
+async fn workspace_secret() {
+    let temp_dir = TempDirectoryHandler::build().expect("temp dir");
+    let workspace = Workspace::open(temp_dir.path()).await.expect("workspace");
+
+    let parameters = CompletionParametersBuilder::default().build()
+        .expect("parameters");
+    let path = workspace.new_conversation_path("Secret").await.expect("path");
+    Conversation::build(parameters, path.clone(), "System").expect("conversation")
+        .save().await.expect("save");
+
+    // Plain workspaces accept any secret
+    let secret = Secret::from_passphrase("secret").expect("secret");
+    let wrong = Secret::from_passphrase("wrong").expect("wrong secret");
+    assert!(!workspace.is_encrypted().await.expect("check plain"));
+    workspace.verify_secret(&wrong).await.expect("verify plain");
+
+    workspace.reencrypt(None, Some(&secret)).await.expect("encrypt");
+    assert!(workspace.is_encrypted().await.expect("check encrypted"));
+    assert!(encryption::is_encrypted_file(&path).await.expect("check file"));
+    assert!(workspace.verify_secret(&wrong).await.is_err());
+    workspace.verify_secret(&secret).await.expect("verify");
+
+    // Conversations are loaded with the secret of the workspace
+    let workspace = workspace.with_secret(Some(secret));
+    let conversation = workspace.load_conversation(&path).await.expect("load");
+    assert!(conversation.is_encrypted());
+}